@@ -1,10 +1,59 @@
 use std::{
+    cell::RefCell,
+    env::VarError,
     fs::create_dir_all,
     path::{Path, PathBuf},
 };
 
+use serde::{de::DeserializeOwned, Serialize};
+
+thread_local! {
+    static FOLDER_OVERRIDE: RefCell<Option<FolderOverride>> = RefCell::new(None);
+}
+
+/// Replaces the home and config folders for the current thread.
+///
+/// This lets tests point BoilR at a fake home folder and Steam installation
+/// instead of reading `HOME` and `XDG_CONFIG_HOME`.
+#[derive(Clone, Debug)]
+pub struct FolderOverride {
+    pub home: PathBuf,
+    pub config: PathBuf,
+}
+
+impl FolderOverride {
+    pub fn install(self) -> FolderOverrideGuard {
+        FOLDER_OVERRIDE.with(|o| *o.borrow_mut() = Some(self));
+        FolderOverrideGuard {}
+    }
+}
+
+/// Removes the folder override again when dropped
+pub struct FolderOverrideGuard {}
+
+impl Drop for FolderOverrideGuard {
+    fn drop(&mut self) {
+        FOLDER_OVERRIDE.with(|o| *o.borrow_mut() = None);
+    }
+}
+
+fn get_folder_override() -> Option<FolderOverride> {
+    FOLDER_OVERRIDE.with(|o| o.borrow().clone())
+}
+
+/// The users home folder, same as reading the `HOME` variable unless overridden
+pub fn get_home() -> Result<String, VarError> {
+    match get_folder_override() {
+        Some(folders) => Ok(folders.home.to_string_lossy().to_string()),
+        None => std::env::var("HOME"),
+    }
+}
+
 #[cfg(target_family = "unix")]
 pub fn get_config_folder() -> PathBuf {
+    if let Some(folders) = get_folder_override() {
+        return folders.config;
+    }
     let config_home = std::env::var("XDG_CONFIG_HOME");
     let home = std::env::var("HOME");
     match (config_home, home) {
@@ -16,6 +65,9 @@ pub fn get_config_folder() -> PathBuf {
 
 #[cfg(windows)]
 pub fn get_config_folder() -> PathBuf {
+    if let Some(folders) = get_folder_override() {
+        return folders.config;
+    }
     let config_home = std::env::var("APPDATA");
     match config_home {
         Ok(p) => Path::new(&p).join("boilr"),
@@ -41,6 +93,54 @@ pub fn get_cache_file() -> PathBuf {
     get_config_folder().join("cache.json")
 }
 
+pub fn get_review_file() -> PathBuf {
+    get_config_folder().join("needs_review.json")
+}
+
+pub fn get_external_ids_file() -> PathBuf {
+    get_config_folder().join("external_ids.json")
+}
+
+pub fn get_manual_picks_file() -> PathBuf {
+    get_config_folder().join("manual_picks.json")
+}
+
+pub fn get_collection_additions_file() -> PathBuf {
+    get_config_folder().join("collection_additions.json")
+}
+
+pub fn get_controller_templates_file() -> PathBuf {
+    get_config_folder().join("controller_templates.json")
+}
+
+pub fn get_image_ids_file() -> PathBuf {
+    get_config_folder().join("image_ids.json")
+}
+
+pub fn get_failed_downloads_file() -> PathBuf {
+    get_config_folder().join("failed_downloads.json")
+}
+
+/// Reads a json file from the config folder, a missing or broken file gives the default
+pub fn load_json_file<T: DeserializeOwned + Default>(path: &Path) -> T {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Writes a json file to the config folder, errors are only printed, `what` names the content in them
+pub fn save_json_file<T: Serialize + ?Sized>(path: &Path, value: &T, what: &str) {
+    match serde_json::to_string(value) {
+        Ok(content) => {
+            if let Err(e) = std::fs::write(path, content) {
+                eprintln!("Could not save {} to {:?}: {}", what, path, e);
+            }
+        }
+        Err(e) => eprintln!("Could not serialize {}: {}", what, e),
+    }
+}
+
 pub fn get_backups_flder() -> PathBuf {
     let backups_path = get_config_folder().join("backup");
     let _ = create_dir_all(&backups_path);
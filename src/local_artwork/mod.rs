@@ -0,0 +1,7 @@
+mod pack;
+mod provider;
+mod settings;
+
+pub use pack::{export_artwork_pack, import_artwork_pack, run_pack_command, PackImport};
+pub use provider::apply_local_artwork;
+pub use settings::LocalArtworkSettings;
@@ -0,0 +1,382 @@
+use std::{
+    collections::HashMap,
+    fs::{create_dir_all, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use steam_shortcuts_util::shortcut::ShortcutOwned;
+
+use crate::{
+    platforms::ExternalId,
+    settings::Settings,
+    steam::{get_shortcuts_for_user, get_shortcuts_paths, SteamUsersInfo},
+    steamgriddb::{
+        find_grid_image, load_external_ids, load_image_ids, remove_grid_images, save_image_ids,
+        ImageType, ToDownload,
+    },
+    sync::IsBoilRShortcut,
+};
+
+use super::provider::local_file_stem;
+
+const MANIFEST: &str = "manifest.json";
+
+const USAGE: &str = "Usage:
+  boilr --export-artwork <file.tar.gz>
+  boilr --import-artwork <file.tar.gz>";
+
+/// Describes the images in an artwork pack
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    images: Vec<PackedImage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PackedImage {
+    game_name: String,
+    external_id: Option<ExternalId>,
+    image_type: ImageType,
+    steamgriddb_image_id: Option<u32>,
+    /// Where the image is stored in the pack
+    file: String,
+}
+
+#[derive(Debug, Default)]
+pub struct PackImport {
+    pub placed: usize,
+    /// Games in the pack that none of the users have
+    pub unmatched: Vec<String>,
+}
+
+/// Writes the images of all BoilR shortcuts to a tar.gz file, returns how many images were exported
+pub fn export_artwork_pack(users: &[SteamUsersInfo], pack_path: &Path) -> io::Result<usize> {
+    let external_ids = load_external_ids();
+    let image_ids = load_image_ids();
+    let mut manifest = Manifest::default();
+    let mut builder = tar::Builder::new(GzEncoder::new(
+        File::create(pack_path)?,
+        Compression::default(),
+    ));
+    for user in users {
+        let grid_folder = Path::new(&user.steam_user_data_folder)
+            .join("config")
+            .join("grid");
+        let shortcut_info = get_shortcuts_for_user(user);
+        for shortcut in shortcut_info
+            .shortcuts
+            .iter()
+            .filter(|s| s.is_boilr_shortcut())
+        {
+            for image_type in ImageType::all() {
+                // The same shortcut is often there for several users, the first one wins
+                let already_packed = manifest.images.iter().any(|image| {
+                    image.game_name == shortcut.app_name && image.image_type == *image_type
+                });
+                if already_packed {
+                    continue;
+                }
+                let image_path = match find_grid_image(&grid_folder, shortcut.app_id, image_type) {
+                    Some(image_path) => image_path,
+                    None => continue,
+                };
+                let extension = image_path
+                    .extension()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                let file = format!(
+                    "images/{}/{}.{}",
+                    shortcut.app_id,
+                    local_file_stem(image_type),
+                    extension
+                );
+                // Links are followed, the pack has to contain the image itself
+                let content = std::fs::read(&image_path)?;
+                append_file(&mut builder, &file, &content)?;
+                manifest.images.push(PackedImage {
+                    game_name: shortcut.app_name.clone(),
+                    external_id: external_ids.get(&shortcut.app_id).cloned(),
+                    image_type: *image_type,
+                    steamgriddb_image_id: image_ids
+                        .get(&image_type.file_name_no_extension(shortcut.app_id))
+                        .copied(),
+                    file,
+                });
+            }
+        }
+    }
+    let manifest_content = serde_json::to_vec_pretty(&manifest)?;
+    append_file(&mut builder, MANIFEST, &manifest_content)?;
+    builder.into_inner()?.finish()?;
+    Ok(manifest.images.len())
+}
+
+fn append_file<W: io::Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    content: &[u8],
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, path, content)
+}
+
+/// Places the images of a pack on the BoilR shortcuts of every user.
+///
+/// Shortcuts get different app ids on different machines, so games are matched
+/// by their platform id first and by name if they have none.
+pub fn import_artwork_pack(users: &[SteamUsersInfo], pack_path: &Path) -> io::Result<PackImport> {
+    let (manifest, files) = read_pack(pack_path)?;
+    let external_ids = load_external_ids();
+    let mut result = PackImport::default();
+    let mut matched_games = vec![];
+    let mut imported = vec![];
+    for user in users {
+        let grid_folder = Path::new(&user.steam_user_data_folder)
+            .join("config")
+            .join("grid");
+        let shortcut_info = get_shortcuts_for_user(user);
+        let shortcuts: Vec<&ShortcutOwned> = shortcut_info
+            .shortcuts
+            .iter()
+            .filter(|s| s.is_boilr_shortcut())
+            .collect();
+        for image in &manifest.images {
+            let shortcut = match find_shortcut(&shortcuts, image, &external_ids) {
+                Some(shortcut) => shortcut,
+                None => continue,
+            };
+            let content = match files.get(&image.file) {
+                Some(content) => content,
+                None => {
+                    eprintln!("{} is missing from the artwork pack", image.file);
+                    continue;
+                }
+            };
+            let extension = Path::new(&image.file)
+                .extension()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            create_dir_all(&grid_folder)?;
+            remove_grid_images(&grid_folder, shortcut.app_id, &image.image_type)?;
+            let path = grid_folder.join(image.image_type.file_name(shortcut.app_id, extension));
+            std::fs::write(&path, content)?;
+            result.placed += 1;
+            matched_games.push(image.game_name.clone());
+            imported.push(ToDownload {
+                path,
+                url: String::new(),
+                app_name: shortcut.app_name.clone(),
+                image_type: image.image_type,
+                image_id: image.steamgriddb_image_id,
+            });
+        }
+    }
+    save_image_ids(&imported);
+    for image in &manifest.images {
+        if !matched_games.contains(&image.game_name) && !result.unmatched.contains(&image.game_name)
+        {
+            result.unmatched.push(image.game_name.clone());
+        }
+    }
+    Ok(result)
+}
+
+fn find_shortcut<'a>(
+    shortcuts: &[&'a ShortcutOwned],
+    image: &PackedImage,
+    external_ids: &HashMap<u32, ExternalId>,
+) -> Option<&'a ShortcutOwned> {
+    let by_id = image.external_id.as_ref().and_then(|external_id| {
+        shortcuts
+            .iter()
+            .find(|s| external_ids.get(&s.app_id) == Some(external_id))
+    });
+    let by_name = || {
+        shortcuts
+            .iter()
+            .find(|s| s.app_name.to_lowercase() == image.game_name.to_lowercase())
+    };
+    by_id.or_else(by_name).copied()
+}
+
+fn read_pack(pack_path: &Path) -> io::Result<(Manifest, HashMap<String, Vec<u8>>)> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(pack_path)?));
+    let mut files = HashMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().to_string();
+        let mut content = vec![];
+        entry.read_to_end(&mut content)?;
+        files.insert(path, content);
+    }
+    let manifest = match files.remove(MANIFEST) {
+        Some(content) => serde_json::from_slice(&content)?,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the artwork pack has no manifest",
+            ))
+        }
+    };
+    Ok((manifest, files))
+}
+
+/// Handles `boilr --export-artwork <file>` and `boilr --import-artwork <file>`
+pub fn run_pack_command(args: &[String]) {
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    let (export, pack_path) = match args.as_slice() {
+        ["--export-artwork", path, ..] => (true, PathBuf::from(path)),
+        ["--import-artwork", path, ..] => (false, PathBuf::from(path)),
+        _ => {
+            eprintln!("{}", USAGE);
+            return;
+        }
+    };
+    let users = match Settings::new()
+        .map_err(|e| e.to_string())
+        .and_then(|settings| get_shortcuts_paths(&settings.steam).map_err(|e| e.to_string()))
+    {
+        Ok(users) => users,
+        Err(e) => {
+            eprintln!("Could not find the Steam users: {}", e);
+            return;
+        }
+    };
+    if export {
+        match export_artwork_pack(&users, &pack_path) {
+            Ok(count) => println!("Exported {} images to {:?}", count, pack_path),
+            Err(e) => eprintln!("Could not export artwork to {:?}: {}", pack_path, e),
+        }
+    } else {
+        match import_artwork_pack(&users, &pack_path) {
+            Ok(result) => {
+                println!("Imported {} images from {:?}", result.placed, pack_path);
+                for name in result.unmatched {
+                    println!("No shortcut found for {}", name);
+                }
+            }
+            Err(e) => eprintln!("Could not import artwork from {:?}: {}", pack_path, e),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod tests {
+    use super::*;
+    use crate::platforms::ExternalPlatform;
+    use crate::steam::get_shortcuts_paths;
+    use crate::steamgriddb::save_external_ids;
+    use crate::sync::fake_steam::{shortcut, FakeSteam};
+
+    const PNG: &[u8] = include_bytes!("../testdata/smallpng.png");
+
+    fn boilr_shortcut(name: &str, exe: &str) -> ShortcutOwned {
+        let mut shortcut = shortcut(name, exe);
+        shortcut.tags.push(crate::sync::BOILR_TAG.to_string());
+        shortcut
+    }
+
+    fn grid_folder(steam: &FakeSteam) -> std::path::PathBuf {
+        steam.user_folder("100").join("config").join("grid")
+    }
+
+    #[test]
+    fn moves_artwork_to_other_app_ids() {
+        let source = FakeSteam::new("pack_export");
+        let renamed = boilr_shortcut("Gwent", "C:/gog/gwent.exe");
+        let same_name = boilr_shortcut("Celeste", "C:/games/celeste.exe");
+        source.add_user("100", Some(&[renamed.clone(), same_name.clone()][..]));
+        save_external_ids(HashMap::from([(
+            renamed.app_id,
+            ExternalId::new(ExternalPlatform::Gog, "1971477531"),
+        )]));
+        let grid = grid_folder(&source);
+        std::fs::create_dir_all(&grid).unwrap();
+        std::fs::write(
+            grid.join(ImageType::Hero.file_name(renamed.app_id, "png")),
+            PNG,
+        )
+        .unwrap();
+        std::fs::write(
+            grid.join(ImageType::Icon.file_name(same_name.app_id, "jpg")),
+            PNG,
+        )
+        .unwrap();
+        save_image_ids([&ToDownload {
+            path: grid.join(ImageType::Hero.file_name(renamed.app_id, "png")),
+            url: String::new(),
+            app_name: renamed.app_name.clone(),
+            image_type: ImageType::Hero,
+            image_id: Some(42),
+        }]);
+
+        let pack = source.root.join("pack.tar.gz");
+        let users = get_shortcuts_paths(&source.settings().steam).unwrap();
+        assert_eq!(export_artwork_pack(&users, &pack).unwrap(), 2);
+        let pack_content = std::fs::read(&pack).unwrap();
+        drop(source);
+
+        let target = FakeSteam::new("pack_import");
+        let target_renamed = boilr_shortcut("GWENT: The Witcher Card Game", "/gog/gwent");
+        let target_same_name = boilr_shortcut("celeste", "/games/celeste");
+        target.add_user(
+            "100",
+            Some(&[target_renamed.clone(), target_same_name.clone()][..]),
+        );
+        save_external_ids(HashMap::from([(
+            target_renamed.app_id,
+            ExternalId::new(ExternalPlatform::Gog, "1971477531"),
+        )]));
+        let pack = target.root.join("pack.tar.gz");
+        std::fs::write(&pack, pack_content).unwrap();
+
+        let users = get_shortcuts_paths(&target.settings().steam).unwrap();
+        let result = import_artwork_pack(&users, &pack).unwrap();
+
+        assert_eq!(result.placed, 2);
+        assert!(result.unmatched.is_empty());
+        let grid = grid_folder(&target);
+        let hero = grid.join(ImageType::Hero.file_name(target_renamed.app_id, "png"));
+        assert_eq!(std::fs::read(hero).unwrap(), PNG);
+        assert!(grid
+            .join(ImageType::Icon.file_name(target_same_name.app_id, "jpg"))
+            .exists());
+        let image_ids = load_image_ids();
+        assert_eq!(
+            image_ids.get(&ImageType::Hero.file_name_no_extension(target_renamed.app_id)),
+            Some(&42)
+        );
+    }
+
+    #[test]
+    fn reports_games_that_are_not_found() {
+        let source = FakeSteam::new("pack_unmatched_export");
+        let game = boilr_shortcut("Tunic", "/games/tunic");
+        source.add_user("100", Some(&[game.clone()][..]));
+        let grid = grid_folder(&source);
+        std::fs::create_dir_all(&grid).unwrap();
+        std::fs::write(
+            grid.join(ImageType::Grid.file_name(game.app_id, "png")),
+            PNG,
+        )
+        .unwrap();
+        let users = get_shortcuts_paths(&source.settings().steam).unwrap();
+        let pack = source.root.join("pack.tar.gz");
+        export_artwork_pack(&users, &pack).unwrap();
+
+        source.add_user(
+            "100",
+            Some(&[boilr_shortcut("Celeste", "/games/celeste")][..]),
+        );
+        let result = import_artwork_pack(&users, &pack).unwrap();
+        assert_eq!(result.placed, 0);
+        assert_eq!(result.unmatched, vec!["Tunic".to_string()]);
+    }
+}
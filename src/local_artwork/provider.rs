@@ -0,0 +1,294 @@
+use std::{
+    collections::HashMap,
+    fs::create_dir_all,
+    io,
+    path::{Path, PathBuf},
+};
+
+use steam_shortcuts_util::shortcut::ShortcutOwned;
+
+use crate::{
+    platforms::ExternalId,
+    steam::{get_shortcuts_for_user, SteamUsersInfo},
+    steamgriddb::{load_external_ids, remove_grid_images, ImageType, GRID_EXTENSIONS},
+};
+
+use super::LocalArtworkSettings;
+
+/// The name of an image inside a game folder, without the extension
+pub fn local_file_stem(image_type: &ImageType) -> &'static str {
+    match image_type {
+        ImageType::Hero => "hero",
+        ImageType::Grid => "grid",
+        ImageType::WideGrid => "widegrid",
+        ImageType::Logo => "logo",
+        ImageType::BigPicture => "bigpicture",
+        ImageType::Icon => "icon",
+    }
+}
+
+/// Places the images from the local artwork folder in the grid folder of every user.
+///
+/// This runs before anything is downloaded, so SteamGridDB only fills in the images that are not found here.
+/// Returns how many images were placed.
+pub fn apply_local_artwork(settings: &LocalArtworkSettings, users: &[SteamUsersInfo]) -> usize {
+    let root = match (settings.enabled, &settings.folder) {
+        (true, Some(folder)) => PathBuf::from(folder),
+        _ => return 0,
+    };
+    // Links have to point at an absolute path
+    let root = root.canonicalize().unwrap_or(root);
+    let game_folders = match index_game_folders(&root) {
+        Ok(game_folders) => game_folders,
+        Err(e) => {
+            eprintln!("Could not read local artwork folder {:?}: {}", root, e);
+            return 0;
+        }
+    };
+    let external_ids = load_external_ids();
+    let mut placed = 0;
+    for user in users {
+        let grid_folder = Path::new(&user.steam_user_data_folder)
+            .join("config")
+            .join("grid");
+        if let Err(e) = create_dir_all(&grid_folder) {
+            eprintln!("Could not create grid folder {:?}: {}", grid_folder, e);
+            continue;
+        }
+        let shortcut_info = get_shortcuts_for_user(user);
+        for shortcut in &shortcut_info.shortcuts {
+            let external_id = external_ids.get(&shortcut.app_id);
+            let game_folder = match find_game_folder(&game_folders, shortcut, external_id) {
+                Some(game_folder) => game_folder,
+                None => continue,
+            };
+            for image_type in ImageType::all() {
+                if let Some(source) = find_image(game_folder, image_type) {
+                    match place_image(
+                        &source,
+                        &grid_folder,
+                        shortcut.app_id,
+                        image_type,
+                        settings.link_images,
+                    ) {
+                        Ok(true) => placed += 1,
+                        Ok(false) => {}
+                        Err(e) => eprintln!("Could not use local image {:?}: {}", source, e),
+                    }
+                }
+            }
+        }
+    }
+    if placed > 0 {
+        println!("Placed {} images from the local artwork folder", placed);
+    }
+    placed
+}
+
+/// The game folders, by their lowercase name
+fn index_game_folders(root: &Path) -> io::Result<HashMap<String, PathBuf>> {
+    Ok(std::fs::read_dir(root)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            (name, entry.path())
+        })
+        .collect())
+}
+
+fn find_game_folder<'a>(
+    game_folders: &'a HashMap<String, PathBuf>,
+    shortcut: &ShortcutOwned,
+    external_id: Option<&ExternalId>,
+) -> Option<&'a PathBuf> {
+    let mut candidates = vec![
+        shortcut.app_name.clone(),
+        file_safe_name(&shortcut.app_name),
+        shortcut.app_id.to_string(),
+    ];
+    if let Some(external_id) = external_id {
+        candidates.push(format!(
+            "{}-{}",
+            external_id.platform.steamgriddb_name(),
+            external_id.id
+        ));
+        candidates.push(external_id.id.clone());
+    }
+    candidates
+        .iter()
+        .filter(|candidate| !candidate.is_empty())
+        .find_map(|candidate| game_folders.get(&candidate.to_lowercase()))
+}
+
+/// Game names often contain characters that can not be used in folder names
+fn file_safe_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*'))
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn find_image(game_folder: &Path, image_type: &ImageType) -> Option<PathBuf> {
+    let stem = local_file_stem(image_type);
+    GRID_EXTENSIONS
+        .iter()
+        .map(|extension| game_folder.join(format!("{}.{}", stem, extension)))
+        .find(|path| path.is_file())
+}
+
+/// Returns false if the image was already in place
+fn place_image(
+    source: &Path,
+    grid_folder: &Path,
+    app_id: u32,
+    image_type: &ImageType,
+    link: bool,
+) -> io::Result<bool> {
+    let extension = match source.extension().and_then(|e| e.to_str()) {
+        Some("jpeg") | Some("jpg") => "jpg",
+        Some(extension) => extension,
+        None => "png",
+    };
+    let target = grid_folder.join(image_type.file_name(app_id, extension));
+    if is_already_placed(source, &target, link) {
+        return Ok(false);
+    }
+    remove_grid_images(grid_folder, app_id, image_type)?;
+    if link {
+        link_image(source, &target)?;
+    } else {
+        std::fs::copy(source, &target)?;
+    }
+    Ok(true)
+}
+
+fn is_already_placed(source: &Path, target: &Path, link: bool) -> bool {
+    if link && cfg!(target_family = "unix") {
+        return std::fs::read_link(target)
+            .map(|linked| linked == source)
+            .unwrap_or(false);
+    }
+    match (std::fs::read(source), std::fs::read(target)) {
+        (Ok(source), Ok(target)) => source == target,
+        _ => false,
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn link_image(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(source, target)
+}
+
+#[cfg(not(target_family = "unix"))]
+fn link_image(source: &Path, target: &Path) -> io::Result<()> {
+    // Creating links needs admin rights on Windows
+    std::fs::copy(source, target).map(|_| ())
+}
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod tests {
+    use super::*;
+    use crate::platforms::ExternalPlatform;
+    use crate::steam::get_shortcuts_paths;
+    use crate::steamgriddb::save_external_ids;
+    use crate::sync::fake_steam::{shortcut, FakeSteam};
+
+    const PNG: &[u8] = include_bytes!("../testdata/smallpng.png");
+
+    fn settings(root: &Path, link_images: bool) -> LocalArtworkSettings {
+        LocalArtworkSettings {
+            enabled: true,
+            folder: Some(root.to_string_lossy().to_string()),
+            link_images,
+        }
+    }
+
+    #[test]
+    fn places_images_by_name_and_platform_id() {
+        let steam = FakeSteam::new("local_artwork");
+        let by_name = shortcut("Hollow Knight: Voidheart", "/games/hollow");
+        let by_id = shortcut("Some Renamed Game", "/games/renamed");
+        steam.add_user("100", Some(&[by_name.clone(), by_id.clone()][..]));
+        save_external_ids(HashMap::from([(
+            by_id.app_id,
+            ExternalId::new(ExternalPlatform::Gog, "1207658924"),
+        )]));
+
+        let root = steam.root.join("artwork");
+        let name_folder = root.join("Hollow Knight Voidheart");
+        let id_folder = root.join("gog-1207658924");
+        std::fs::create_dir_all(&name_folder).unwrap();
+        std::fs::create_dir_all(&id_folder).unwrap();
+        std::fs::write(name_folder.join("grid.png"), PNG).unwrap();
+        std::fs::write(name_folder.join("hero.jpeg"), PNG).unwrap();
+        std::fs::write(id_folder.join("logo.png"), PNG).unwrap();
+
+        let grid_folder = steam.user_folder("100").join("config").join("grid");
+        std::fs::create_dir_all(&grid_folder).unwrap();
+        let old_grid = grid_folder.join(ImageType::Grid.file_name(by_name.app_id, "webp"));
+        std::fs::write(&old_grid, "old").unwrap();
+
+        let users = get_shortcuts_paths(&steam.settings().steam).unwrap();
+        assert_eq!(apply_local_artwork(&settings(&root, false), &users), 3);
+
+        let grid = grid_folder.join(ImageType::Grid.file_name(by_name.app_id, "png"));
+        assert_eq!(std::fs::read(grid).unwrap(), PNG);
+        assert!(!old_grid.exists());
+        assert!(grid_folder
+            .join(ImageType::Hero.file_name(by_name.app_id, "jpg"))
+            .exists());
+        assert!(grid_folder
+            .join(ImageType::Logo.file_name(by_id.app_id, "png"))
+            .exists());
+
+        // Nothing changes on the next run
+        assert_eq!(apply_local_artwork(&settings(&root, false), &users), 0);
+    }
+
+    #[test]
+    fn links_images() {
+        let steam = FakeSteam::new("local_artwork_links");
+        let game = shortcut("Celeste", "/games/celeste");
+        steam.add_user("100", Some(&[game.clone()][..]));
+        let root = steam.root.join("artwork");
+        std::fs::create_dir_all(root.join("celeste")).unwrap();
+        std::fs::write(root.join("celeste").join("icon.png"), PNG).unwrap();
+
+        let users = get_shortcuts_paths(&steam.settings().steam).unwrap();
+        assert_eq!(apply_local_artwork(&settings(&root, true), &users), 1);
+        assert_eq!(apply_local_artwork(&settings(&root, true), &users), 0);
+
+        let icon = steam
+            .user_folder("100")
+            .join("config")
+            .join("grid")
+            .join(ImageType::Icon.file_name(game.app_id, "png"));
+        let linked = std::fs::read_link(icon).unwrap();
+        assert_eq!(
+            linked,
+            root.canonicalize()
+                .unwrap()
+                .join("celeste")
+                .join("icon.png")
+        );
+    }
+
+    #[test]
+    fn does_nothing_when_disabled() {
+        let steam = FakeSteam::new("local_artwork_disabled");
+        steam.add_user("100", Some(&[shortcut("Celeste", "/games/celeste")][..]));
+        let root = steam.root.join("artwork");
+        std::fs::create_dir_all(root.join("Celeste")).unwrap();
+        std::fs::write(root.join("Celeste").join("icon.png"), PNG).unwrap();
+
+        let users = get_shortcuts_paths(&steam.settings().steam).unwrap();
+        let settings = LocalArtworkSettings {
+            enabled: false,
+            ..settings(&root, false)
+        };
+        assert_eq!(apply_local_artwork(&settings, &users), 0);
+    }
+}
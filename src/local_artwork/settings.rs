@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LocalArtworkSettings {
+    pub enabled: bool,
+    /// Has a folder per game, named after the game or its platform id
+    pub folder: Option<String>,
+    /// Link to the images instead of copying them, only used on Linux
+    pub link_images: bool,
+}
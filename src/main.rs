@@ -1,4 +1,5 @@
 mod config;
+mod local_artwork;
 mod migration;
 mod platforms;
 mod settings;
@@ -15,8 +16,17 @@ fn main() -> Result<()> {
     migration::migrate_config();
 
     let args: Vec<String> = std::env::args().collect();
-    if args.contains(&"--no-ui".to_string()) {
-        ui::run_sync();
+    if let Some(index) = args.iter().position(|arg| arg == "--cache") {
+        steamgriddb::run_cache_command(&args[index + 1..]);
+    } else if let Some(index) = args
+        .iter()
+        .position(|arg| arg == "--export-artwork" || arg == "--import-artwork")
+    {
+        local_artwork::run_pack_command(&args[index..]);
+    } else if let Some(index) = args.iter().position(|arg| arg == "--remove-everything") {
+        sync::run_cleanup_command(&args[index..]);
+    } else if args.contains(&"--no-ui".to_string()) {
+        ui::run_sync(args);
     } else {
         ui::run_ui(args);
     }
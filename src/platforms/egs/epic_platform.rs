@@ -1,5 +1,6 @@
 use crate::platforms::{
-    load_settings, to_shortcuts, FromSettingsString, GamesPlatform, NeedsPorton, ShortcutToImport,
+    load_settings, to_shortcuts, ExternalId, ExternalPlatform, FromSettingsString, GamesPlatform,
+    NeedsPorton, ShortcutToImport,
 };
 
 use super::{get_egs_manifests, settings::EpicGamesLauncherSettings, ManifestItem};
@@ -30,6 +31,13 @@ impl NeedsPorton<EpicPlatform> for ManifestItem {
     fn create_symlinks(&self, _platform: &EpicPlatform) -> bool {
         false
     }
+
+    fn external_id(&self) -> Option<ExternalId> {
+        Some(ExternalId::new(
+            ExternalPlatform::Egs,
+            &self.catalog_item_id,
+        ))
+    }
 }
 
 impl GamesPlatform for EpicPlatform {
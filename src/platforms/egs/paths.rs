@@ -24,7 +24,7 @@ mod unix {
     use std::path::Path;
 
     pub fn get_locations() -> Option<EpicPaths> {
-        if let Ok(home) = std::env::var("HOME") {
+        if let Ok(home) = crate::config::get_home() {
             let compat_folder_path = Path::new(&home)
                 .join(".steam")
                 .join("steam")
@@ -1,7 +1,8 @@
 use std::path::{Path, PathBuf};
 
 use crate::platforms::{
-    load_settings, to_shortcuts, FromSettingsString, GamesPlatform, NeedsPorton, ShortcutToImport,
+    load_settings, to_shortcuts, ExternalId, ExternalPlatform, FromSettingsString, GamesPlatform,
+    NeedsPorton, ShortcutToImport,
 };
 
 use super::{
@@ -183,6 +184,10 @@ impl NeedsPorton<GogPlatform> for GogShortcut {
     fn create_symlinks(&self, platform: &GogPlatform) -> bool {
         platform.settings.create_symlinks
     }
+
+    fn external_id(&self) -> Option<ExternalId> {
+        Some(ExternalId::new(ExternalPlatform::Gog, &self.game_id))
+    }
 }
 
 #[cfg(target_family = "unix")]
@@ -219,7 +224,7 @@ pub fn default_location() -> PathBuf {
     }
     #[cfg(target_family = "unix")]
     {
-        let home = std::env::var("HOME").expect("Expected a home variable to be defined");
+        let home = crate::config::get_home().expect("Expected a home variable to be defined");
         Path::new(&home).join("Games/gog-galaxy/drive_c/ProgramData/GOG.com/Galaxy")
     }
 }
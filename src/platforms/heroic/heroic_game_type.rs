@@ -44,8 +44,17 @@ impl HeroicGameType {
 impl From<HeroicGameType> for ShortcutOwned {
     fn from(heroic_game_type: HeroicGameType) -> Self {
         match heroic_game_type {
-            HeroicGameType::Epic(epic) => epic.into(),
-            HeroicGameType::Gog(gog, _) => gog.into(),
+            HeroicGameType::Epic(epic) => {
+                let mut owned_shortcut: ShortcutOwned = epic.into();
+                owned_shortcut.tags.push("Heroic Epic".to_owned());
+                owned_shortcut
+            }
+            HeroicGameType::Gog(gog, _) => {
+                let mut owned_shortcut: ShortcutOwned = gog.into();
+                owned_shortcut.tags.push("Heroic".to_owned());
+                owned_shortcut.tags.push("Heroic GOG".to_owned());
+                owned_shortcut
+            }
             HeroicGameType::Heroic {
                 title,
                 app_name,
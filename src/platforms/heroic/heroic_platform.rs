@@ -2,7 +2,7 @@ use serde::Deserialize;
 
 use super::{HeroicGame, HeroicGameType, HeroicSettings};
 use crate::platforms::{load_settings, FromSettingsString, GamesPlatform};
-use crate::platforms::{to_shortcuts, NeedsPorton, ShortcutToImport};
+use crate::platforms::{to_shortcuts, ExternalId, ExternalPlatform, NeedsPorton, ShortcutToImport};
 use std::collections::HashMap;
 use std::path::Path;
 
@@ -34,7 +34,7 @@ struct HeroicGogPath {
 }
 
 fn get_installed_json_location(install_mode: &InstallationMode) -> PathBuf {
-    let home_dir = std::env::var("HOME").unwrap_or_else(|_| "".to_string());
+    let home_dir = crate::config::get_home().unwrap_or_else(|_| "".to_string());
     match install_mode {
         InstallationMode::FlatPak => Path::new(&home_dir)
             .join(".var/app/com.heroicgameslauncher.hgl/config/legendary/installed.json"),
@@ -43,7 +43,7 @@ fn get_installed_json_location(install_mode: &InstallationMode) -> PathBuf {
 }
 
 fn get_gog_installed_location(install_mode: &InstallationMode) -> PathBuf {
-    let home_dir = std::env::var("HOME").unwrap_or_else(|_| "".to_string());
+    let home_dir = crate::config::get_home().unwrap_or_else(|_| "".to_string());
     match install_mode {
         InstallationMode::FlatPak => Path::new(&home_dir)
             .join(".var/app/com.heroicgameslauncher.hgl/config/heroic/gog_store/installed.json"),
@@ -104,6 +104,18 @@ impl NeedsPorton<HeroicPlatform> for HeroicGameType {
     fn create_symlinks(&self, _platform: &HeroicPlatform) -> bool {
         false
     }
+
+    fn external_id(&self) -> Option<ExternalId> {
+        match self {
+            HeroicGameType::Epic(game) => {
+                Some(ExternalId::new(ExternalPlatform::Egs, &game.app_name))
+            }
+            HeroicGameType::Gog(shortcut, _) => {
+                Some(ExternalId::new(ExternalPlatform::Gog, &shortcut.game_id))
+            }
+            HeroicGameType::Heroic { .. } => None,
+        }
+    }
 }
 
 impl HeroicPlatform {
@@ -76,7 +76,7 @@ fn dbpath_to_game(paths: &DbPaths) -> Option<ItchGame> {
 #[cfg(target_family = "unix")]
 pub fn get_default_location() -> String {
     //If we don't have a home drive we have to just die
-    let home = std::env::var("HOME").expect("Expected a home variable to be defined");
+    let home = crate::config::get_home().expect("Expected a home variable to be defined");
     format!("{}/.config/itch/", home)
 }
 
@@ -18,7 +18,7 @@ impl From<LutrisGame> for ShortcutOwned {
     fn from(game: LutrisGame) -> Self {
         let options = game.get_options();
         let exectuable = game.get_executable();
-        Shortcut::new(
+        let mut owned_shortcut = Shortcut::new(
             "0",
             game.name.as_str(),
             exectuable.as_str(),
@@ -27,7 +27,13 @@ impl From<LutrisGame> for ShortcutOwned {
             "",
             options.as_str(),
         )
-        .to_owned()
+        .to_owned();
+        owned_shortcut.tags.push("Lutris".to_owned());
+        // The runner, like wine or dosbox, so collections can be made per runner
+        if !game.runner.is_empty() {
+            owned_shortcut.tags.push(game.runner);
+        }
+        owned_shortcut
     }
 }
 
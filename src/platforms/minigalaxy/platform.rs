@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::platforms::{GamesPlatform, FromSettingsString, load_settings, GogShortcut, NeedsPorton};
+use crate::platforms::{GamesPlatform, FromSettingsString, load_settings, GogShortcut, NeedsPorton, ExternalId, ExternalPlatform};
 
 #[derive(Clone)]
 pub struct MiniGalaxyPlatform {
@@ -47,6 +47,10 @@ impl NeedsPorton<MiniGalaxyPlatform> for GogShortcut{
     fn create_symlinks(&self, platform: &MiniGalaxyPlatform) -> bool {
         platform.settings.create_symlinks
     }
+
+    fn external_id(&self) -> Option<ExternalId> {
+        Some(ExternalId::new(ExternalPlatform::Gog, &self.game_id))
+    }
 }
 
 impl FromSettingsString for MiniGalaxyPlatform {
@@ -117,6 +121,6 @@ fn get_default_folder_string() -> String {
 }
 
 fn get_default_folder_path() -> eyre::Result<std::path::PathBuf> {
-    let home = std::env::var("HOME")?;
+    let home = crate::config::get_home()?;
     Ok(std::path::Path::new(&home).join("GOG Games"))
 }
\ No newline at end of file
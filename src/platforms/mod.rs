@@ -18,6 +18,8 @@ pub(crate) use platform::*;
 pub(crate) use gog::get_gog_shortcuts_from_game_folders;
 pub(crate) use gog::GogShortcut;
 pub use platforms_load::get_platforms;
+#[cfg(test)]
+pub(crate) use platforms_load::load_platform;
 pub(crate) use platforms_load::load_settings;
 pub(crate) use platforms_load::FromSettingsString;
 pub use platforms_load::Platforms;
@@ -1,5 +1,6 @@
 use crate::platforms::{
-    load_settings, to_shortcuts, FromSettingsString, GamesPlatform, NeedsPorton, ShortcutToImport,
+    load_settings, to_shortcuts, ExternalId, ExternalPlatform, FromSettingsString, GamesPlatform,
+    NeedsPorton, ShortcutToImport,
 };
 use nom::bytes::complete::take_until;
 use std::{
@@ -28,6 +29,10 @@ impl NeedsPorton<OriginPlatform> for OriginGame {
     fn create_symlinks(&self, _platform: &OriginPlatform) -> bool {
         false
     }
+
+    fn external_id(&self) -> Option<ExternalId> {
+        Some(ExternalId::new(ExternalPlatform::Origin, &self.id))
+    }
 }
 
 impl OriginPlatform {
@@ -104,7 +109,7 @@ struct OriginPathData {
 #[cfg(target_family = "unix")]
 fn get_default_locations() -> Option<OriginPathData> {
     let mut res = OriginPathData::default();
-    if let Ok(home) = std::env::var("HOME") {
+    if let Ok(home) = crate::config::get_home() {
         let compat_folder_path = Path::new(&home)
             .join(".steam")
             .join("steam")
@@ -1,4 +1,5 @@
 use dyn_clone::DynClone;
+use serde::{Deserialize, Serialize};
 use steam_shortcuts_util::shortcut::ShortcutOwned;
 
 pub trait GamesPlatform
@@ -27,6 +28,45 @@ pub struct ShortcutToImport {
     pub shortcut: ShortcutOwned,
     pub needs_proton: bool,
     pub needs_symlinks: bool,
+    pub external_id: Option<ExternalId>,
+}
+
+/// The stores SteamGridDB can look games up by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExternalPlatform {
+    Steam,
+    Egs,
+    Gog,
+    Origin,
+    Uplay,
+}
+
+impl ExternalPlatform {
+    pub fn steamgriddb_name(&self) -> &str {
+        match self {
+            ExternalPlatform::Steam => "steam",
+            ExternalPlatform::Egs => "egs",
+            ExternalPlatform::Gog => "gog",
+            ExternalPlatform::Origin => "origin",
+            ExternalPlatform::Uplay => "uplay",
+        }
+    }
+}
+
+/// The id a platform knows a game by
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalId {
+    pub platform: ExternalPlatform,
+    pub id: String,
+}
+
+impl ExternalId {
+    pub fn new<S: Into<String>>(platform: ExternalPlatform, id: S) -> Self {
+        ExternalId {
+            platform,
+            id: id.into(),
+        }
+    }
 }
 
 pub(crate) fn to_shortcuts<T, P>(
@@ -42,11 +82,13 @@ where
     for m in shortcuts {
         let needs_proton = m.needs_proton(platform);
         let needs_symlinks = m.create_symlinks(platform);
+        let external_id = m.external_id();
         let shortcut = m.into();
         shortcut_info.push(ShortcutToImport {
             shortcut,
             needs_proton,
             needs_symlinks,
+            external_id,
         });
     }
     Ok(shortcut_info)
@@ -68,6 +110,7 @@ where
             shortcut,
             needs_proton,
             needs_symlinks,
+            external_id: None,
         });
     }
     Ok(shortcut_info)
@@ -77,4 +120,9 @@ pub trait NeedsPorton<P> {
     fn needs_proton(&self, platform: &P) -> bool;
 
     fn create_symlinks(&self, platform: &P) -> bool;
+
+    /// The id the platform knows the game by, used to find its images
+    fn external_id(&self) -> Option<ExternalId> {
+        None
+    }
 }
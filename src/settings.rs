@@ -1,6 +1,6 @@
 use crate::{
-    config::get_config_file, platforms::Platforms, steam::SteamSettings,
-    steamgriddb::SteamGridDbSettings,
+    config::get_config_file, local_artwork::LocalArtworkSettings, platforms::Platforms,
+    steam::SteamSettings, steamgriddb::SteamGridDbSettings,
 };
 
 use config::{Config, ConfigError, Environment, File};
@@ -12,8 +12,13 @@ pub struct Settings {
     pub debug: bool,
     pub config_version: Option<usize>,
     pub blacklisted_games: Vec<u32>,
+    /// Games that are imported but not shown in the library by default
+    #[serde(default)]
+    pub hidden_games: Vec<u32>,
     pub steamgrid_db: SteamGridDbSettings,
     pub steam: SteamSettings,
+    #[serde(default)]
+    pub local_artwork: LocalArtworkSettings,
 }
 
 impl Settings {
@@ -62,7 +67,7 @@ pub fn load_setting_sections() -> eyre::Result<HashMap<String, String>> {
     }
     add_sections(&current_section_name, &current_section_lines, &mut result);
 
-    let blacklisted_sections = ["steamgrid_db", "steam"];
+    let blacklisted_sections = ["steamgrid_db", "steam", "local_artwork"];
     for section in blacklisted_sections {
         let _ = result.remove(section);
     }
@@ -70,6 +75,8 @@ pub fn load_setting_sections() -> eyre::Result<HashMap<String, String>> {
 }
 
 pub fn save_settings(settings: &Settings, platforms: &Platforms) {
+    // toml can not write a plain value after a table,
+    // so the settings structs keep their fields that are tables or lists of tables last
     let mut toml = toml::to_string(&settings).unwrap();
 
     for platform in platforms {
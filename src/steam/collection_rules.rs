@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+use steam_shortcuts_util::shortcut::ShortcutOwned;
+
+use super::Collection;
+
+const PLATFORM_PLACEHOLDER: &str = "{platform}";
+
+/// Decides which collections BoilR writes, by default there is one collection per platform
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CollectionRules {
+    /// The name of a platform collection, {platform} is replaced with the name of the platform
+    pub name_template: String,
+    /// Platforms that share one collection instead of getting one each
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub merged_platforms: Vec<MergedPlatforms>,
+    /// Extra collections with every game that has one of the tags
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tag_collections: Vec<TagCollection>,
+    /// Extra collections with the games listed by the name they have in Steam, after renames
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub custom_collections: Vec<CustomCollection>,
+    /// Collections made in Steam, like Favorites, that imported games are added to
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub existing_collections: Vec<ExistingCollection>,
+}
+
+impl Default for CollectionRules {
+    fn default() -> Self {
+        Self {
+            name_template: PLATFORM_PLACEHOLDER.to_string(),
+            merged_platforms: vec![],
+            tag_collections: vec![],
+            custom_collections: vec![],
+            existing_collections: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MergedPlatforms {
+    /// Used in place of the platform names in the name template
+    pub name: String,
+    pub platforms: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagCollection {
+    pub name: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomCollection {
+    pub name: String,
+    pub games: Vec<String>,
+}
+
+/// A collection that was not made by BoilR, found by its name.
+/// Games are added if they come from one of the platforms, have one of the tags or are listed by name.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExistingCollection {
+    pub name: String,
+    #[serde(default)]
+    pub platforms: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub games: Vec<String>,
+}
+
+fn has_any_tag(shortcut: &ShortcutOwned, tags: &[String]) -> bool {
+    shortcut
+        .tags
+        .iter()
+        .any(|tag| tags.iter().any(|wanted| wanted.eq_ignore_ascii_case(tag)))
+}
+
+fn is_listed(shortcut: &ShortcutOwned, games: &[String]) -> bool {
+    lists_game(games, &shortcut.app_name)
+}
+
+fn lists_game(games: &[String], name: &str) -> bool {
+    games
+        .iter()
+        .any(|game| game.trim().eq_ignore_ascii_case(name.trim()))
+}
+
+impl CollectionRules {
+    fn platform_collection_name(&self, platform: &str) -> String {
+        let group = self.merged_platforms.iter().find(|group| {
+            group
+                .platforms
+                .iter()
+                .any(|p| p.eq_ignore_ascii_case(platform))
+        });
+        let platform = group.map(|group| group.name.as_str()).unwrap_or(platform);
+        if self.name_template.trim().is_empty() {
+            return platform.to_string();
+        }
+        self.name_template.replace(PLATFORM_PLACEHOLDER, platform)
+    }
+
+    /// The names of the custom collections that list the game
+    pub fn game_collections(&self, game: &str) -> Vec<String> {
+        self.custom_collections
+            .iter()
+            .filter(|c| lists_game(&c.games, game))
+            .map(|c| c.name.clone())
+            .collect()
+    }
+
+    /// Lists the game in exactly the given custom collections.
+    /// Collections that do not exist yet are added, and collections that end up empty are removed.
+    pub fn set_game_collections(&mut self, game: &str, collections: &[String]) {
+        for custom_collection in &mut self.custom_collections {
+            custom_collection
+                .games
+                .retain(|listed| !listed.trim().eq_ignore_ascii_case(game.trim()));
+        }
+        for name in collections {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            match self
+                .custom_collections
+                .iter_mut()
+                .find(|c| c.name.eq_ignore_ascii_case(name))
+            {
+                Some(custom_collection) => custom_collection.games.push(game.to_string()),
+                None => self.custom_collections.push(CustomCollection {
+                    name: name.to_string(),
+                    games: vec![game.to_string()],
+                }),
+            }
+        }
+        self.custom_collections.retain(|c| !c.games.is_empty());
+    }
+
+    /// Lays out the collections for the imported games.
+    /// Collections that end up with the same name are merged, and empty collections are left out.
+    pub fn layout_collections(
+        &self,
+        platform_results: &[(String, Vec<ShortcutOwned>)],
+    ) -> Vec<Collection> {
+        let mut collections: Vec<Collection> = vec![];
+        let mut add = |name: String, game_ids: Vec<usize>| {
+            if game_ids.is_empty() {
+                return;
+            }
+            match collections.iter_mut().find(|c| c.name == name) {
+                Some(collection) => {
+                    for id in game_ids {
+                        if !collection.game_ids.contains(&id) {
+                            collection.game_ids.push(id);
+                        }
+                    }
+                }
+                None => collections.push(Collection { name, game_ids }),
+            }
+        };
+
+        for (platform, shortcuts) in platform_results {
+            let game_ids = shortcuts.iter().map(|s| s.app_id as usize).collect();
+            add(self.platform_collection_name(platform), game_ids);
+        }
+
+        let all_shortcuts = || platform_results.iter().flat_map(|(_, shortcuts)| shortcuts);
+        for tag_collection in &self.tag_collections {
+            let game_ids = all_shortcuts()
+                .filter(|s| has_any_tag(s, &tag_collection.tags))
+                .map(|s| s.app_id as usize)
+                .collect();
+            add(tag_collection.name.clone(), game_ids);
+        }
+        for custom_collection in &self.custom_collections {
+            let game_ids = all_shortcuts()
+                .filter(|s| is_listed(s, &custom_collection.games))
+                .map(|s| s.app_id as usize)
+                .collect();
+            add(custom_collection.name.clone(), game_ids);
+        }
+        collections
+    }
+
+    /// The games to add to collections that were made in Steam.
+    /// Every rule gives a collection, also when it is empty,
+    /// so games added by an earlier import can be taken out again.
+    pub fn existing_collection_additions(
+        &self,
+        platform_results: &[(String, Vec<ShortcutOwned>)],
+    ) -> Vec<Collection> {
+        self.existing_collections
+            .iter()
+            .map(|existing| {
+                let game_ids = platform_results
+                    .iter()
+                    .flat_map(|(platform, shortcuts)| {
+                        let whole_platform = existing
+                            .platforms
+                            .iter()
+                            .any(|p| p.eq_ignore_ascii_case(platform));
+                        shortcuts.iter().filter(move |s| {
+                            whole_platform
+                                || has_any_tag(s, &existing.tags)
+                                || is_listed(s, &existing.games)
+                        })
+                    })
+                    .map(|s| s.app_id as usize)
+                    .collect();
+                Collection {
+                    name: existing.name.clone(),
+                    game_ids,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use steam_shortcuts_util::Shortcut;
+
+    fn shortcut(name: &str, tags: &[&str]) -> ShortcutOwned {
+        let mut shortcut = Shortcut::new("0", name, name, "", "", "", "").to_owned();
+        shortcut.tags = tags.iter().map(|t| t.to_string()).collect();
+        shortcut
+    }
+
+    fn layout(rules: &CollectionRules) -> Vec<(String, Vec<usize>)> {
+        let results = vec![
+            (
+                "Epic".to_string(),
+                vec![shortcut("Fortnite", &["EGS", "Installed"])],
+            ),
+            (
+                "Legendary".to_string(),
+                vec![shortcut("Celeste", &["Legendary", "Installed"])],
+            ),
+            ("Lutris".to_string(), vec![shortcut("Doom", &["wine"])]),
+        ];
+        rules
+            .layout_collections(&results)
+            .into_iter()
+            .map(|c| (c.name, c.game_ids))
+            .collect()
+    }
+
+    #[test]
+    fn default_rules_make_one_collection_per_platform() {
+        let names: Vec<String> = layout(&CollectionRules::default())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["Epic", "Legendary", "Lutris"]);
+    }
+
+    #[test]
+    fn applies_templates_merges_tags_and_custom_collections() {
+        let rules = CollectionRules {
+            name_template: "BoilR: {platform}".to_string(),
+            merged_platforms: vec![MergedPlatforms {
+                name: "Epic".to_string(),
+                platforms: vec!["epic".to_string(), "Legendary".to_string()],
+            }],
+            tag_collections: vec![TagCollection {
+                name: "Installed".to_string(),
+                tags: vec!["installed".to_string()],
+            }],
+            custom_collections: vec![CustomCollection {
+                name: "Favourites".to_string(),
+                games: vec!["doom".to_string(), "Not Installed".to_string()],
+            }],
+            existing_collections: vec![],
+        };
+        let collections = layout(&rules);
+        let names: Vec<&str> = collections.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["BoilR: Epic", "BoilR: Lutris", "Installed", "Favourites"]
+        );
+        assert_eq!(collections[0].1.len(), 2);
+        assert_eq!(collections[2].1, collections[0].1);
+        assert_eq!(collections[3].1, collections[1].1);
+    }
+
+    #[test]
+    fn sets_the_custom_collections_of_a_game() {
+        let mut rules = CollectionRules::default();
+        rules.set_game_collections("Doom", &["Shooters".to_string(), "Retro".to_string()]);
+        rules.set_game_collections("Quake", &["shooters".to_string()]);
+        assert_eq!(rules.game_collections("doom"), vec!["Shooters", "Retro"]);
+        assert_eq!(rules.custom_collections[0].games, vec!["Doom", "Quake"]);
+
+        rules.set_game_collections("Doom", &["Shooters".to_string()]);
+        assert_eq!(rules.game_collections("Doom"), vec!["Shooters"]);
+        assert_eq!(rules.custom_collections.len(), 1);
+    }
+}
@@ -3,11 +3,22 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use rusty_leveldb::{LdbIterator, Options, WriteBatch, DB};
 
+use crate::config::{get_collection_additions_file, load_json_file, save_json_file};
+
+use super::{get_steam_path, SteamSettings};
+
 const BOILR_TAG: &str = "boilr";
+/// The collections Steam makes itself, by id and the name shown in the client
+const STEAM_COLLECTIONS: [(&str, &str); 2] = [("favorite", "Favorites"), ("hidden", "Hidden")];
+/// The name of the collection of hidden games, shortcuts in it are not shown in the library
+pub const HIDDEN_COLLECTION: &str = "Hidden";
+
+static SNAPSHOT_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
@@ -46,11 +57,7 @@ impl ActualSteamCollection {
         let name = name.as_ref();
         let key = format!("user-collections.{}", name_to_key(name));
         let value = serialize_collection_value(name, ids);
-        let start = SystemTime::now();
-        let since_the_epoch = start
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards");
-        let timestamp = since_the_epoch.as_secs();
+        let timestamp = now_timestamp();
 
         ActualSteamCollection {
             key,
@@ -62,10 +69,124 @@ impl ActualSteamCollection {
         }
     }
 
+    /// One of the collections Steam makes itself, for when it has not made it yet
+    fn steam_collection(id: &str, name: &str) -> Self {
+        let value = serde_json::json!({ "id": id, "name": name, "added": [], "removed": [] });
+        ActualSteamCollection {
+            key: format!("user-collections.{}", id),
+            timestamp: now_timestamp(),
+            value: value.to_string(),
+            conflict_resolution_method: Some("custom".to_string()),
+            str_method_id: Some("union-collections".to_string()),
+            version: None,
+        }
+    }
+
     pub fn is_boilr_collection(&self) -> bool {
         self.key
             .contains(&format!("user-collections.{}", BOILR_TAG))
     }
+
+    /// Adds the wanted games to a collection made in Steam and takes out the games
+    /// BoilR added before that are not wanted anymore, games added by the user are kept.
+    /// Returns the change, or None if this collection is not one games are added to.
+    fn apply_additions(
+        &mut self,
+        additions: &[Collection],
+        tracked: &HashMap<String, Vec<usize>>,
+    ) -> Option<CollectionChange> {
+        // The value is kept as json, so fields BoilR does not know about are not lost
+        let mut value: serde_json::Value = serde_json::from_str(&self.value).ok()?;
+        let id = value.get("id")?.as_str()?.to_string();
+        let name = value
+            .get("name")
+            .and_then(|n| n.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let wanted = additions
+            .iter()
+            .find(|addition| is_same_collection(&id, &name, &addition.name))
+            .map(|addition| addition.game_ids.clone())
+            .unwrap_or_default();
+        let added_before = tracked.get(&id).cloned().unwrap_or_default();
+        if wanted.is_empty() && added_before.is_empty() {
+            return None;
+        }
+
+        let mut current: Vec<usize> = serde_json::from_value(value.get("added")?.clone()).ok()?;
+        let removed: Vec<usize> = added_before
+            .iter()
+            .filter(|game_id| !wanted.contains(game_id) && current.contains(game_id))
+            .copied()
+            .collect();
+        current.retain(|game_id| !removed.contains(game_id));
+        let added: Vec<usize> = wanted
+            .iter()
+            .filter(|game_id| !current.contains(game_id))
+            .copied()
+            .collect();
+        current.extend(added.iter().copied());
+        let boilr_ids = wanted
+            .iter()
+            .filter(|game_id| added.contains(game_id) || added_before.contains(game_id))
+            .copied()
+            .collect();
+
+        if !removed.is_empty() || !added.is_empty() {
+            value["added"] = serde_json::json!(current);
+            self.value = value.to_string();
+            self.timestamp = now_timestamp();
+        }
+        Some(CollectionChange {
+            id,
+            name,
+            added,
+            removed,
+            boilr_ids,
+        })
+    }
+}
+
+/// What BoilR changed in a collection it did not make
+struct CollectionChange {
+    id: String,
+    name: String,
+    added: Vec<usize>,
+    removed: Vec<usize>,
+    /// All the games in the collection that BoilR put there
+    boilr_ids: Vec<usize>,
+}
+
+fn is_same_collection(id: &str, name: &str, wanted_name: &str) -> bool {
+    let wanted_name = wanted_name.trim();
+    let is_steam_collection = STEAM_COLLECTIONS.iter().any(|(steam_id, steam_name)| {
+        id == *steam_id
+            && (wanted_name.eq_ignore_ascii_case(steam_id)
+                || wanted_name.eq_ignore_ascii_case(steam_name))
+    });
+    is_steam_collection || name.eq_ignore_ascii_case(wanted_name)
+}
+
+fn now_timestamp() -> u64 {
+    let since_the_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards");
+    since_the_epoch.as_secs()
+}
+
+/// The games BoilR added to collections it did not make, by steam user and collection id
+type CollectionAdditions = HashMap<String, HashMap<String, Vec<usize>>>;
+
+fn load_collection_additions() -> CollectionAdditions {
+    load_json_file(&get_collection_additions_file())
+}
+
+fn save_collection_additions(additions: &CollectionAdditions) {
+    save_json_file(
+        &get_collection_additions_file(),
+        additions,
+        "collection additions",
+    );
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -99,16 +220,33 @@ struct DeletedCollection {
     version: String,
 }
 
+#[derive(Debug, Clone)]
 pub struct Collection {
     pub name: String,
     pub game_ids: Vec<usize>,
 }
 
+/// Replaces the collections made by BoilR with the given ones, they are left alone if there are none,
+/// and adds games to collections made in Steam, like Favorites, by their name.
 pub fn write_collections<S: AsRef<str>>(
+    settings: &SteamSettings,
     steam_user_id: S,
-    collections_to_add: &[Collection],
+    collections_to_add: Option<&[Collection]>,
+    additions: &[Collection],
 ) -> Result<(), Box<dyn Error>> {
     let steam_user_id = steam_user_id.as_ref();
+    let mut all_additions = load_collection_additions();
+    let tracked = all_additions
+        .get(steam_user_id)
+        .cloned()
+        .unwrap_or_default();
+    let replace_boilr_collections = collections_to_add.is_some();
+    let has_additions = additions.iter().any(|a| !a.game_ids.is_empty());
+    if !replace_boilr_collections && !has_additions && tracked.is_empty() {
+        // Nothing to do, so Steam does not have to be closed
+        return Ok(());
+    }
+    let collections_to_add = collections_to_add.unwrap_or_default();
     let new_collections: Vec<(String, SteamCollection)> = collections_to_add
         .iter()
         .map(|c| {
@@ -117,27 +255,47 @@ pub fn write_collections<S: AsRef<str>>(
         })
         .collect();
 
-    let mut db = open_db()?;
+    let mut db = open_db(settings)?;
 
     let current_categories = get_categories(steam_user_id, &mut db)?;
     //this is a collection of collections, known as a category
     let mut write_batch = WriteBatch::new();
+    let mut changes: Vec<CollectionChange> = vec![];
 
     for (category_key, mut collections) in current_categories {
-        collections.retain(|(_key, collection)| !collection.is_boilr_collection());
+        if replace_boilr_collections {
+            collections.retain(|(_key, collection)| !collection.is_boilr_collection());
+        }
+        for (id, name) in STEAM_COLLECTIONS {
+            let key = format!("user-collections.{}", id);
+            let is_wanted = additions
+                .iter()
+                .any(|a| !a.game_ids.is_empty() && is_same_collection(id, name, &a.name));
+            if is_wanted && !collections.iter().any(|(k, _)| *k == key) {
+                let collection = ActualSteamCollection::steam_collection(id, name);
+                collections.push((key, SteamCollection::Actual(collection)));
+            }
+        }
+        for (_key, collection) in collections.iter_mut() {
+            if let SteamCollection::Actual(actual) = collection {
+                changes.extend(actual.apply_additions(additions, &tracked));
+            }
+        }
         collections.extend(new_collections.clone());
         save_category(category_key, collections, &mut write_batch)?;
 
-        if let Some(path) = get_vdf_path(steam_user_id) {
+        if let Some(path) = get_vdf_path(settings, steam_user_id) {
             let content = std::fs::read_to_string(&path).expect("Should be able to read this file");
             if let Some(mut vdf_collections) = parse_vdf_collection(content) {
-                let boilr_keys: Vec<String> = vdf_collections
-                    .keys()
-                    .filter(|k| k.contains(BOILR_TAG))
-                    .cloned()
-                    .collect();
-                for key in boilr_keys {
-                    vdf_collections.remove(&key);
+                if replace_boilr_collections {
+                    let boilr_keys: Vec<String> = vdf_collections
+                        .keys()
+                        .filter(|k| k.contains(BOILR_TAG))
+                        .cloned()
+                        .collect();
+                    for key in boilr_keys {
+                        vdf_collections.remove(&key);
+                    }
                 }
 
                 let new_vdfs = collections_to_add.iter().map(|collection| {
@@ -152,6 +310,18 @@ pub fn write_collections<S: AsRef<str>>(
                 for new_vdf in new_vdfs {
                     vdf_collections.insert(new_vdf.id.clone(), new_vdf.clone());
                 }
+                for change in &changes {
+                    let vdf_collection = vdf_collections
+                        .entry(change.id.clone())
+                        .or_insert_with(|| VdfCollection::new(&change.id));
+                    let added = &mut vdf_collection.added;
+                    added.retain(|game_id| !change.removed.contains(game_id));
+                    for game_id in &change.added {
+                        if !added.contains(game_id) {
+                            added.push(*game_id);
+                        }
+                    }
+                }
 
                 let new_string = write_vdf_collection_to_string(
                     &path.clone().to_string_lossy(),
@@ -166,47 +336,42 @@ pub fn write_collections<S: AsRef<str>>(
 
     db.write(write_batch, true)?;
 
-    Ok(())
-}
-
-#[cfg(target_family = "unix")]
-fn get_vdf_path<S: AsRef<str>>(steamid: S) -> Option<PathBuf> {
-    match std::env::var("HOME") {
-        Ok(home) => {
-            let path = Path::new(&home)
-                .join(".steam")
-                .join("steam")
-                .join("userdata")
-                .join(steamid.as_ref())
-                .join("config")
-                .join("localconfig.vdf");
-            if path.exists() {
-                return Some(path);
-            }
-            None
+    for addition in additions {
+        let found = changes
+            .iter()
+            .any(|change| is_same_collection(&change.id, &change.name, &addition.name));
+        if !found && !addition.game_ids.is_empty() {
+            println!(
+                "Could not find the collection {} for user {}",
+                addition.name, steam_user_id
+            );
         }
-        Err(_e) => None,
     }
-}
-
-#[cfg(target_os = "windows")]
-fn get_vdf_path<S: AsRef<str>>(steamid: S) -> Option<PathBuf> {
-    match std::env::var("PROGRAMFILES(X86)") {
-        Ok(program_files) => {
-            let path = Path::new(&program_files)
-                .join("Steam")
-                .join("userdata")
-                .join(steamid.as_ref())
-                .join("config")
-                .join("localconfig.vdf");
-            if path.exists() {
-                Some(path)
-            } else {
-                None
-            }
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let user_additions = all_additions.entry(steam_user_id.to_string()).or_default();
+    for change in changes {
+        if change.boilr_ids.is_empty() {
+            user_additions.remove(&change.id);
+        } else {
+            user_additions.insert(change.id, change.boilr_ids);
         }
-        Err(_e) => None,
     }
+    save_collection_additions(&all_additions);
+
+    Ok(())
+}
+
+/// The localconfig.vdf of a user, in the same Steam folder the shortcuts are read from
+fn get_vdf_path<S: AsRef<str>>(settings: &SteamSettings, steamid: S) -> Option<PathBuf> {
+    let steam_path = get_steam_path(settings).ok()?;
+    let path = Path::new(&steam_path)
+        .join("userdata")
+        .join(steamid.as_ref())
+        .join("config")
+        .join("localconfig.vdf");
+    path.exists().then_some(path)
 }
 
 fn save_category<S: AsRef<str>>(
@@ -242,10 +407,10 @@ fn get_categories<S: AsRef<str>>(
     Ok(res)
 }
 
-fn open_db() -> Result<DB, Box<dyn Error>> {
-    let location = get_level_db_location();
+fn open_db(settings: &SteamSettings) -> Result<DB, Box<dyn Error>> {
+    let location = get_level_db_location(settings)?;
     let options = Options::default();
-    let open_res = DB::open(location.unwrap(), options);
+    let open_res = DB::open(location, options);
     if let Err(e) = &open_res {
         match &e.code {
             rusty_leveldb::StatusCode::LockError => {
@@ -260,6 +425,102 @@ fn open_db() -> Result<DB, Box<dyn Error>> {
     Ok(open_res?)
 }
 
+/// Checks if Steam holds the lock on its level database, collections can not be written while it does
+pub fn is_collections_db_locked(settings: &SteamSettings) -> bool {
+    match get_level_db_location(settings).map(|location| DB::open(location, Options::default())) {
+        Ok(Err(e)) => matches!(e.code, rusty_leveldb::StatusCode::LockError),
+        _ => false,
+    }
+}
+
+/// Steam only makes the level database after it has been started once
+pub fn has_collections_db(settings: &SteamSettings) -> bool {
+    get_level_db_location(settings).is_ok()
+}
+
+/// Copies the level database with the collections of all users to the given folder
+pub fn backup_collections_db(
+    settings: &SteamSettings,
+    target: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let location = get_level_db_location(settings)?;
+    copy_db(&location, target)
+}
+
+/// Reads the collections of a user from a copy of the level database,
+/// so they can be read while Steam is running and holds the lock on it
+pub fn read_collections<S: AsRef<str>>(
+    settings: &SteamSettings,
+    steam_user_id: S,
+) -> Result<Vec<Collection>, Box<dyn Error>> {
+    read_snapshot_collections(settings, steam_user_id, false)
+}
+
+/// Reads only the collections BoilR made for a user, see [`read_collections`]
+pub fn read_boilr_collections<S: AsRef<str>>(
+    settings: &SteamSettings,
+    steam_user_id: S,
+) -> Result<Vec<Collection>, Box<dyn Error>> {
+    read_snapshot_collections(settings, steam_user_id, true)
+}
+
+fn read_snapshot_collections<S: AsRef<str>>(
+    settings: &SteamSettings,
+    steam_user_id: S,
+    only_boilr: bool,
+) -> Result<Vec<Collection>, Box<dyn Error>> {
+    let location = get_level_db_location(settings)?;
+    let snapshot = std::env::temp_dir().join(format!(
+        "boilr-leveldb-{}-{}",
+        std::process::id(),
+        SNAPSHOT_COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let _ = std::fs::remove_dir_all(&snapshot);
+    let result = copy_db(&location, &snapshot).and_then(|_| {
+        let mut db = DB::open(&snapshot, Options::default())?;
+        get_categories(steam_user_id, &mut db)
+    });
+    let _ = std::fs::remove_dir_all(&snapshot);
+
+    let mut collections: Vec<Collection> = vec![];
+    for (_key, collection) in result?.into_values().flatten() {
+        if let SteamCollection::Actual(actual) = collection {
+            if only_boilr && !actual.is_boilr_collection() {
+                continue;
+            }
+            let value: serde_json::Value = match serde_json::from_str(&actual.value) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            let name = match value.get("name").and_then(|n| n.as_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            // Dynamic collections have a filter instead of a list of games, they are shown empty
+            let game_ids = value
+                .get("added")
+                .and_then(|added| serde_json::from_value(added.clone()).ok())
+                .unwrap_or_default();
+            if !collections.iter().any(|c| c.name == name) {
+                collections.push(Collection { name, game_ids });
+            }
+        }
+    }
+    Ok(collections)
+}
+
+/// Copies the database files, but not the lock Steam holds on them
+fn copy_db(location: &Path, target: &Path) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(target)?;
+    for entry in std::fs::read_dir(location)?.flatten() {
+        let path = entry.path();
+        if path.is_file() && entry.file_name() != "LOCK" {
+            std::fs::copy(&path, target.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
 fn get_namespace_keys<S: AsRef<str>>(steamid: S, db: &mut DB) -> HashSet<String> {
     let keyprefix = get_steam_user_prefix(steamid);
 
@@ -285,43 +546,32 @@ fn get_namespaces(db: &mut DB, key_bytes: &[u8]) -> Option<Vec<(i32, String)>> {
     }
 }
 
-#[cfg(target_family = "unix")]
-fn get_level_db_location() -> Option<PathBuf> {
-    match std::env::var("HOME") {
-        Ok(home) => {
-            let path = Path::new(&home)
-                .join(".steam")
-                .join("steam")
-                .join("config")
-                .join("htmlcache")
-                .join("Local Storage")
-                .join("leveldb");
-            if path.exists() {
-                return Some(path);
-            }
-            None
-        }
-        Err(_e) => None,
+/// The level database Steam keeps the collections in, found in the same Steam folder the shortcuts are read from
+fn get_level_db_location(settings: &SteamSettings) -> Result<PathBuf, Box<dyn Error>> {
+    let steam_path = get_steam_path(settings).map_err(|e| e.to_string())?;
+    let candidates = level_db_candidates(Path::new(&steam_path));
+    match candidates.iter().find(|path| path.exists()) {
+        Some(path) => Ok(path.clone()),
+        None => Err(format!(
+            "Could not find the steam level database at {:?}, try to open and close steam once",
+            candidates
+        )
+        .into()),
     }
 }
 
-#[cfg(target_os = "windows")]
-fn get_level_db_location() -> Option<PathBuf> {
-    match std::env::var("LOCALAPPDATA") {
-        Ok(localdata) => {
-            let path = Path::new(&localdata)
-                .join("Steam")
-                .join("htmlcache")
-                .join("Local Storage")
-                .join("leveldb");
-            if path.exists() {
-                Some(path)
-            } else {
-                None
-            }
-        }
-        Err(_e) => None,
+fn level_db_candidates(steam_path: &Path) -> Vec<PathBuf> {
+    let level_db = |htmlcache: PathBuf| htmlcache.join("Local Storage").join("leveldb");
+    #[allow(unused_mut)]
+    let mut candidates = vec![level_db(steam_path.join("config").join("htmlcache"))];
+    // The Windows client keeps its html cache in the local app data instead of the Steam folder
+    #[cfg(target_os = "windows")]
+    if let Ok(local_data) = std::env::var("LOCALAPPDATA") {
+        candidates.push(level_db(
+            Path::new(&local_data).join("Steam").join("htmlcache"),
+        ));
     }
+    candidates
 }
 
 fn serialize_collection_value<S: AsRef<str>>(name: S, game_ids: &[usize]) -> String {
@@ -389,6 +639,16 @@ pub struct VdfCollection {
     removed: Vec<usize>,
 }
 
+impl VdfCollection {
+    fn new(id: &str) -> Self {
+        VdfCollection {
+            id: id.to_string(),
+            added: vec![],
+            removed: vec![],
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -417,4 +677,37 @@ mod tests {
         let collection = parse_steam_collections(input).unwrap();
         assert_eq!(28, collection.len())
     }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn reads_collections_while_the_database_is_open() {
+        let steam = crate::sync::fake_steam::FakeSteam::new("read_collections");
+        steam.add_user("100", None);
+        steam.add_steam_collection("100", "favorite", "Favorites", &[1, 2]);
+        let settings = steam.settings().steam;
+        let location = get_level_db_location(&settings).unwrap();
+        // Stands in for Steam, which keeps the database open while it runs
+        let _steam_db = DB::open(&location, Options::default()).unwrap();
+
+        let collections = read_collections(&settings, "100").unwrap();
+        assert_eq!(collections.len(), 1);
+        assert_eq!(collections[0].name, "Favorites");
+        assert_eq!(collections[0].game_ids, vec![1, 2]);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn finds_the_level_database_in_the_configured_steam_folder() {
+        let steam = crate::sync::fake_steam::FakeSteam::new("level_db_location");
+        steam.add_user("100", None);
+        let custom_steam = steam.root.join("custom-steam");
+        let mut settings = steam.settings().steam;
+        settings.location = Some(custom_steam.to_string_lossy().to_string());
+        let error = get_level_db_location(&settings).unwrap_err();
+        assert!(error.to_string().contains("custom-steam"));
+
+        let level_db = level_db_candidates(&custom_steam).remove(0);
+        std::fs::create_dir_all(&level_db).unwrap();
+        assert_eq!(get_level_db_location(&settings).unwrap(), level_db);
+    }
 }
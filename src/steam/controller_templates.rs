@@ -0,0 +1,386 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{get_controller_templates_file, load_json_file, save_json_file};
+
+use super::{get_steam_path, PerGameOverrides, SteamSettings};
+
+const CONFIG_SET_PREFIX: &str = "configset_controller_";
+/// The Steam Deck, used when Steam has not written a configuration for any controller yet
+const DEFAULT_CONTROLLER: &str = "neptune";
+
+/// Steam Input layouts that ship with Steam
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControllerTemplate {
+    Gamepad,
+    GamepadWithMouseTrackpad,
+    GamepadWithCameraControls,
+    KeyboardAndMouse,
+}
+
+impl ControllerTemplate {
+    pub const ALL: [ControllerTemplate; 4] = [
+        ControllerTemplate::Gamepad,
+        ControllerTemplate::GamepadWithMouseTrackpad,
+        ControllerTemplate::GamepadWithCameraControls,
+        ControllerTemplate::KeyboardAndMouse,
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            ControllerTemplate::Gamepad => "Gamepad",
+            ControllerTemplate::GamepadWithMouseTrackpad => "Gamepad with mouse trackpad",
+            ControllerTemplate::GamepadWithCameraControls => "Gamepad with camera controls",
+            ControllerTemplate::KeyboardAndMouse => "Keyboard (WASD) and mouse",
+        }
+    }
+
+    fn suffix(&self) -> &str {
+        match self {
+            ControllerTemplate::Gamepad => "gamepad_joystick",
+            ControllerTemplate::GamepadWithMouseTrackpad => "gamepad+mouse",
+            ControllerTemplate::GamepadWithCameraControls => "gamepad_fps",
+            ControllerTemplate::KeyboardAndMouse => "wasd",
+        }
+    }
+
+    /// Every controller type has its own copy of the template
+    fn file_name(&self, controller: &str) -> String {
+        format!("controller_{}_{}.vdf", controller, self.suffix())
+    }
+}
+
+/// The Steam Input layout imported games start with, instead of the one Steam picks
+pub type ControllerTemplates = PerGameOverrides<ControllerTemplate>;
+
+/// Steam keeps the controller configuration of a non-Steam shortcut under its name in lower case,
+/// not under its app id
+pub fn controller_config_key(shortcut_name: &str) -> String {
+    shortcut_name.to_lowercase()
+}
+
+/// The template files BoilR wrote, by steam user, controller and [`controller_config_key`]
+type WrittenTemplates = HashMap<String, HashMap<String, HashMap<String, String>>>;
+
+/// Sets the Steam Input template of the given shortcuts for a user,
+/// and takes out the templates BoilR set before for the other given shortcuts.
+/// Both are keyed by [`controller_config_key`].
+/// Only entries BoilR wrote itself are changed, so layouts the user picked in Steam are left alone.
+pub fn write_controller_templates<S: AsRef<str>>(
+    settings: &SteamSettings,
+    steam_user_id: S,
+    templates: &HashMap<String, ControllerTemplate>,
+    boilr_games: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let steam_user_id = steam_user_id.as_ref();
+    let config_folder = get_controller_config_folder(settings, steam_user_id)?;
+    let mut config_sets = get_config_sets(&config_folder);
+    if config_sets.is_empty() {
+        if templates.is_empty() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&config_folder)?;
+        config_sets.push((
+            DEFAULT_CONTROLLER.to_string(),
+            config_folder.join(format!("{}{}.vdf", CONFIG_SET_PREFIX, DEFAULT_CONTROLLER)),
+        ));
+    }
+    let mut all_written: WrittenTemplates = load_json_file(&get_controller_templates_file());
+    let before = all_written.clone();
+    let user_written = all_written.entry(steam_user_id.to_string()).or_default();
+    let mut result = Ok(());
+    for (controller, path) in config_sets {
+        let written = user_written.entry(controller.clone()).or_default();
+        let content = std::fs::read_to_string(&path).unwrap_or_default();
+        let new_content = set_templates(&content, &controller, templates, boilr_games, written);
+        if new_content != content {
+            if let Err(e) = std::fs::write(&path, new_content) {
+                result = Err(e.into());
+                break;
+            }
+        }
+    }
+    user_written.retain(|_, written| !written.is_empty());
+    all_written.retain(|_, user_written| !user_written.is_empty());
+    if all_written != before {
+        save_json_file(
+            &get_controller_templates_file(),
+            &all_written,
+            "controller templates",
+        );
+    }
+    result
+}
+
+fn get_controller_config_folder<S: AsRef<str>>(
+    settings: &SteamSettings,
+    steam_user_id: S,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let steam_path = get_steam_path(settings).map_err(|e| e.to_string())?;
+    Ok(Path::new(&steam_path)
+        .join("steamapps")
+        .join("common")
+        .join("Steam Controller Configs")
+        .join(steam_user_id.as_ref())
+        .join("config"))
+}
+
+/// The controller types Steam has a configuration file for, with the path of that file
+fn get_config_sets(config_folder: &Path) -> Vec<(String, PathBuf)> {
+    let mut config_sets: Vec<(String, PathBuf)> = std::fs::read_dir(config_folder)
+        .map(|files| {
+            files
+                .flatten()
+                .filter_map(|file| {
+                    let file_name = file.file_name().to_string_lossy().to_string();
+                    let controller = file_name
+                        .strip_prefix(CONFIG_SET_PREFIX)?
+                        .strip_suffix(".vdf")?
+                        .to_string();
+                    Some((controller, file.path()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    config_sets.sort();
+    config_sets
+}
+
+/// A game in a configuration set, with the lines between its braces
+struct ConfigSetEntry {
+    key: String,
+    body: Vec<String>,
+}
+
+impl ConfigSetEntry {
+    fn template(&self) -> Option<&str> {
+        self.body.iter().find_map(|line| {
+            let line = line.trim();
+            line.strip_prefix("\"template\"")
+                .map(|value| value.trim().trim_matches('"'))
+        })
+    }
+
+    /// Written by BoilR and not changed in Steam since
+    fn is_boilr_template(&self, written: &HashMap<String, String>) -> bool {
+        written
+            .get(&self.key)
+            .map(|file_name| self.template() == Some(file_name.as_str()))
+            .unwrap_or(false)
+    }
+}
+
+fn parse_config_set(content: &str) -> Vec<ConfigSetEntry> {
+    let mut entries = vec![];
+    let mut depth = 0;
+    let mut current: Option<ConfigSetEntry> = None;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        match trimmed {
+            "{" => {
+                depth += 1;
+                if depth > 2 {
+                    if let Some(entry) = current.as_mut() {
+                        entry.body.push(line.to_string());
+                    }
+                }
+            }
+            "}" => {
+                depth -= 1;
+                if depth == 1 {
+                    entries.extend(current.take());
+                } else if let Some(entry) = current.as_mut() {
+                    entry.body.push(line.to_string());
+                }
+            }
+            _ if depth == 1 && trimmed.starts_with('"') => {
+                current = Some(ConfigSetEntry {
+                    key: trimmed.trim_matches('"').to_string(),
+                    body: vec![],
+                });
+            }
+            _ if depth >= 2 => {
+                if let Some(entry) = current.as_mut() {
+                    entry.body.push(line.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+fn write_config_set(entries: &[ConfigSetEntry]) -> String {
+    let mut result = "\"controller_config\"\n{\n".to_string();
+    for entry in entries {
+        result.push_str(&format!("\t\"{}\"\n\t{{\n", entry.key));
+        for line in &entry.body {
+            result.push_str(line);
+            result.push('\n');
+        }
+        result.push_str("\t}\n");
+    }
+    result.push_str("}\n");
+    result
+}
+
+/// Updates the config set of one controller, `written` keeps the template files BoilR wrote in it
+fn set_templates(
+    content: &str,
+    controller: &str,
+    templates: &HashMap<String, ControllerTemplate>,
+    boilr_games: &[String],
+    written: &mut HashMap<String, String>,
+) -> String {
+    let mut entries = parse_config_set(content);
+    // Entries that were removed or changed in Steam are no longer BoilR's
+    written.retain(|key, file_name| {
+        entries
+            .iter()
+            .any(|entry| &entry.key == key && entry.template() == Some(file_name.as_str()))
+    });
+    let mut changed = false;
+    entries.retain(|entry| {
+        let is_stale = boilr_games.contains(&entry.key) && !templates.contains_key(&entry.key);
+        let keep = !is_stale || !entry.is_boilr_template(written);
+        changed |= !keep;
+        keep
+    });
+    written.retain(|key, _| !boilr_games.contains(key) || templates.contains_key(key));
+
+    let mut keys: Vec<&String> = templates.keys().collect();
+    keys.sort();
+    for key in keys {
+        let file_name = templates[key].file_name(controller);
+        let template_line = format!("\t\t\"template\"\t\t\"{}\"", file_name);
+        let key = key.clone();
+        match entries.iter_mut().find(|entry| entry.key == key) {
+            Some(entry) if entry.is_boilr_template(written) => {
+                if entry.template() != Some(file_name.as_str()) {
+                    entry.body = vec![template_line];
+                    written.insert(key, file_name);
+                    changed = true;
+                }
+            }
+            Some(_) => {}
+            None => {
+                entries.push(ConfigSetEntry {
+                    key: key.clone(),
+                    body: vec![template_line],
+                });
+                written.insert(key, file_name);
+                changed = true;
+            }
+        }
+    }
+    if changed {
+        write_config_set(&entries)
+    } else {
+        content.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Shaped like the configuration sets Steam writes, shortcuts are keyed by their name in lower case
+    const CONFIG_SET: &str = "\"controller_config\"\n{\n\t\"celeste\"\n\t{\n\t\t\"workshop\"\t\t\"123\"\n\t}\n\t\"doom\"\n\t{\n\t\t\"template\"\t\t\"controller_neptune_wasd.vdf\"\n\t}\n\t\"quake\"\n\t{\n\t\t\"workshop\"\t\t\"456\"\n\t}\n}\n";
+
+    fn keys(names: &[&str]) -> Vec<String> {
+        names
+            .iter()
+            .map(|name| controller_config_key(name))
+            .collect()
+    }
+
+    #[test]
+    fn sets_and_removes_boilr_templates() {
+        let mut templates = HashMap::new();
+        templates.insert(controller_config_key("Quake"), ControllerTemplate::Gamepad);
+        templates.insert(
+            controller_config_key("Half-Life"),
+            ControllerTemplate::GamepadWithMouseTrackpad,
+        );
+        let boilr_games = keys(&["Doom", "Quake", "Half-Life"]);
+        let mut written = HashMap::new();
+        written.insert(
+            "doom".to_string(),
+            "controller_neptune_wasd.vdf".to_string(),
+        );
+        let result = set_templates(
+            CONFIG_SET,
+            "neptune",
+            &templates,
+            &boilr_games,
+            &mut written,
+        );
+        let entries = parse_config_set(&result);
+        let entry_keys: Vec<&str> = entries.iter().map(|e| e.key.as_str()).collect();
+        // Doom is no longer wanted, and the layout picked in Steam for Quake is kept
+        assert_eq!(entry_keys, vec!["celeste", "quake", "half-life"]);
+        assert_eq!(entries[1].template(), None);
+        assert_eq!(
+            entries[2].template(),
+            Some("controller_neptune_gamepad+mouse.vdf")
+        );
+
+        assert_eq!(written.keys().collect::<Vec<_>>(), vec!["half-life"]);
+
+        let removed = set_templates(
+            &result,
+            "neptune",
+            &HashMap::new(),
+            &boilr_games,
+            &mut written,
+        );
+        let entry_keys: Vec<String> = parse_config_set(&removed)
+            .into_iter()
+            .map(|e| e.key)
+            .collect();
+        assert_eq!(entry_keys, vec!["celeste", "quake"]);
+        assert!(written.is_empty());
+    }
+
+    #[test]
+    fn keeps_stock_layouts_picked_in_steam() {
+        let mut templates = HashMap::new();
+        templates.insert(controller_config_key("Doom"), ControllerTemplate::Gamepad);
+        let mut written = HashMap::new();
+        let result = set_templates(
+            CONFIG_SET,
+            "neptune",
+            &templates,
+            &keys(&["Doom"]),
+            &mut written,
+        );
+        assert_eq!(result, CONFIG_SET);
+        let result = set_templates(
+            CONFIG_SET,
+            "neptune",
+            &HashMap::new(),
+            &keys(&["Doom"]),
+            &mut written,
+        );
+        assert_eq!(result, CONFIG_SET);
+        assert!(written.is_empty());
+    }
+
+    #[test]
+    fn leaves_unchanged_config_sets_alone() {
+        let result = set_templates(
+            CONFIG_SET,
+            "neptune",
+            &HashMap::new(),
+            &keys(&["Other"]),
+            &mut HashMap::new(),
+        );
+        assert_eq!(result, CONFIG_SET);
+    }
+}
@@ -0,0 +1,94 @@
+use steam_shortcuts_util::shortcut::ShortcutOwned;
+
+use super::PerGameOverrides;
+
+const COMMAND_PLACEHOLDER: &str = "%command%";
+
+/// Launch options put around the ones the platform makes for a game.
+/// A template like `DXVK_ASYNC=1 gamemoderun %command% -fullscreen` puts environment variables
+/// and wrappers before the game and arguments after it.
+pub type LaunchOptionTemplates = PerGameOverrides<String>;
+
+/// A template typed in a text field, left empty to use no template
+pub fn template_from_text(text: &str) -> Option<String> {
+    (!text.trim().is_empty()).then(|| text.to_string())
+}
+
+impl LaunchOptionTemplates {
+    /// Puts the template of the game around the launch options the platform made for it
+    pub fn apply(&self, platform: &str, shortcut: &mut ShortcutOwned) {
+        if let Some(template) = self.template_for(platform, shortcut.app_id) {
+            shortcut.launch_options = apply_template(template, &shortcut.launch_options);
+        }
+    }
+}
+
+/// Splits launch options around %command%, options without it are all arguments
+fn split_command(launch_options: &str) -> (&str, &str) {
+    match launch_options.split_once(COMMAND_PLACEHOLDER) {
+        Some((before, after)) => (before.trim(), after.trim()),
+        None => ("", launch_options.trim()),
+    }
+}
+
+/// Environment variables and wrappers from the template go after the ones the platform needs,
+/// because a wrapper has to come after every variable, and its arguments go after the arguments of the platform.
+/// %command% is only kept when something goes before it, plain arguments are just joined.
+fn apply_template(template: &str, launch_options: &str) -> String {
+    let (template_before, template_after) = split_command(template);
+    let (before, after) = split_command(launch_options);
+    let command = if before.is_empty() && template_before.is_empty() {
+        ""
+    } else {
+        COMMAND_PLACEHOLDER
+    };
+    let parts: Vec<&str> = [before, template_before, command, after, template_after]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect();
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_templates_around_launch_options() {
+        assert_eq!(
+            apply_template("gamemoderun %command%", ""),
+            "gamemoderun %command%"
+        );
+        assert_eq!(
+            apply_template(
+                "DXVK_ASYNC=1 mangohud %command% -fullscreen",
+                "lutris:rungame/doom"
+            ),
+            "DXVK_ASYNC=1 mangohud %command% lutris:rungame/doom -fullscreen"
+        );
+        assert_eq!(
+            apply_template(
+                "PROTON_ENABLE_NVAPI=1 %command%",
+                "STEAM_COMPAT_DATA_PATH=\"/prefix\" %command% 'args'"
+            ),
+            "STEAM_COMPAT_DATA_PATH=\"/prefix\" PROTON_ENABLE_NVAPI=1 %command% 'args'"
+        );
+        assert_eq!(
+            apply_template("-skipintro", "-nolauncher"),
+            "-nolauncher -skipintro"
+        );
+        assert_eq!(
+            apply_template("%command% -skipintro", "%command% -nolauncher"),
+            "-nolauncher -skipintro"
+        );
+    }
+
+    #[test]
+    fn blank_text_is_no_template() {
+        assert_eq!(template_from_text(" "), None);
+        assert_eq!(
+            template_from_text("mangohud %command%"),
+            Some("mangohud %command%".to_string())
+        );
+    }
+}
@@ -1,15 +1,23 @@
+mod collection_rules;
 mod collections;
+mod controller_templates;
 mod installed_games;
+mod launch_options;
+mod per_game_overrides;
 #[cfg(target_family = "unix")]
 mod proton_vdf_util;
 mod restarter;
 mod settings;
 mod utils;
 
+pub use collection_rules::*;
 pub use collections::*;
+pub use controller_templates::*;
 pub use installed_games::*;
+pub use launch_options::*;
+pub use per_game_overrides::*;
 #[cfg(target_family = "unix")]
 pub use proton_vdf_util::*;
 pub use restarter::*;
-pub use settings::SteamSettings;
+pub use settings::{GameCompatTool, SteamSettings, NATIVE_COMPAT_TOOL};
 pub use utils::*;
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+/// A setting that can be given to all games of a platform and to single games,
+/// the value of a game wins over the value of its platform
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PerGameOverrides<T> {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub platforms: Vec<PlatformOverride<T>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub games: Vec<GameOverride<T>>,
+}
+
+impl<T> Default for PerGameOverrides<T> {
+    fn default() -> Self {
+        Self {
+            platforms: vec![],
+            games: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlatformOverride<T> {
+    pub platform: String,
+    pub template: T,
+}
+
+/// Found by the app id the platform gives the game, so the value is kept when the game is renamed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GameOverride<T> {
+    pub app_id: u32,
+    pub template: T,
+}
+
+impl<T> PerGameOverrides<T> {
+    pub fn platform_template(&self, platform: &str) -> Option<&T> {
+        self.platforms
+            .iter()
+            .find(|p| p.platform.eq_ignore_ascii_case(platform))
+            .map(|p| &p.template)
+    }
+
+    pub fn game_template(&self, app_id: u32) -> Option<&T> {
+        self.games
+            .iter()
+            .find(|g| g.app_id == app_id)
+            .map(|g| &g.template)
+    }
+
+    pub fn template_for(&self, platform: &str, app_id: u32) -> Option<&T> {
+        self.game_template(app_id)
+            .or_else(|| self.platform_template(platform))
+    }
+
+    /// None takes the value of the platform out again
+    pub fn set_platform_template(&mut self, platform: &str, template: Option<T>) {
+        self.platforms
+            .retain(|p| !p.platform.eq_ignore_ascii_case(platform));
+        if let Some(template) = template {
+            self.platforms.push(PlatformOverride {
+                platform: platform.to_string(),
+                template,
+            });
+        }
+    }
+
+    /// None takes the value of the game out again, so the platform value is used
+    pub fn set_game_template(&mut self, app_id: u32, template: Option<T>) {
+        self.games.retain(|g| g.app_id != app_id);
+        if let Some(template) = template {
+            self.games.push(GameOverride { app_id, template });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_values_win_over_platform_values() {
+        let mut overrides = PerGameOverrides::default();
+        overrides.set_platform_template("Lutris", Some("platform"));
+        overrides.set_game_template(7, Some("game"));
+        assert_eq!(overrides.template_for("lutris", 7), Some(&"game"));
+        assert_eq!(overrides.template_for("Lutris", 8), Some(&"platform"));
+        assert_eq!(overrides.template_for("Heroic", 8), None);
+
+        overrides.set_game_template(7, None);
+        assert!(overrides.games.is_empty());
+        assert_eq!(overrides.template_for("Lutris", 7), Some(&"platform"));
+    }
+}
@@ -1,20 +1,90 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use nom::FindSubstring;
 
-pub fn setup_proton_games<B: AsRef<str>>(games: &[B]) {
-    if let Ok(home) = std::env::var("HOME") {
-        let config_file = Path::new(&home).join(".local/share/Steam/config/config.vdf");
-        if config_file.exists() {
-            if let Ok(config_content) = std::fs::read_to_string(&config_file) {
-                let new_string = enable_proton_games(config_content, games);
-                std::fs::write(config_file, new_string).unwrap();
+use super::{get_steam_path, SteamSettings};
+
+/// The compatibility tool BoilR picks for games that need proton
+const DEFAULT_COMPAT_TOOL: &str = "proton_experimental";
+
+pub fn setup_proton_games<B: AsRef<str>>(settings: &SteamSettings, games: &[B]) {
+    update_config_vdf(settings, |config_content| {
+        enable_proton_games(config_content, games)
+    });
+}
+
+/// Takes the given games out of the compatibility tool mapping, so they no longer run with proton
+pub fn remove_proton_games<B: AsRef<str>>(settings: &SteamSettings, games: &[B]) {
+    update_config_vdf(settings, |config_content| {
+        disable_proton_games(config_content, games)
+    });
+}
+
+/// Runs the given games with the given compatibility tools, replacing the tools they had
+pub fn set_compat_tools<B: AsRef<str>, T: AsRef<str>>(settings: &SteamSettings, games: &[(B, T)]) {
+    let ids: Vec<&str> = games.iter().map(|(id, _)| id.as_ref()).collect();
+    update_config_vdf(settings, |config_content| {
+        enable_compat_tools(disable_proton_games(config_content, &ids), games)
+    });
+}
+
+/// The config.vdf with the compatibility tool mapping, in the same Steam folder the shortcuts are read from
+pub fn get_config_vdf_path(settings: &SteamSettings) -> Option<PathBuf> {
+    let steam_path = get_steam_path(settings).ok()?;
+    Some(Path::new(&steam_path).join("config").join("config.vdf"))
+}
+
+fn update_config_vdf<F: FnOnce(&str) -> String>(settings: &SteamSettings, update: F) {
+    if let Some(config_file) = get_config_vdf_path(settings) {
+        if let Ok(config_content) = std::fs::read_to_string(&config_file) {
+            let new_string = update(&config_content);
+            if new_string != config_content {
+                if let Err(e) = std::fs::write(&config_file, new_string) {
+                    eprintln!("Could not write {:?}: {}", config_file, e);
+                }
+            }
+        }
+    }
+}
+
+fn disable_proton_games<S: AsRef<str>, B: AsRef<str>>(vdf_content: S, games: &[B]) -> String {
+    let vdf_content = vdf_content.as_ref();
+    let section_info = match find_indexes(vdf_content) {
+        Some(section_info) => section_info,
+        None => return vdf_content.to_string(),
+    };
+    let field_indent: String = (0..=section_info.base_indentation).map(|_| '\t').collect();
+    let mut section = vdf_content[section_info.start..section_info.append_end].to_string();
+    for game in games {
+        let game_start = format!("\n{}\"{}\"\n", field_indent, game.as_ref());
+        let game_end = format!("\n{}}}", field_indent);
+        if let Some(start) = section.find(&game_start) {
+            if let Some(end) = section[start..].find(&game_end) {
+                section.replace_range(start..start + end + game_end.len(), "");
             }
         }
     }
+    format!(
+        "{}{}{}",
+        &vdf_content[..section_info.start],
+        section,
+        &vdf_content[section_info.append_end..]
+    )
 }
 
 fn enable_proton_games<S: AsRef<str>, B: AsRef<str>>(vdf_content: S, games: &[B]) -> String {
+    let games: Vec<(&str, &str)> = games
+        .iter()
+        .map(|game| (game.as_ref(), DEFAULT_COMPAT_TOOL))
+        .collect();
+    enable_compat_tools(vdf_content, &games)
+}
+
+/// Adds a compatibility tool mapping for every game that does not have one yet
+fn enable_compat_tools<S: AsRef<str>, B: AsRef<str>, T: AsRef<str>>(
+    vdf_content: S,
+    games: &[(B, T)],
+) -> String {
     let vdf_content = vdf_content.as_ref();
     if let Some(section_info) = find_indexes(vdf_content) {
         let (base_indent_string, field_indent_string) = {
@@ -32,13 +102,17 @@ fn enable_proton_games<S: AsRef<str>, B: AsRef<str>>(vdf_content: S, games: &[B]
         let section_str = &vdf_content[section_info.start..section_info.append_end];
         let games_strings_to_add = games
             .iter()
-            .filter(|g| {
+            .filter(|(g, _)| {
                 let game_section_start = format!("\"{}\"\n", g.as_ref());
                 !section_str.contains(&game_section_start)
             })
-            .map(|game_id| {
+            .map(|(game_id, tool)| {
                 let res = proton_replace_string.to_string();
                 let res = res.replace("\"X\"", &format!("\"{}\"", game_id.as_ref()));
+                let res = res.replace(
+                    &format!("\"{}\"", DEFAULT_COMPAT_TOOL),
+                    &format!("\"{}\"", tool.as_ref()),
+                );
                 let res = res.replace('=', &base_indent_string);
                 res.replace('+', &field_indent_string)
             });
@@ -122,6 +196,28 @@ mod tests {
         assert_eq!(expected, output);
     }
 
+    #[test]
+    pub fn disable_proton_test() {
+        let input = include_str!("../testdata/vdf/testconfig.vdf");
+        let enabled = enable_proton_games(input, &["42", "43"]);
+        let output = disable_proton_games(&enabled, &["42", "43", "44"]);
+        assert_eq!(input, output);
+    }
+
+    #[test]
+    pub fn enable_compat_tools_test() {
+        let input = include_str!("../testdata/vdf/testconfig.vdf");
+        let enabled = enable_proton_games(input, &["42"]);
+        let output = enable_compat_tools(
+            disable_proton_games(&enabled, &["42"]),
+            &[("42", "proton_7")],
+        );
+        let game = &output[output.find("\"42\"\n").unwrap()..];
+        assert!(game[..game.find('}').unwrap()].contains("\"proton_7\""));
+        assert_eq!(output.matches("\"proton_7\"").count(), 1);
+        assert_eq!(disable_proton_games(&output, &["42"]), input);
+    }
+
     #[test]
     pub fn enable_proton_test_empty() {
         let input = include_str!("../testdata/vdf/testconfig.vdf");
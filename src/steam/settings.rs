@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
 
+use super::{CollectionRules, ControllerTemplates, LaunchOptionTemplates};
+
+/// Set as the compatibility tool of a game to run it without one, also when BoilR thinks it needs proton
+pub const NATIVE_COMPAT_TOOL: &str = "native";
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct SteamSettings {
     pub location: Option<String>,
@@ -7,4 +12,39 @@ pub struct SteamSettings {
     pub optimize_for_big_picture: bool,
     pub stop_steam: bool,
     pub start_steam: bool,
+    #[serde(default)]
+    pub collection_rules: CollectionRules,
+    #[serde(default)]
+    pub controller_templates: ControllerTemplates,
+    #[serde(default)]
+    pub launch_options: LaunchOptionTemplates,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compat_tools: Vec<GameCompatTool>,
+}
+
+/// A compatibility tool picked on the details page of a game
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GameCompatTool {
+    pub app_id: u32,
+    pub tool: String,
+}
+
+impl SteamSettings {
+    pub fn compat_tool(&self, app_id: u32) -> Option<&str> {
+        self.compat_tools
+            .iter()
+            .find(|c| c.app_id == app_id)
+            .map(|c| c.tool.as_str())
+    }
+
+    /// An empty tool lets BoilR decide again
+    pub fn set_compat_tool(&mut self, app_id: u32, tool: &str) {
+        self.compat_tools.retain(|c| c.app_id != app_id);
+        if !tool.trim().is_empty() {
+            self.compat_tools.push(GameCompatTool {
+                app_id,
+                tool: tool.trim().to_string(),
+            });
+        }
+    }
 }
@@ -127,7 +127,7 @@ pub fn get_default_location() -> Result<String, Box<dyn Error + Sync + Send>> {
     };
     #[cfg(target_os = "linux")]
     let path_string = {
-        let home = std::env::var("HOME")?;
+        let home = crate::config::get_home()?;
         let default_path = Path::new(&home).join(".steam").join("steam");
         if default_path.exists() {
             default_path.to_string_lossy().to_string()
@@ -144,7 +144,7 @@ pub fn get_default_location() -> Result<String, Box<dyn Error + Sync + Send>> {
     };
     #[cfg(target_os = "macos")]
     let path_string = {
-        let home = std::env::var("HOME")?;
+        let home = crate::config::get_home()?;
         String::from(
             Path::new(&home)
                 .join("Library")
@@ -0,0 +1,330 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use futures::{stream, StreamExt};
+use tokio::sync::watch::Sender;
+
+use crate::{
+    platforms::{ExternalId, ExternalPlatform},
+    settings::Settings,
+    steam::{get_installed_games, get_shortcuts_for_user, get_steam_path, get_users_images},
+    steam::{SteamSettings, SteamUsersInfo},
+    sync::{send_progress, IsBoilRShortcut, SyncCancel, SyncProgress},
+};
+
+use super::{
+    download_with_retries, downloader::get_images_for_ids, get_image_extension, save_image_ids,
+    CachedSearch, ImageProcessing, ImageType, PlatformLookup, ToDownload,
+};
+
+/// The images bulk mode fills in, the others are rarely missing from Steam games
+pub const BULK_IMAGE_TYPES: [ImageType; 3] = [ImageType::Hero, ImageType::Logo, ImageType::Grid];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkGameKind {
+    SteamGame,
+    Shortcut,
+}
+
+/// A game that is missing artwork, shown as a preview before anything is downloaded
+#[derive(Debug, Clone)]
+pub struct BulkTarget {
+    pub app_id: u32,
+    pub name: String,
+    pub kind: BulkGameKind,
+    pub missing: Vec<ImageType>,
+}
+
+/// Finds the installed Steam games without library assets and the shortcuts not made by BoilR
+/// that are missing images for the given user.
+///
+/// Banned images are left out, and other shortcuts are only included if
+/// `only_download_boilr_images` is off.
+pub fn find_bulk_targets(settings: &Settings, user: &SteamUsersInfo) -> Vec<BulkTarget> {
+    let known_images = get_users_images(user).unwrap_or_default();
+    let missing_types = |app_id: u32, has_official: &dyn Fn(&ImageType) -> bool| {
+        BULK_IMAGE_TYPES
+            .iter()
+            .filter(|image_type| {
+                !known_images.contains(&image_type.file_name_no_extension(app_id))
+                    && !settings.steamgrid_db.is_image_banned(image_type, app_id)
+                    && !has_official(image_type)
+            })
+            .copied()
+            .collect::<Vec<ImageType>>()
+    };
+
+    let mut targets = vec![];
+    let library_cache = library_cache_folder(&settings.steam);
+    for game in get_installed_games(&settings.steam) {
+        let has_official = |image_type: &ImageType| match &library_cache {
+            Some(library_cache) => has_library_asset(library_cache, game.appid, image_type),
+            None => false,
+        };
+        let missing = missing_types(game.appid, &has_official);
+        if !missing.is_empty() {
+            targets.push(BulkTarget {
+                app_id: game.appid,
+                name: game.name,
+                kind: BulkGameKind::SteamGame,
+                missing,
+            });
+        }
+    }
+
+    if !settings.steamgrid_db.only_download_boilr_images {
+        let shortcut_info = get_shortcuts_for_user(user);
+        for shortcut in shortcut_info
+            .shortcuts
+            .iter()
+            .filter(|s| !s.is_boilr_shortcut() && !s.app_name.is_empty())
+        {
+            let missing = missing_types(shortcut.app_id, &|_| false);
+            if !missing.is_empty() {
+                targets.push(BulkTarget {
+                    app_id: shortcut.app_id,
+                    name: shortcut.app_name.clone(),
+                    kind: BulkGameKind::Shortcut,
+                    missing,
+                });
+            }
+        }
+    }
+    targets
+}
+
+fn library_cache_folder(settings: &SteamSettings) -> Option<PathBuf> {
+    let steam_path = get_steam_path(settings).ok()?;
+    Some(Path::new(&steam_path).join("appcache").join("librarycache"))
+}
+
+/// Steam keeps the official artwork of a game in its library cache,
+/// older clients use flat file names and newer ones a folder per game
+fn has_library_asset(library_cache: &Path, app_id: u32, image_type: &ImageType) -> bool {
+    let file_name = match image_type {
+        ImageType::Hero => "library_hero.jpg",
+        ImageType::Logo => "logo.png",
+        ImageType::Grid => "library_600x900.jpg",
+        _ => return false,
+    };
+    if library_cache
+        .join(format!("{}_{}", app_id, file_name))
+        .exists()
+    {
+        return true;
+    }
+    let game_folder = library_cache.join(app_id.to_string());
+    if game_folder.join(file_name).exists() {
+        return true;
+    }
+    std::fs::read_dir(game_folder)
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|entry| entry.path().join(file_name).exists())
+        })
+        .unwrap_or(false)
+}
+
+/// Downloads the missing images of the given targets into the grid folder of the user.
+/// Returns how many images were downloaded.
+pub async fn download_bulk_artwork(
+    settings: &Settings,
+    user: &SteamUsersInfo,
+    targets: &[BulkTarget],
+    sender: &mut Option<Sender<SyncProgress>>,
+    cancel: &SyncCancel,
+) -> usize {
+    let auth_key = match &settings.steamgrid_db.auth_key {
+        Some(auth_key) => auth_key,
+        None => return 0,
+    };
+    send_progress(sender, SyncProgress::FindingImages);
+    let client = settings.steamgrid_db.client(auth_key);
+    // Steam games can always be looked up by their own id
+    let steam_ids: HashMap<u32, ExternalId> = targets
+        .iter()
+        .filter(|t| t.kind == BulkGameKind::SteamGame)
+        .map(|t| {
+            let external_id = ExternalId::new(ExternalPlatform::Steam, t.app_id.to_string());
+            (t.app_id, external_id)
+        })
+        .collect();
+    let lookup = PlatformLookup::new(&settings.steamgrid_db, auth_key).with_external_ids(steam_ids);
+    let search = CachedSearch::new(&client)
+        .with_platform_lookup(lookup)
+        .with_max_age_days(settings.steamgrid_db.cache_max_age_days);
+    let search = &search;
+    let grid_folder = Path::new(&user.steam_user_data_folder)
+        .join("config")
+        .join("grid");
+
+    let found_games = stream::iter(targets)
+        .map(|target| async move {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            match search.search(target.app_id, &target.name).await {
+                Ok(Some(game_id)) => Some((target, game_id)),
+                _ => None,
+            }
+        })
+        .buffer_unordered(settings.steamgrid_db.concurrent_downloads())
+        .filter_map(|found| async move { found })
+        .collect::<Vec<(&BulkTarget, usize)>>()
+        .await;
+    search.save();
+
+    // Animated images are looked for first when they are preferred, static images fill in the rest
+    let animated_passes: &[bool] = if settings.steamgrid_db.prefer_animated {
+        &[true, false]
+    } else {
+        &[false]
+    };
+    let mut to_downloads = vec![];
+    for image_type in BULK_IMAGE_TYPES {
+        let preferences = settings.steamgrid_db.image_preferences.get(&image_type);
+        let mut games: Vec<(&BulkTarget, usize)> = found_games
+            .iter()
+            .filter(|(target, _)| target.missing.contains(&image_type))
+            .copied()
+            .collect();
+        for download_animated in animated_passes {
+            let mut not_found = vec![];
+            for games in games.chunks(99) {
+                if cancel.is_cancelled() {
+                    break;
+                }
+                let game_ids: Vec<usize> = games.iter().map(|(_, game_id)| *game_id).collect();
+                let images = match get_images_for_ids(
+                    &client,
+                    &game_ids,
+                    &image_type,
+                    *download_animated,
+                    &settings.steamgrid_db,
+                )
+                .await
+                {
+                    Ok(images) => images,
+                    Err(err) => {
+                        println!("Error getting images: {}", err);
+                        not_found.extend_from_slice(games);
+                        continue;
+                    }
+                };
+                for (index, (target, game_id)) in games.iter().enumerate() {
+                    let image = images
+                        .get(index)
+                        .and_then(|image| image.as_ref().ok())
+                        .filter(|image| preferences.accepts(image));
+                    match image {
+                        Some(image) => {
+                            let extension = get_image_extension(&image.mime);
+                            to_downloads.push(ToDownload {
+                                path: grid_folder
+                                    .join(image_type.file_name(target.app_id, extension)),
+                                url: image.url.clone(),
+                                app_name: target.name.clone(),
+                                image_type,
+                                image_id: Some(image.id),
+                            });
+                        }
+                        None => not_found.push((*target, *game_id)),
+                    }
+                }
+            }
+            games = not_found;
+        }
+    }
+
+    let total = to_downloads.len();
+    let completed = &AtomicUsize::new(0);
+    let failed = &AtomicUsize::new(0);
+    let sender = &*sender;
+    let processing = &ImageProcessing::from(&settings.steamgrid_db);
+    let retries = settings.steamgrid_db.download_retries;
+    let downloaded = stream::iter(&to_downloads)
+        .map(|to_download| async move {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            let result = download_with_retries(to_download, retries, processing).await;
+            if let Err(e) = &result {
+                println!("Error downloading {:?}: {}", &to_download.path, e);
+                failed.fetch_add(1, Ordering::SeqCst);
+            }
+            send_progress(
+                sender,
+                SyncProgress::DownloadingImages {
+                    to_download: total,
+                    completed: completed.fetch_add(1, Ordering::SeqCst) + 1,
+                    failed: failed.load(Ordering::SeqCst),
+                    current_game: to_download.app_name.clone(),
+                },
+            );
+            result.ok().map(|_| to_download)
+        })
+        .buffer_unordered(settings.steamgrid_db.concurrent_downloads())
+        .filter_map(|downloaded| async move { downloaded })
+        .collect::<Vec<&ToDownload>>()
+        .await;
+    save_image_ids(downloaded.iter().copied());
+    downloaded.len()
+}
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod tests {
+    use super::*;
+    use crate::steam::get_shortcuts_paths;
+    use crate::sync::fake_steam::{shortcut, FakeSteam};
+    use crate::sync::BOILR_TAG;
+
+    #[test]
+    fn finds_games_missing_artwork() {
+        let steam = FakeSteam::new("bulk_targets");
+        let other = shortcut("Emulator", "/usr/bin/emulator");
+        let mut boilr = shortcut("Imported", "/games/imported");
+        boilr.tags.push(BOILR_TAG.to_string());
+        steam.add_user("100", Some(&[other.clone(), boilr][..]));
+        let grid_folder = steam.user_folder("100").join("config").join("grid");
+        std::fs::create_dir_all(&grid_folder).unwrap();
+        std::fs::write(
+            grid_folder.join(ImageType::Logo.file_name(other.app_id, "png")),
+            "logo",
+        )
+        .unwrap();
+        // Wildermyth is installed and has an official hero in the new cache layout
+        let game_cache = steam
+            .steam
+            .join("appcache")
+            .join("librarycache")
+            .join("763890")
+            .join("abc");
+        std::fs::create_dir_all(&game_cache).unwrap();
+        std::fs::write(game_cache.join("library_hero.jpg"), "hero").unwrap();
+
+        let mut settings = steam.settings();
+        settings
+            .steamgrid_db
+            .set_image_banned(&ImageType::Grid, other.app_id, true);
+        let users = get_shortcuts_paths(&settings.steam).unwrap();
+        let targets = find_bulk_targets(&settings, &users[0]);
+
+        assert_eq!(targets.len(), 2);
+        let wildermyth = &targets[0];
+        assert_eq!(wildermyth.kind, BulkGameKind::SteamGame);
+        assert_eq!(wildermyth.missing, vec![ImageType::Logo, ImageType::Grid]);
+        let emulator = &targets[1];
+        assert_eq!(emulator.kind, BulkGameKind::Shortcut);
+        assert_eq!(emulator.app_id, other.app_id);
+        assert_eq!(emulator.missing, vec![ImageType::Hero]);
+
+        settings.steamgrid_db.only_download_boilr_images = true;
+        let targets = find_bulk_targets(&settings, &users[0]);
+        assert_eq!(targets.len(), 1);
+    }
+}
@@ -0,0 +1,53 @@
+use super::{clear_cache, get_cache_entries, remove_cache_entry, set_cache_entry};
+
+const USAGE: &str = "Usage:
+  boilr --cache list [filter]
+  boilr --cache set <app_id> <steamgriddb_id>
+  boilr --cache delete <app_id>
+  boilr --cache clear";
+
+/// Handles `boilr --cache ...`, the arguments are the ones after `--cache`
+pub fn run_cache_command(args: &[String]) {
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    match args.as_slice() {
+        ["list"] => list_entries(""),
+        ["list", filter] => list_entries(filter),
+        ["set", app_id, grid_id] => match (app_id.parse(), grid_id.parse()) {
+            (Ok(app_id), Ok(grid_id)) => {
+                let name = get_cache_entries()
+                    .into_iter()
+                    .find(|(id, _)| *id == app_id)
+                    .map(|(_, entry)| entry.name)
+                    .unwrap_or_default();
+                set_cache_entry(app_id, name, grid_id);
+                println!("Matched {} to SteamGridDB game {}", app_id, grid_id);
+            }
+            _ => eprintln!("{}", USAGE),
+        },
+        ["delete", app_id] => match app_id.parse() {
+            Ok(app_id) => {
+                if remove_cache_entry(app_id) {
+                    println!("Removed {} from the cache", app_id);
+                } else {
+                    eprintln!("{} is not in the cache", app_id);
+                }
+            }
+            Err(_) => eprintln!("{}", USAGE),
+        },
+        ["clear"] => {
+            clear_cache();
+            println!("Cleared the cache");
+        }
+        _ => eprintln!("{}", USAGE),
+    }
+}
+
+fn list_entries(filter: &str) {
+    let filter = filter.to_lowercase();
+    for (app_id, entry) in get_cache_entries() {
+        if entry.name.to_lowercase().contains(&filter) || app_id.to_string().contains(&filter) {
+            let manual = if entry.manual { " (by hand)" } else { "" };
+            println!("{}\t{}\t{}{}", app_id, entry.grid_id, entry.name, manual);
+        }
+    }
+}
@@ -1,32 +1,136 @@
 use dashmap::DashMap;
-use std::{fs::File, io::Write};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::config::get_cache_file;
+use crate::config::{get_cache_file, get_review_file, load_json_file, save_json_file};
 
-type SearchMap = DashMap<u32, (String, usize)>;
+use super::match_score::{score_results, ScoredMatch, AUTO_ACCEPT_SCORE};
+use super::platform_lookup::PlatformLookup;
+
+type SearchMap = DashMap<u32, CacheEntry>;
+type ReviewMap = DashMap<u32, NeedsReview>;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The SteamGridDB game a shortcut was matched to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// The name the game had when it was matched
+    pub name: String,
+    pub grid_id: usize,
+    /// When the game was matched, in seconds since the unix epoch
+    pub updated: u64,
+    /// Picked by the user, these entries never expire
+    #[serde(default)]
+    pub manual: bool,
+    /// The game was looked up by its platform id before this entry was made
+    #[serde(default)]
+    pub id_checked: bool,
+}
+
+impl CacheEntry {
+    fn new<S: Into<String>>(name: S, grid_id: usize, manual: bool) -> Self {
+        CacheEntry {
+            name: name.into(),
+            grid_id,
+            updated: now(),
+            manual,
+            id_checked: false,
+        }
+    }
+
+    /// Games matched by name before their platform id was known are looked up by that id once
+    fn waits_for_lookup(&self, has_external_id: bool) -> bool {
+        has_external_id && !self.manual && !self.id_checked
+    }
+
+    /// An entry is used as long as the game keeps its name and the entry is not too old
+    fn is_valid_for(&self, name: &str, max_age: Option<u64>, now: u64) -> bool {
+        // Entries added by id alone, from the command line, do not know the name yet
+        if !self.name.is_empty() && self.name != name {
+            return false;
+        }
+        match max_age {
+            Some(max_age) if !self.manual => now.saturating_sub(self.updated) <= max_age,
+            _ => true,
+        }
+    }
+}
+
+/// Older versions of BoilR saved only the name and the grid id
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredEntry {
+    Entry(CacheEntry),
+    Legacy(String, usize),
+}
+
+impl From<StoredEntry> for CacheEntry {
+    fn from(stored: StoredEntry) -> Self {
+        match stored {
+            StoredEntry::Entry(entry) => entry,
+            StoredEntry::Legacy(name, grid_id) => CacheEntry::new(name, grid_id, false),
+        }
+    }
+}
+
+/// A game where no search result was a confident match, the user has to pick one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NeedsReview {
+    pub app_id: u32,
+    pub name: String,
+    pub candidates: Vec<ScoredMatch>,
+}
+
+const REVIEW_CANDIDATES: usize = 10;
 
 pub struct CachedSearch<'a> {
     search_map: SearchMap,
+    review_map: ReviewMap,
     client: &'a steamgriddb_api::Client,
+    lookup: Option<PlatformLookup>,
+    /// Entries older than this many seconds are searched again
+    max_age: Option<u64>,
 }
 
 impl<'a> CachedSearch<'a> {
     pub fn new(client: &steamgriddb_api::Client) -> CachedSearch {
         CachedSearch {
             search_map: get_search_map(),
+            review_map: get_review_map(),
             client,
+            lookup: None,
+            max_age: None,
         }
     }
 
+    /// Search games again when their cache entry is older than the given number of days, 0 keeps entries forever
+    pub fn with_max_age_days(mut self, days: u64) -> Self {
+        self.max_age = match days {
+            0 => None,
+            days => Some(days * SECONDS_PER_DAY),
+        };
+        self
+    }
+
+    /// Look games up by their platform ids before searching by name
+    pub fn with_platform_lookup(mut self, lookup: PlatformLookup) -> Self {
+        self.lookup = Some(lookup);
+        self
+    }
+
     pub fn save(&self) {
         save_search_map(&self.search_map);
+        save_review_map(&self.review_map);
     }
 
     pub fn set_cache<S>(&mut self, app_id: u32, name: S, new_grid_id: usize)
     where
         S: Into<String>,
     {
-        self.search_map.insert(app_id, (name.into(), new_grid_id));
+        self.search_map
+            .insert(app_id, CacheEntry::new(name, new_grid_id, true));
+        self.review_map.remove(&app_id);
         self.save();
     }
 
@@ -38,36 +142,268 @@ impl<'a> CachedSearch<'a> {
     where
         S: AsRef<str> + Into<String>,
     {
-        let cached_result = self.search_map.get(&app_id);
-        if let Some(result) = cached_result {
-            return Ok(Some(result.1));
+        let cached_result = self.search_map.get(&app_id).map(|entry| entry.clone());
+        let has_external_id = self
+            .lookup
+            .as_ref()
+            .map_or(false, |lookup| lookup.has_external_id(app_id));
+        if let Some(entry) = cached_result {
+            if entry.is_valid_for(query.as_ref(), self.max_age, now())
+                && !entry.waits_for_lookup(has_external_id)
+            {
+                return Ok(Some(entry.grid_id));
+            }
+            // The game was renamed, the match is old or the game can be found by id now, look for it again
+            self.search_map.remove(&app_id);
+        }
+        let mut id_checked = false;
+        if let Some(lookup) = &self.lookup {
+            match lookup.find_game(app_id).await {
+                Ok(Some(game_id)) => {
+                    let entry = CacheEntry {
+                        id_checked: true,
+                        ..CacheEntry::new(query, game_id, false)
+                    };
+                    self.search_map.insert(app_id, entry);
+                    self.review_map.remove(&app_id);
+                    return Ok(Some(game_id));
+                }
+                Ok(None) => id_checked = has_external_id,
+                Err(e) => println!("Could not look up {} by id: {}", query.as_ref(), e),
+            }
+        }
+        if self.review_map.contains_key(&app_id) {
+            // Waiting for the user to pick the right game
+            return Ok(None);
         }
         println!("Searching for {}", query.as_ref());
         let search = self.client.search(query.as_ref()).await?;
         if search.is_empty() {
             return Ok(None);
         }
-        let first_item = &search[0];
-        let assumed_id = first_item.id;
-        self.search_map.insert(app_id, (query.into(), assumed_id));
+        let matches = score_results(query.as_ref(), &search);
+        let confident_match = matches
+            .first()
+            .filter(|best| best.score >= AUTO_ACCEPT_SCORE)
+            .map(|best| best.id);
+        if let Some(game_id) = confident_match {
+            let entry = CacheEntry {
+                id_checked,
+                ..CacheEntry::new(query, game_id, false)
+            };
+            self.search_map.insert(app_id, entry);
+            return Ok(Some(game_id));
+        }
+        println!(
+            "No confident match for {}, it needs to be reviewed",
+            query.as_ref()
+        );
+        self.review_map.insert(
+            app_id,
+            NeedsReview {
+                app_id,
+                name: query.into(),
+                candidates: matches.into_iter().take(REVIEW_CANDIDATES).collect(),
+            },
+        );
+        Ok(None)
+    }
+}
+
+/// The games that are waiting for the user to pick a match, sorted by name
+pub fn get_needs_review() -> Vec<NeedsReview> {
+    let mut needs_review: Vec<NeedsReview> = get_review_map()
+        .into_iter()
+        .map(|(_, review)| review)
+        .collect();
+    needs_review.sort_by(|a, b| a.name.cmp(&b.name));
+    needs_review
+}
 
-        Ok(Some(assumed_id))
+fn get_review_map() -> ReviewMap {
+    load_json_file(&get_review_file())
+}
+
+fn save_review_map(review_map: &ReviewMap) {
+    save_json_file(&get_review_file(), review_map, "games to review");
+}
+
+/// All cached matches, sorted by name
+pub fn get_cache_entries() -> Vec<(u32, CacheEntry)> {
+    let mut entries: Vec<(u32, CacheEntry)> = get_search_map().into_iter().collect();
+    entries.sort_by(|a, b| a.1.name.cmp(&b.1.name).then(a.0.cmp(&b.0)));
+    entries
+}
+
+/// Matches a game to a SteamGridDB game by hand
+pub fn set_cache_entry<S: Into<String>>(app_id: u32, name: S, grid_id: usize) {
+    let search_map = get_search_map();
+    search_map.insert(app_id, CacheEntry::new(name, grid_id, true));
+    save_search_map(&search_map);
+    let review_map = get_review_map();
+    if review_map.remove(&app_id).is_some() {
+        save_review_map(&review_map);
     }
 }
 
+/// Forgets the match for a game, returns false if there was none
+pub fn remove_cache_entry(app_id: u32) -> bool {
+    let search_map = get_search_map();
+    let removed = search_map.remove(&app_id).is_some();
+    if removed {
+        save_search_map(&search_map);
+    }
+    removed
+}
+
+/// Forgets all matches, every game is searched again on the next download
+pub fn clear_cache() {
+    save_search_map(&SearchMap::new());
+}
+
 fn get_search_map() -> SearchMap {
     let path = get_cache_file();
-    if path.exists() {
-        let string = std::fs::read_to_string(path).unwrap();
-        serde_json::from_str::<SearchMap>(&string).expect("Failed to parse cache.json")
-    } else {
-        SearchMap::new()
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return SearchMap::new(),
+    };
+    match serde_json::from_str::<DashMap<u32, StoredEntry>>(&content) {
+        Ok(stored) => stored
+            .into_iter()
+            .map(|(app_id, entry)| (app_id, entry.into()))
+            .collect(),
+        Err(e) => {
+            // Keep the broken file around, so nothing is lost if it can be repaired by hand
+            let backup = path.with_extension(format!("json.corrupt-{}", now()));
+            eprintln!(
+                "Could not parse {:?}, moving it to {:?} and starting with an empty cache: {}",
+                path, backup, e
+            );
+            if let Err(e) = std::fs::rename(&path, &backup) {
+                eprintln!("Could not back up {:?}: {}", path, e);
+            }
+            SearchMap::new()
+        }
     }
 }
 
 fn save_search_map(search_map: &SearchMap) {
-    let string = serde_json::to_string(search_map).unwrap();
-    let path = get_cache_file();
-    let mut file = File::create(&path).unwrap();
-    file.write_all(string.as_bytes()).unwrap();
+    save_json_file(&get_cache_file(), search_map, "search cache");
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(target_family = "unix")]
+    use crate::sync::fake_steam::FakeSteam;
+
+    #[test]
+    fn entries_expire_unless_picked_by_hand() {
+        let day = SECONDS_PER_DAY;
+        let entry = CacheEntry {
+            name: "Celeste".to_string(),
+            grid_id: 1,
+            updated: 10 * day,
+            manual: false,
+            id_checked: false,
+        };
+        assert!(entry.is_valid_for("Celeste", Some(30 * day), 20 * day));
+        assert!(!entry.is_valid_for("Celeste", Some(30 * day), 41 * day));
+        assert!(entry.is_valid_for("Celeste", None, 1000 * day));
+
+        let manual = CacheEntry {
+            manual: true,
+            ..entry
+        };
+        assert!(manual.is_valid_for("Celeste", Some(30 * day), 1000 * day));
+    }
+
+    #[test]
+    fn renamed_games_are_not_valid() {
+        let entry = CacheEntry::new("Celeste", 1, true);
+        assert!(!entry.is_valid_for("Celeste Classic", None, now()));
+
+        let unnamed = CacheEntry::new("", 1, true);
+        assert!(unnamed.is_valid_for("Celeste Classic", None, now()));
+    }
+
+    #[test]
+    fn name_matches_are_looked_up_by_id_once() {
+        let entry = CacheEntry::new("Celeste", 1, false);
+        assert!(entry.waits_for_lookup(true));
+        assert!(!entry.waits_for_lookup(false));
+
+        let checked = CacheEntry {
+            id_checked: true,
+            ..entry.clone()
+        };
+        assert!(!checked.waits_for_lookup(true));
+        let manual = CacheEntry {
+            manual: true,
+            ..entry
+        };
+        assert!(!manual.waits_for_lookup(true));
+    }
+
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn loads_legacy_cache_files() {
+        let _steam = FakeSteam::new("legacy_cache");
+        std::fs::write(get_cache_file(), r#"{"42":["Celeste",1234]}"#).unwrap();
+
+        let entries = get_cache_entries();
+        assert_eq!(entries.len(), 1);
+        let (app_id, entry) = &entries[0];
+        assert_eq!(*app_id, 42);
+        assert_eq!(entry.name, "Celeste");
+        assert_eq!(entry.grid_id, 1234);
+        assert!(!entry.manual);
+        assert!(entry.updated > 0);
+    }
+
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn backs_up_corrupt_cache_files() {
+        let steam = FakeSteam::new("corrupt_cache");
+        std::fs::write(get_cache_file(), "{\"42\": [\"Cel").unwrap();
+
+        assert!(get_cache_entries().is_empty());
+        assert!(!get_cache_file().exists());
+        let backups = std::fs::read_dir(&steam.config)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("cache.json.corrupt-")
+            })
+            .count();
+        assert_eq!(backups, 1);
+    }
+
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn edits_and_clears_entries() {
+        let _steam = FakeSteam::new("edit_cache");
+        set_cache_entry(1, "Celeste", 10);
+        set_cache_entry(2, "Animal Well", 20);
+        assert!(remove_cache_entry(1));
+        assert!(!remove_cache_entry(1));
+
+        let entries = get_cache_entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, 2);
+        assert!(entries[0].1.manual);
+
+        clear_cache();
+        assert!(get_cache_entries().is_empty());
+    }
 }
@@ -1,49 +1,75 @@
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{collections::HashMap, path::Path};
 
 use futures::{stream, StreamExt};
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
-use steamgriddb_api::query_parameters::{
-    GridDimentions, MimeType, MimeTypeIcon, MimeTypeLogo, Nsfw,
-};
+use std::fmt::Display;
+use std::time::Duration;
+use steamgriddb_api::query_parameters::{MimeType, MimeTypeIcon, MimeTypeLogo};
 use tokio::sync::watch::Sender; // 0.3.1
 
 use steam_shortcuts_util::shortcut::ShortcutOwned;
 use steamgriddb_api::Client;
 
-use super::CachedSearch;
+use super::image_validation::{validate_image, ImageProcessing, InvalidImage};
+use super::{
+    find_grid_image, find_manual_pick, link_or_copy, load_external_ids, load_manual_picks,
+    CachedSearch, ImageQuery, ManualPick, PlatformLookup,
+};
+use crate::config::{
+    get_failed_downloads_file, get_image_ids_file, load_json_file, save_json_file,
+};
+use crate::platforms::ExternalId;
 use crate::settings::Settings;
 use crate::steam::{get_shortcuts_for_user, get_users_images, SteamUsersInfo};
-use crate::steamgriddb::ImageType;
+use crate::steamgriddb::{ImageType, SteamGridDbSettings};
 use crate::sync::IsBoilRShortcut;
-use crate::sync::SyncProgress;
+use crate::sync::{send_progress, SyncCancel, SyncProgress};
 
 const CONCURRENT_REQUESTS: usize = 10;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
 
 pub async fn download_images_for_users<'b>(
     settings: &Settings,
     users: &[SteamUsersInfo],
     download_animated: bool,
     sender: &mut Option<Sender<SyncProgress>>,
+    cancel: &SyncCancel,
 ) {
     let auth_key = &settings.steamgrid_db.auth_key;
     if let Some(auth_key) = auth_key {
         println!("Checking for game images");
         let start_time = std::time::Instant::now();
-        let client = steamgriddb_api::Client::new(auth_key);
-        let search = CachedSearch::new(&client);
+        let client = settings.steamgrid_db.client(auth_key);
+        let lookup = PlatformLookup::new(&settings.steamgrid_db, auth_key);
+        let search = CachedSearch::new(&client)
+            .with_platform_lookup(lookup)
+            .with_max_age_days(settings.steamgrid_db.cache_max_age_days);
         let search = &search;
         let client = &client;
-        if let Some(sender) = sender {
-            let _ = sender.send(SyncProgress::FindingImages);
+        let manual_picks = &load_manual_picks();
+        let external_ids = &load_external_ids();
+        send_progress(sender, SyncProgress::FindingImages);
+        if settings.steamgrid_db.apply_to_all_users {
+            let shared = share_images_between_users(users, &settings.steamgrid_db);
+            if shared > 0 {
+                println!("Shared {} images between Steam users", shared);
+            }
         }
         let to_downloads = stream::iter(users)
             .map(|user| {
                 let shortcut_info = get_shortcuts_for_user(user);
                 async move {
+                    if cancel.is_cancelled() {
+                        return vec![];
+                    }
                     let known_images = get_users_images(user).unwrap_or_default();
                     let res = search_for_images_to_download(
                         known_images,
@@ -54,6 +80,8 @@ pub async fn download_images_for_users<'b>(
                         download_animated,
                         settings.steam.optimize_for_big_picture,
                         settings,
+                        manual_picks,
+                        external_ids,
                     )
                     .await;
                     res.unwrap_or_default()
@@ -62,36 +90,83 @@ pub async fn download_images_for_users<'b>(
             .buffer_unordered(CONCURRENT_REQUESTS)
             .collect::<Vec<Vec<ToDownload>>>()
             .await;
-        let to_downloads = to_downloads.iter().flatten().collect::<Vec<&ToDownload>>();
+        if cancel.is_cancelled() {
+            search.save();
+            return;
+        }
+        let to_downloads =
+            with_failed_downloads_first(load_failed_downloads(), users, to_downloads);
+        // Users with the same shortcut need the same images, those are only downloaded once
+        let to_downloads = group_by_url(to_downloads);
         let total = to_downloads.len();
         if !to_downloads.is_empty() {
-            if let Some(sender) = sender {
-                let _ = sender.send(SyncProgress::DownloadingImages { to_download: total });
-            }
+            send_progress(
+                sender,
+                SyncProgress::DownloadingImages {
+                    to_download: total,
+                    completed: 0,
+                    failed: 0,
+                    current_game: String::new(),
+                },
+            );
             search.save();
-            stream::iter(&to_downloads)
-                .map(|to_download| async move {
-                    if let Err(e) = download_to_download(to_download).await {
-                        println!("Error downloading {:?}: {}", &to_download.path, e);
+            let completed = &AtomicUsize::new(0);
+            let failed = &AtomicUsize::new(0);
+            let sender = &*sender;
+            let retries = settings.steamgrid_db.download_retries;
+            let processing = &ImageProcessing::from(&settings.steamgrid_db);
+            let results = stream::iter(&to_downloads)
+                .map(|same_image| async move {
+                    let (to_download, copies) = same_image.split_first()?;
+                    // Downloads that have not started yet are skipped when cancelled
+                    if cancel.is_cancelled() {
+                        return None;
+                    }
+                    let result = download_with_retries(to_download, retries, processing).await;
+                    match &result {
+                        Ok(path) => {
+                            for copy in copies {
+                                let target = image_path_like(&copy.path, path);
+                                if let Err(e) = link_or_copy(path, &target) {
+                                    println!("Error copying {:?} to {:?}: {}", path, target, e);
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            println!("Error downloading {:?}: {}", &to_download.path, e);
+                            failed.fetch_add(1, Ordering::SeqCst);
+                        }
                     }
+                    let completed = completed.fetch_add(1, Ordering::SeqCst) + 1;
+                    send_progress(
+                        sender,
+                        SyncProgress::DownloadingImages {
+                            to_download: total,
+                            completed,
+                            failed: failed.load(Ordering::SeqCst),
+                            current_game: to_download.app_name.clone(),
+                        },
+                    );
+                    Some((same_image, result.is_ok()))
                 })
-                .buffer_unordered(CONCURRENT_REQUESTS)
-                .collect::<Vec<()>>()
+                .buffer_unordered(settings.steamgrid_db.concurrent_downloads())
+                .filter_map(|result| async move { result })
+                .collect::<Vec<(&Vec<ToDownload>, bool)>>()
                 .await;
+            let (downloaded, failed_downloads): (Vec<_>, Vec<_>) =
+                results.into_iter().partition(|(_, downloaded)| *downloaded);
+            let failed_downloads: Vec<&ToDownload> = failed_downloads
+                .into_iter()
+                .flat_map(|(same_image, _)| same_image)
+                .collect();
+            save_failed_downloads(&failed_downloads);
+            save_image_ids(
+                downloaded
+                    .into_iter()
+                    .flat_map(|(same_image, _)| same_image),
+            );
             let duration = start_time.elapsed();
             println!("Finished getting images in: {:?}", duration);
-
-            //Validate that the downloads where ok
-            for to_download in to_downloads {
-                let file_length = std::fs::metadata(&to_download.path)
-                    .map(|m| m.len())
-                    .unwrap_or_default();
-                if file_length < 2 {
-                    // Image is too small, something went wrong
-                    //Try to delete file again, don't care if it fails
-                    let _ = std::fs::remove_file(&to_download.path);
-                }
-            }
         } else {
             println!("No images needed");
         }
@@ -128,8 +203,9 @@ pub struct PublicGameResponse {
     data: Option<PublicGameResponseData>,
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn search_for_images_to_download(
-    known_images: Vec<String>,
+    mut known_images: Vec<String>,
     user_data_folder: &str,
     shortcuts: &[ShortcutOwned],
     search: &CachedSearch<'_>,
@@ -137,6 +213,8 @@ async fn search_for_images_to_download(
     download_animated: bool,
     download_big_picture: bool,
     settings: &Settings,
+    manual_picks: &[ManualPick],
+    external_ids: &HashMap<u32, ExternalId>,
 ) -> Result<Vec<ToDownload>, Box<dyn Error>> {
     let types = {
         let mut types = vec![
@@ -152,6 +230,35 @@ async fn search_for_images_to_download(
         types
     };
 
+    let grid_folder = Path::new(user_data_folder).join("config").join("grid");
+    // Images picked by hand are downloaded again instead of searching for new ones
+    let mut to_download = vec![];
+    for shortcut in shortcuts {
+        for image_type in &types {
+            let file_stem = image_type.file_name_no_extension(shortcut.app_id);
+            if known_images.contains(&file_stem)
+                || settings
+                    .steamgrid_db
+                    .is_image_banned(image_type, shortcut.app_id)
+            {
+                continue;
+            }
+            let external_id = external_ids.get(&shortcut.app_id);
+            if let Some(pick) =
+                find_manual_pick(manual_picks, &shortcut.app_name, external_id, image_type)
+            {
+                to_download.push(ToDownload {
+                    path: grid_folder.join(image_type.file_name(shortcut.app_id, &pick.extension)),
+                    url: pick.url.clone(),
+                    app_name: shortcut.app_name.clone(),
+                    image_type: *image_type,
+                    image_id: Some(pick.image_id),
+                });
+                known_images.push(file_stem);
+            }
+        }
+    }
+
     let shortcuts_to_search_for = shortcuts
         .iter()
         .filter(|s| !settings.steamgrid_db.only_download_boilr_images || s.is_boilr_shortcut())
@@ -165,7 +272,7 @@ async fn search_for_images_to_download(
         });
     let shortcuts_to_search_for: Vec<&ShortcutOwned> = shortcuts_to_search_for.collect();
     if shortcuts_to_search_for.is_empty() {
-        return Ok(vec![]);
+        return Ok(to_download);
     }
     let mut search_results = HashMap::new();
     let search_results_a = stream::iter(shortcuts_to_search_for)
@@ -186,49 +293,64 @@ async fn search_for_images_to_download(
         search_results.insert(app_id, search);
     }
 
-    let mut to_download = vec![];
-    let grid_folder = Path::new(user_data_folder).join("config").join("grid");
     for image_type in types {
         let images_needed = shortcuts
             .iter()
             .filter(|s| search_results.contains_key(&s.app_id))
             .filter(|s| !settings.steamgrid_db.is_image_banned(&image_type, s.app_id))
             .filter(|s| !known_images.contains(&image_type.file_name_no_extension(s.app_id)));
-        let image_ids: Vec<usize> = images_needed
-            .clone()
-            .filter_map(|s| search_results.get(&s.app_id))
-            .copied()
+        // Shortcuts and game ids are kept as pairs, so every batch lines up with the images it gets back
+        let games: Vec<(&ShortcutOwned, usize)> = images_needed
+            .filter_map(|s| search_results.get(&s.app_id).map(|game_id| (s, *game_id)))
             .collect();
 
-        let shortcuts: Vec<&ShortcutOwned> = images_needed.collect();
-
-        for image_ids in image_ids.chunks(99) {
-            let image_search_result =
-                get_images_for_ids(client, image_ids, &image_type, download_animated, settings.steamgrid_db.allow_nsfw).await;
+        for games in games.chunks(99) {
+            let image_ids: Vec<usize> = games.iter().map(|(_, game_id)| *game_id).collect();
+            let image_search_result = get_images_for_ids(
+                client,
+                &image_ids,
+                &image_type,
+                download_animated,
+                &settings.steamgrid_db,
+            )
+            .await;
             match image_search_result {
                 Ok(images) => {
+                    let preferences = settings.steamgrid_db.image_preferences.get(&image_type);
                     let images = images
                         .iter()
-                        .enumerate()
-                        .map(|(index, image)| (image, shortcuts[index], image_ids[index]));
+                        .zip(games.iter())
+                        .map(|(image, (shortcut, game_id))| (image, *shortcut, *game_id));
                     let download_for_this_type = stream::iter(images)
                         .filter_map(|(image, shortcut, game_id)| {
-                            let extension = image
+                            // Images that do not match the preferences are replaced like missing ones
+                            let image = image
                                 .as_ref()
+                                .ok()
+                                .filter(|image| preferences.accepts(image));
+                            let extension = image
                                 .map(|image| get_image_extension(&image.mime))
                                 .unwrap_or("png");
                             let path =
                                 grid_folder.join(image_type.file_name(shortcut.app_id, extension));
                             async move {
                                 let image_url = match image {
-                                    Ok(img) => Some(img.url.clone()),
-                                    Err(_) => get_steam_image_url(game_id, &image_type).await,
+                                    Some(img) => Some(img.url.clone()),
+                                    None => {
+                                        get_steam_image_url(
+                                            game_id,
+                                            &image_type,
+                                            &settings.steamgrid_db,
+                                        )
+                                        .await
+                                    }
                                 };
                                 image_url.map(|url| ToDownload {
                                     path,
                                     url,
                                     app_name: shortcut.app_name.clone(),
                                     image_type,
+                                    image_id: image.map(|img| img.id),
                                 })
                             }
                         })
@@ -256,85 +378,86 @@ pub fn get_image_extension(mime_type: &steamgriddb_api::images::MimeTypes) -> &'
     }
 }
 
-async fn get_images_for_ids(
+pub(super) async fn get_images_for_ids(
     client: &Client,
     image_ids: &[usize],
     image_type: &ImageType,
     download_animated: bool,
-    allow_nsfw: bool,
+    settings: &SteamGridDbSettings,
 ) -> Result<Vec<steamgriddb_api::response::SteamGridDbResult<steamgriddb_api::images::Image>>, String>
 {
-    let query_type = get_query_type(download_animated, image_type, allow_nsfw);
+    let query = ImageQuery::new(download_animated, image_type, settings);
+    let query_type = get_query_type(&query);
 
     let image_search_result = client.get_images_for_ids(image_ids, &query_type).await;
 
     image_search_result.map_err(|e| format!("Image search failed {:?}", e))
 }
 
-const BIG_PICTURE_DIMS: [GridDimentions; 2] = [GridDimentions::D920x430, GridDimentions::D460x215];
-
-pub fn get_query_type(
-    download_animated: bool,
-    image_type: &ImageType,
-    allow_nsfw: bool,
-) -> steamgriddb_api::QueryType {
-    let anymation_type = if download_animated {
-        Some(&[steamgriddb_api::query_parameters::AnimtionType::Animated][..])
-    } else {
-        None
-    };
-    use steamgriddb_api::query_parameters::GridQueryParameters;
-    let allow_nsfw_enum = match allow_nsfw {
-        true => Some(&Nsfw::Any),
-        false => Some(&Nsfw::False),
-    };
-    let big_picture_parameters = GridQueryParameters {
-        dimentions: Some(&BIG_PICTURE_DIMS),
-        types: anymation_type,
-        nsfw: allow_nsfw_enum,
-        ..Default::default()
-    };
-    use steamgriddb_api::query_parameters::HeroQueryParameters;
-    let hero_parameters = HeroQueryParameters {
-        types: anymation_type,
-        nsfw: allow_nsfw_enum,
-        ..Default::default()
+pub fn get_query_type(query: &ImageQuery) -> steamgriddb_api::QueryType<'_> {
+    use steamgriddb_api::query_parameters::{
+        GridQueryParameters, HeroQueryParameters, IconQueryParameters, LogoQueryParameters,
     };
+    let types = non_empty(&query.animation);
+    let nsfw = Some(&query.nsfw);
+    let humor = Some(&query.humor);
     let grid_parameters = GridQueryParameters {
-        types: anymation_type,
-        nsfw: allow_nsfw_enum,
-        ..Default::default()
-    };
-    use steamgriddb_api::query_parameters::LogoQueryParameters;
-    let logo_parameters = LogoQueryParameters {
-        types: anymation_type,
-        nsfw: allow_nsfw_enum,
-        ..Default::default()
+        styles: non_empty(&query.grid_styles),
+        dimentions: non_empty(&query.grid_dimensions),
+        mimes: non_empty(&query.mimes),
+        types,
+        nsfw,
+        humor,
     };
+    match query.image_type {
+        ImageType::Hero => steamgriddb_api::QueryType::Hero(Some(HeroQueryParameters {
+            styles: non_empty(&query.hero_styles),
+            dimentions: non_empty(&query.hero_dimensions),
+            mimes: non_empty(&query.mimes),
+            types,
+            nsfw,
+            humor,
+        })),
+        ImageType::Grid | ImageType::WideGrid | ImageType::BigPicture => {
+            steamgriddb_api::QueryType::Grid(Some(grid_parameters))
+        }
+        ImageType::Logo => steamgriddb_api::QueryType::Logo(Some(LogoQueryParameters {
+            styles: non_empty(&query.logo_styles),
+            mimes: non_empty(&query.logo_mimes),
+            types,
+            nsfw,
+            humor,
+        })),
+        ImageType::Icon => steamgriddb_api::QueryType::Icon(Some(IconQueryParameters {
+            styles: non_empty(&query.icon_styles),
+            mimes: non_empty(&query.icon_mimes),
+            nsfw,
+            humor,
+            ..Default::default()
+        })),
+    }
+}
 
-    use steamgriddb_api::query_parameters::IconQueryParameters;
-    let icon_parameters = IconQueryParameters {
-        nsfw: allow_nsfw_enum,
-        ..Default::default()
-    };
-    let query_type = match image_type {
-        ImageType::Hero => steamgriddb_api::QueryType::Hero(Some(hero_parameters)),
-        ImageType::BigPicture => steamgriddb_api::QueryType::Grid(Some(big_picture_parameters)),
-        ImageType::Grid => steamgriddb_api::QueryType::Grid(Some(grid_parameters)),
-        ImageType::WideGrid => steamgriddb_api::QueryType::Grid(Some(big_picture_parameters)),
-        ImageType::Logo => steamgriddb_api::QueryType::Logo(Some(logo_parameters)),
-        ImageType::Icon => steamgriddb_api::QueryType::Icon(Some(icon_parameters)),
-    };
-    query_type
+/// SteamGridDB treats a missing parameter as "everything"
+fn non_empty<T>(values: &[T]) -> Option<&[T]> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values)
+    }
 }
 
-async fn get_steam_image_url(game_id: usize, image_type: &ImageType) -> Option<String> {
+async fn get_steam_image_url(
+    game_id: usize,
+    image_type: &ImageType,
+    settings: &SteamGridDbSettings,
+) -> Option<String> {
     if let ImageType::Icon = image_type {
-        if let Some(url) = get_steam_icon_url(game_id).await {
+        if let Some(url) = get_steam_icon_url(game_id, settings).await {
             return Some(url);
         }
     }
-    let steamgriddb_page_url = format!("https://www.steamgriddb.com/api/public/game/{}/", game_id);
+    let steamgriddb_page_url = settings.public_game_url(game_id);
     let response = reqwest::get(steamgriddb_page_url).await;
     if let Ok(response) = response {
         let text_response = response.json::<PublicGameResponse>().await;
@@ -350,15 +473,15 @@ async fn get_steam_image_url(game_id: usize, image_type: &ImageType) -> Option<S
             if let (Some(Some(Some(steam_app_id))), Some(Some(Some(Some(Some(mtime)))))) =
                 (game_id, mtime)
             {
-                return Some(image_type.steam_url(steam_app_id, mtime));
+                return Some(image_type.steam_url(settings.steam_cdn_url(), steam_app_id, mtime));
             }
         }
     }
     None
 }
 
-async fn get_steam_icon_url(game_id: usize) -> Option<String> {
-    let steamgriddb_page_url = format!("https://www.steamgriddb.com/api/public/game/{}/", game_id);
+async fn get_steam_icon_url(game_id: usize, settings: &SteamGridDbSettings) -> Option<String> {
+    let steamgriddb_page_url = settings.public_game_url(game_id);
     let response = reqwest::get(steamgriddb_page_url).await;
     if let Ok(response) = response {
         let text_response = response.json::<PublicGameResponse>().await;
@@ -374,37 +497,656 @@ async fn get_steam_icon_url(game_id: usize) -> Option<String> {
             if let (Some(Some(Some(steam_app_id))), Some(Some(Some(Some(Some(mtime)))))) =
                 (game_id, mtime)
             {
-                return Some(icon_url(&steam_app_id, &mtime));
+                return Some(icon_url(settings.steam_cdn_url(), &steam_app_id, &mtime));
             }
         }
     }
     None
 }
 
-fn icon_url(steam_app_id: &str, icon_id: &str) -> String {
+fn icon_url(cdn_url: &str, steam_app_id: &str, icon_id: &str) -> String {
     format!(
-        "https://cdn.cloudflare.steamstatic.com/steamcommunity/public/images/apps/{}/{}.ico",
-        steam_app_id, icon_id
+        "{}/steamcommunity/public/images/apps/{}/{}.ico",
+        cdn_url, steam_app_id, icon_id
     )
 }
 
-pub async fn download_to_download(to_download: &ToDownload) -> Result<(), Box<dyn Error>> {
+/// Downloads a single image, the file is first written next to the target
+/// and only moved in place once it is known to be valid.
+/// Returns where the image was written, the extension can change when the image is processed.
+pub async fn download_to_download(
+    to_download: &ToDownload,
+    processing: &ImageProcessing,
+) -> Result<PathBuf, DownloadError> {
     println!(
         "Downloading {:?} for {} to {:?}",
         to_download.image_type, to_download.app_name, to_download.path
     );
-    let path = &to_download.path;
-    let url = &to_download.url;
-    let mut file = File::create(path).unwrap();
-    let response = reqwest::get(url).await?;
+    let response = reqwest::get(&to_download.url).await?;
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .map(Duration::from_secs);
+        return Err(DownloadError::RateLimited { retry_after });
+    }
+    if !status.is_success() {
+        return Err(DownloadError::Status(status));
+    }
     let content = response.bytes().await?;
-    file.write_all(&content).unwrap();
-    Ok(())
+    if content.len() < 2 {
+        // Image is too small, something went wrong
+        return Err(DownloadError::TooSmall);
+    }
+    let image = validate_image(&content, &to_download.image_type, processing)?;
+    let path = match image.extension {
+        Some(extension) => to_download.path.with_extension(extension),
+        None => to_download.path.clone(),
+    };
+    let temp_path = temp_download_path(&path);
+    let write_result = File::create(&temp_path)
+        .and_then(|mut file| file.write_all(&image.bytes))
+        .and_then(|_| std::fs::rename(&temp_path, &path));
+    if let Err(e) = write_result {
+        //Try to clean up, don't care if it fails
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(path)
+}
+
+/// Downloads an image, retrying failed attempts with an exponential backoff.
+/// If SteamGridDB tells us to slow down, we wait as long as it asks for.
+pub async fn download_with_retries(
+    to_download: &ToDownload,
+    retries: usize,
+    processing: &ImageProcessing,
+) -> Result<PathBuf, DownloadError> {
+    let mut attempt = 0;
+    loop {
+        match download_to_download(to_download, processing).await {
+            Err(e) if attempt < retries && e.is_retryable() => {
+                let backoff = RETRY_BASE_DELAY * 2u32.pow(attempt.min(10) as u32);
+                let wait = match &e {
+                    DownloadError::RateLimited {
+                        retry_after: Some(retry_after),
+                    } => *retry_after,
+                    _ => backoff,
+                }
+                .min(RETRY_MAX_DELAY);
+                println!(
+                    "Download of {:?} failed ({}), retrying in {:?}",
+                    to_download.path, e, wait
+                );
+                tokio::time::sleep(wait).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// The download groups that share the same url, the first one of each group is the one downloaded
+fn group_by_url(to_downloads: Vec<ToDownload>) -> Vec<Vec<ToDownload>> {
+    let mut groups: Vec<Vec<ToDownload>> = vec![];
+    let mut group_by_url = HashMap::new();
+    for to_download in to_downloads {
+        match group_by_url.get(&to_download.url) {
+            Some(index) => groups[*index].push(to_download),
+            None => {
+                group_by_url.insert(to_download.url.clone(), groups.len());
+                groups.push(vec![to_download]);
+            }
+        }
+    }
+    groups
+}
+
+/// The target path with the extension the downloaded image ended up with
+fn image_path_like(target: &Path, downloaded: &Path) -> PathBuf {
+    match downloaded.extension() {
+        Some(extension) => target.with_extension(extension),
+        None => target.to_path_buf(),
+    }
+}
+
+/// Puts an image in the grid folders of other users, replacing the images they had for it.
+/// Returns how many users got the image.
+pub fn share_image(image: &Path, users: &[SteamUsersInfo]) -> usize {
+    let file_name = match image.file_name() {
+        Some(file_name) => file_name,
+        None => return 0,
+    };
+    let mut shared = 0;
+    for user in users {
+        let grid_folder = Path::new(&user.steam_user_data_folder)
+            .join("config")
+            .join("grid");
+        let target = grid_folder.join(file_name);
+        if target == image {
+            continue;
+        }
+        let result =
+            std::fs::create_dir_all(&grid_folder).and_then(|_| link_or_copy(image, &target));
+        match result {
+            Ok(()) => shared += 1,
+            Err(e) => println!("Error copying {:?} to {:?}: {}", image, target, e),
+        }
+    }
+    shared
+}
+
+/// Gives every user the images another user already has for the same shortcut
+fn share_images_between_users(users: &[SteamUsersInfo], settings: &SteamGridDbSettings) -> usize {
+    let grid_folders: Vec<PathBuf> = users
+        .iter()
+        .map(|user| {
+            Path::new(&user.steam_user_data_folder)
+                .join("config")
+                .join("grid")
+        })
+        .collect();
+    let mut shared = 0;
+    for (user, grid_folder) in users.iter().zip(&grid_folders) {
+        let known_images = get_users_images(user).unwrap_or_default();
+        let shortcut_info = get_shortcuts_for_user(user);
+        for shortcut in &shortcut_info.shortcuts {
+            for image_type in ImageType::all() {
+                let file_stem = image_type.file_name_no_extension(shortcut.app_id);
+                if known_images.contains(&file_stem)
+                    || settings.is_image_banned(image_type, shortcut.app_id)
+                {
+                    continue;
+                }
+                let source = grid_folders
+                    .iter()
+                    .filter(|other| *other != grid_folder)
+                    .find_map(|other| find_grid_image(other, shortcut.app_id, image_type));
+                if let Some(source) = source {
+                    let target = grid_folder.join(source.file_name().unwrap_or_default());
+                    match link_or_copy(&source, &target) {
+                        Ok(()) => shared += 1,
+                        Err(e) => println!("Error copying {:?} to {:?}: {}", source, target, e),
+                    }
+                }
+            }
+        }
+    }
+    shared
+}
+
+fn temp_download_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".download");
+    path.with_file_name(file_name)
+}
+
+/// Puts the downloads that failed last time first, so they are retried before anything else.
+/// Failed downloads that are no longer needed are dropped.
+fn with_failed_downloads_first(
+    failed_downloads: Vec<ToDownload>,
+    users: &[SteamUsersInfo],
+    to_downloads: Vec<Vec<ToDownload>>,
+) -> Vec<ToDownload> {
+    let mut result: Vec<ToDownload> = failed_downloads
+        .into_iter()
+        .filter(|d| !d.path.exists())
+        .filter(|d| {
+            users
+                .iter()
+                .any(|u| d.path.starts_with(&u.steam_user_data_folder))
+        })
+        .collect();
+    for to_download in to_downloads.into_iter().flatten() {
+        if !result.iter().any(|d| d.path == to_download.path) {
+            result.push(to_download);
+        }
+    }
+    result
+}
+
+/// The SteamGridDB image ids of the images in the grid folders, by file name without extension
+pub fn load_image_ids() -> HashMap<String, u32> {
+    load_json_file(&get_image_ids_file())
+}
+
+/// Remembers which SteamGridDB images were downloaded, images from other sources forget the old id
+pub fn save_image_ids<'a, I>(downloaded: I)
+where
+    I: IntoIterator<Item = &'a ToDownload>,
+{
+    let mut image_ids = load_image_ids();
+    let mut changed = false;
+    for download in downloaded {
+        let key = download
+            .path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        changed = true;
+        match download.image_id {
+            Some(image_id) => image_ids.insert(key, image_id),
+            None => image_ids.remove(&key),
+        };
+    }
+    if changed {
+        save_json_file(&get_image_ids_file(), &image_ids, "image ids");
+    }
+}
+
+fn load_failed_downloads() -> Vec<ToDownload> {
+    load_json_file(&get_failed_downloads_file())
+}
+
+fn save_failed_downloads(failed_downloads: &[&ToDownload]) {
+    let path = get_failed_downloads_file();
+    if failed_downloads.is_empty() {
+        let _ = std::fs::remove_file(path);
+        return;
+    }
+    save_json_file(&path, failed_downloads, "failed downloads");
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ToDownload {
     pub path: PathBuf,
     pub url: String,
     pub app_name: String,
     pub image_type: ImageType,
+    /// The SteamGridDB image, not set for images from Steam
+    #[serde(default)]
+    pub image_id: Option<u32>,
+}
+
+#[derive(Debug)]
+pub enum DownloadError {
+    RateLimited { retry_after: Option<Duration> },
+    Status(StatusCode),
+    TooSmall,
+    InvalidImage(InvalidImage),
+    Request(reqwest::Error),
+    Io(std::io::Error),
+}
+
+impl DownloadError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            DownloadError::RateLimited { .. } | DownloadError::Request(_) => true,
+            DownloadError::Status(status) => status.is_server_error(),
+            DownloadError::TooSmall | DownloadError::InvalidImage(_) | DownloadError::Io(_) => {
+                false
+            }
+        }
+    }
+}
+
+impl Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadError::RateLimited { .. } => write!(f, "rate limited by server"),
+            DownloadError::Status(status) => write!(f, "server responded with {}", status),
+            DownloadError::TooSmall => write!(f, "downloaded image was empty"),
+            DownloadError::InvalidImage(e) => write!(f, "{}", e),
+            DownloadError::Request(e) => write!(f, "{}", e),
+            DownloadError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for DownloadError {}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(e: reqwest::Error) -> Self {
+        DownloadError::Request(e)
+    }
+}
+
+impl From<InvalidImage> for DownloadError {
+    fn from(e: InvalidImage) -> Self {
+        DownloadError::InvalidImage(e)
+    }
+}
+
+impl From<std::io::Error> for DownloadError {
+    fn from(e: std::io::Error) -> Self {
+        DownloadError::Io(e)
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod tests {
+    use super::*;
+    use crate::platforms::{ExternalId, ExternalPlatform};
+    use crate::steam::get_shortcuts_paths;
+    use crate::steamgriddb::{save_manual_pick, MockData, MockGame, MockServer};
+    use crate::sync::fake_steam::{shortcut, FakeSteam};
+
+    const AUTOMATIC_TYPES: [ImageType; 5] = [
+        ImageType::Logo,
+        ImageType::Hero,
+        ImageType::Grid,
+        ImageType::WideGrid,
+        ImageType::Icon,
+    ];
+
+    async fn download_with_mock(steam: &FakeSteam, data: MockData) -> MockServer {
+        let server = MockServer::start(data).await.unwrap();
+        download_with_server(steam, &server).await;
+        server
+    }
+
+    async fn download_with_server(steam: &FakeSteam, server: &MockServer) {
+        let mut settings = steam.settings();
+        settings.steamgrid_db.enabled = true;
+        settings.steamgrid_db.auth_key = Some("mock".to_string());
+        settings.steamgrid_db.base_url = Some(server.base_url.clone());
+        settings.steamgrid_db.steam_cdn_url = Some(server.base_url.clone());
+        let users = get_shortcuts_paths(&settings.steam).unwrap();
+        download_images_for_users(&settings, &users, false, &mut None, &SyncCancel::default())
+            .await;
+    }
+
+    fn grid_folder(steam: &FakeSteam) -> PathBuf {
+        steam.user_folder("100").join("config").join("grid")
+    }
+
+    fn count_requests(server: &MockServer, prefix: &str) -> usize {
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.starts_with(prefix))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn downloads_images_in_batches_of_99() {
+        let steam = FakeSteam::new("batches");
+        let shortcuts: Vec<ShortcutOwned> = (0..120)
+            .map(|i| shortcut(&format!("Game {}", i), &format!("/games/{}", i)))
+            .collect();
+        steam.add_user("100", Some(&shortcuts[..]));
+        let games = (0..120)
+            .map(|i| MockGame::new(i + 1, format!("Game {}", i)))
+            .collect();
+
+        let server = download_with_mock(&steam, MockData::with_games(games)).await;
+
+        assert_eq!(count_requests(&server, "/api/v2/search/autocomplete/"), 120);
+        assert_eq!(count_requests(&server, "/api/v2/logos/game/"), 2);
+        assert_eq!(count_requests(&server, "/api/v2/heroes/game/"), 2);
+        assert_eq!(count_requests(&server, "/api/v2/icons/game/"), 2);
+        //Both grid and wide grid use the grids endpoint
+        assert_eq!(count_requests(&server, "/api/v2/grids/game/"), 4);
+        let grid_folder = grid_folder(&steam);
+        for shortcut in &shortcuts {
+            for image_type in AUTOMATIC_TYPES {
+                let path = grid_folder.join(image_type.file_name(shortcut.app_id, "png"));
+                assert!(path.exists(), "Missing {:?}", path);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_steam_images() {
+        let steam = FakeSteam::new("fallback");
+        let with_images = shortcut("Has Images", "/games/has_images");
+        let steam_game = shortcut("Steam Game", "/games/steam_game");
+        steam.add_user("100", Some(&[with_images, steam_game.clone()][..]));
+        let mut mock_steam_game = MockGame::new(2, "Steam Game");
+        mock_steam_game.has_images = false;
+        mock_steam_game.steam_app_id = Some("763890".to_string());
+        let games = vec![MockGame::new(1, "Has Images"), mock_steam_game];
+
+        let server = download_with_mock(&steam, MockData::with_games(games)).await;
+
+        assert!(count_requests(&server, "/api/public/game/2/") > 0);
+        assert_eq!(count_requests(&server, "/api/public/game/1/"), 0);
+        assert_eq!(
+            count_requests(&server, "/steam/apps/763890/library_hero.jpg"),
+            1
+        );
+        let hero = grid_folder(&steam).join(ImageType::Hero.file_name(steam_game.app_id, "png"));
+        assert!(hero.exists());
+    }
+
+    #[tokio::test]
+    async fn removes_images_that_are_too_small() {
+        let steam = FakeSteam::new("small_files");
+        let good = shortcut("Good Game", "/games/good");
+        let broken = shortcut("Broken Game", "/games/broken");
+        steam.add_user("100", Some(&[good.clone(), broken.clone()][..]));
+        let mut broken_mock = MockGame::new(2, "Broken Game");
+        broken_mock.image_bytes = Some(vec![0]);
+        let games = vec![MockGame::new(1, "Good Game"), broken_mock];
+
+        download_with_mock(&steam, MockData::with_games(games)).await;
+
+        let grid_folder = grid_folder(&steam);
+        for image_type in AUTOMATIC_TYPES {
+            assert!(grid_folder
+                .join(image_type.file_name(good.app_id, "png"))
+                .exists());
+            assert!(!grid_folder
+                .join(image_type.file_name(broken.app_id, "png"))
+                .exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_downloads_that_are_not_images() {
+        let steam = FakeSteam::new("not_images");
+        let game = shortcut("Error Page Game", "/games/error_page");
+        steam.add_user("100", Some(&[game.clone()][..]));
+        let mut mock = MockGame::new(1, "Error Page Game");
+        mock.image_bytes = Some(b"<html><body>Bad gateway</body></html>".to_vec());
+
+        download_with_mock(&steam, MockData::with_games(vec![mock])).await;
+
+        let grid_folder = grid_folder(&steam);
+        for image_type in AUTOMATIC_TYPES {
+            assert!(!grid_folder
+                .join(image_type.file_name(game.app_id, "png"))
+                .exists());
+        }
+        assert_eq!(load_failed_downloads().len(), AUTOMATIC_TYPES.len());
+    }
+
+    #[tokio::test]
+    async fn looks_up_games_by_platform_id_before_searching() {
+        let steam = FakeSteam::new("platform_ids");
+        let game = shortcut("Tunic", "/games/tunic");
+        let unknown = shortcut("Unknown Game", "/games/unknown");
+        steam.add_user("100", Some(&[game.clone(), unknown.clone()][..]));
+        // A name search would find the demo, which has no images
+        let mut demo = MockGame::new(1, "Tunic");
+        demo.has_images = false;
+        let mut full_game = MockGame::new(2, "Tunic (Full Game)");
+        full_game.platform_ids = vec![("gog".to_string(), "1234".to_string())];
+        let games = vec![demo, full_game, MockGame::new(3, "Unknown Game")];
+        crate::steamgriddb::save_external_ids(HashMap::from([
+            (game.app_id, ExternalId::new(ExternalPlatform::Gog, "1234")),
+            (
+                unknown.app_id,
+                ExternalId::new(ExternalPlatform::Egs, "missing"),
+            ),
+        ]));
+
+        let server = download_with_mock(&steam, MockData::with_games(games)).await;
+
+        assert_eq!(count_requests(&server, "/api/v2/games/gog/1234"), 1);
+        assert_eq!(
+            count_requests(&server, "/api/v2/search/autocomplete/Tunic"),
+            0
+        );
+        // Games SteamGridDB does not know by id fall back to a name search
+        assert_eq!(count_requests(&server, "/api/v2/games/egs/missing"), 1);
+        assert_eq!(
+            count_requests(&server, "/api/v2/search/autocomplete/Unknown"),
+            1
+        );
+        let grid_folder = grid_folder(&steam);
+        for image_type in AUTOMATIC_TYPES {
+            let path = grid_folder.join(image_type.file_name(game.app_id, "png"));
+            assert!(path.exists(), "Missing {:?}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn uncertain_matches_are_queued_for_review() {
+        let steam = FakeSteam::new("needs_review");
+        let game = shortcut("Tunic", "/games/tunic");
+        steam.add_user("100", Some(&[game.clone()][..]));
+        let games = vec![MockGame::new(1, "Tunic Demo")];
+        let data = MockData {
+            games,
+            fuzzy_search: true,
+            ..Default::default()
+        };
+
+        download_with_mock(&steam, data).await;
+
+        let grid_folder = grid_folder(&steam);
+        for image_type in AUTOMATIC_TYPES {
+            assert!(!grid_folder
+                .join(image_type.file_name(game.app_id, "png"))
+                .exists());
+        }
+        let needs_review = crate::steamgriddb::get_needs_review();
+        assert_eq!(needs_review.len(), 1);
+        assert_eq!(needs_review[0].app_id, game.app_id);
+        assert_eq!(needs_review[0].candidates[0].name, "Tunic Demo");
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limited_downloads() {
+        let steam = FakeSteam::new("rate_limited");
+        let game = shortcut("Limited Game", "/games/limited");
+        steam.add_user("100", Some(&[game.clone()][..]));
+        let data = MockData {
+            games: vec![MockGame::new(1, "Limited Game")],
+            rate_limited_image_requests: 2,
+            ..Default::default()
+        };
+
+        download_with_mock(&steam, data).await;
+
+        let grid_folder = grid_folder(&steam);
+        for image_type in AUTOMATIC_TYPES {
+            let path = grid_folder.join(image_type.file_name(game.app_id, "png"));
+            assert!(path.exists(), "Missing {:?}", path);
+        }
+        assert!(!crate::config::get_failed_downloads_file().exists());
+    }
+
+    #[tokio::test]
+    async fn failed_downloads_are_retried_first_on_next_run() {
+        let steam = FakeSteam::new("failed_list");
+        let game = shortcut("Flaky Game", "/games/flaky");
+        steam.add_user("100", Some(&[game.clone()][..]));
+        let mut settings = steam.settings();
+        settings.steamgrid_db.download_retries = 0;
+        let data = MockData {
+            games: vec![MockGame::new(1, "Flaky Game")],
+            rate_limited_image_requests: usize::MAX,
+            ..Default::default()
+        };
+        let server = MockServer::start(data).await.unwrap();
+        settings.steamgrid_db.auth_key = Some("mock".to_string());
+        settings.steamgrid_db.base_url = Some(server.base_url.clone());
+        let users = get_shortcuts_paths(&settings.steam).unwrap();
+        download_images_for_users(&settings, &users, false, &mut None, &SyncCancel::default())
+            .await;
+
+        let failed = load_failed_downloads();
+        assert_eq!(failed.len(), AUTOMATIC_TYPES.len());
+
+        let retry_order = with_failed_downloads_first(failed, &users, vec![]);
+        assert_eq!(retry_order.len(), AUTOMATIC_TYPES.len());
+        assert!(retry_order.iter().all(|d| d.app_name == "Flaky Game"));
+        let grid_folder = grid_folder(&steam);
+        assert!(std::fs::read_dir(grid_folder)
+            .map(|entries| entries.count() == 0)
+            .unwrap_or(true));
+    }
+
+    #[tokio::test]
+    async fn users_with_the_same_shortcut_share_downloads() {
+        let steam = FakeSteam::new("shared_users");
+        let game = shortcut("Shared Game", "/games/shared");
+        steam.add_user("100", Some(&[game.clone()][..]));
+        steam.add_user("200", Some(&[game.clone()][..]));
+
+        let server = download_with_mock(
+            &steam,
+            MockData::with_games(vec![MockGame::new(1, "Shared Game")]),
+        )
+        .await;
+
+        assert_eq!(count_requests(&server, "/images/"), AUTOMATIC_TYPES.len());
+        for user_id in ["100", "200"] {
+            let grid_folder = steam.user_folder(user_id).join("config").join("grid");
+            for image_type in AUTOMATIC_TYPES {
+                let path = grid_folder.join(image_type.file_name(game.app_id, "png"));
+                assert!(path.exists(), "Missing {:?}", path);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn images_of_other_users_are_used_first() {
+        let steam = FakeSteam::new("existing_user_images");
+        let game = shortcut("Picked Game", "/games/picked");
+        steam.add_user("100", Some(&[game.clone()][..]));
+        steam.add_user("200", Some(&[game.clone()][..]));
+        let other_grid = steam.user_folder("200").join("config").join("grid");
+        std::fs::create_dir_all(&other_grid).unwrap();
+        let picked = other_grid.join(ImageType::Logo.file_name(game.app_id, "webp"));
+        std::fs::write(&picked, "picked by hand").unwrap();
+
+        download_with_mock(
+            &steam,
+            MockData::with_games(vec![MockGame::new(1, "Picked Game")]),
+        )
+        .await;
+
+        let logo = grid_folder(&steam).join(ImageType::Logo.file_name(game.app_id, "webp"));
+        assert_eq!(std::fs::read_to_string(logo).unwrap(), "picked by hand");
+        assert!(!grid_folder(&steam)
+            .join(ImageType::Logo.file_name(game.app_id, "png"))
+            .exists());
+    }
+
+    #[tokio::test]
+    async fn manual_picks_are_downloaded_again() {
+        let steam = FakeSteam::new("manual_pick_download");
+        let game = shortcut("Picked Game", "/games/picked");
+        steam.add_user("100", Some(&[game.clone()][..]));
+        let server = MockServer::start(MockData::with_games(vec![MockGame::new(1, "Picked Game")]))
+            .await
+            .unwrap();
+        save_manual_pick(ManualPick {
+            app_name: "picked game".to_string(),
+            external_id: None,
+            image_type: ImageType::Hero,
+            image_id: 77,
+            url: format!("{}/images/1-picked.png", server.base_url),
+            extension: "png".to_string(),
+        });
+
+        download_with_server(&steam, &server).await;
+
+        assert_eq!(count_requests(&server, "/images/1-picked.png"), 1);
+        assert_eq!(count_requests(&server, "/images/1-heroes.png"), 0);
+        let hero = grid_folder(&steam).join(ImageType::Hero.file_name(game.app_id, "png"));
+        assert!(hero.exists());
+        assert_eq!(
+            load_image_ids().get(&ImageType::Hero.file_name_no_extension(game.app_id)),
+            Some(&77)
+        );
+    }
 }
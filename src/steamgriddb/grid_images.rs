@@ -0,0 +1,44 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use super::ImageType;
+
+/// The extensions Steam accepts for images in the grid folder
+pub const GRID_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "ico"];
+
+/// The image Steam uses for a shortcut, if there is one
+pub fn find_grid_image(grid_folder: &Path, app_id: u32, image_type: &ImageType) -> Option<PathBuf> {
+    GRID_EXTENSIONS
+        .iter()
+        .map(|extension| grid_folder.join(image_type.file_name(app_id, extension)))
+        .find(|path| path.is_file())
+}
+
+/// Removes the image of a shortcut, whatever its extension
+pub fn remove_grid_images(
+    grid_folder: &Path,
+    app_id: u32,
+    image_type: &ImageType,
+) -> io::Result<()> {
+    remove_with_any_extension(&grid_folder.join(image_type.file_name_no_extension(app_id)))
+}
+
+/// Hard links the image when possible so it only takes up space once.
+/// The images it replaces are removed first, whatever their extension.
+pub fn link_or_copy(source: &Path, target: &Path) -> io::Result<()> {
+    remove_with_any_extension(target)?;
+    std::fs::hard_link(source, target).or_else(|_| std::fs::copy(source, target).map(|_| ()))
+}
+
+/// Steam picks one of them at random if there are images of the same type with different extensions
+fn remove_with_any_extension(path: &Path) -> io::Result<()> {
+    for extension in GRID_EXTENSIONS {
+        let old_image = path.with_extension(extension);
+        if old_image.symlink_metadata().is_ok() {
+            std::fs::remove_file(old_image)?;
+        }
+    }
+    Ok(())
+}
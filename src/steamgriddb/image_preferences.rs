@@ -0,0 +1,423 @@
+use serde::{Deserialize, Serialize};
+use steamgriddb_api::images::Image;
+use steamgriddb_api::query_parameters::{
+    AnimtionType, GridDimentions, GridStyle, HeroDimentions, HeroStyle, Humor, IconStyle,
+    LogoStyle, MimeType, MimeTypeIcon, MimeTypeLogo, Nsfw,
+};
+
+use super::{ImageType, SteamGridDbSettings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageStyle {
+    Alternate,
+    Blurred,
+    WhiteLogo,
+    NoLogo,
+    Material,
+    Official,
+    White,
+    Black,
+    Custom,
+}
+
+impl ImageStyle {
+    pub fn name(&self) -> &str {
+        match self {
+            ImageStyle::Alternate => "Alternate",
+            ImageStyle::Blurred => "Blurred",
+            ImageStyle::WhiteLogo => "White logo",
+            ImageStyle::NoLogo => "No logo",
+            ImageStyle::Material => "Material",
+            ImageStyle::Official => "Official",
+            ImageStyle::White => "White",
+            ImageStyle::Black => "Black",
+            ImageStyle::Custom => "Custom",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageMime {
+    Png,
+    Jpeg,
+    Webp,
+    Ico,
+}
+
+impl ImageMime {
+    pub fn name(&self) -> &str {
+        match self {
+            ImageMime::Png => "png",
+            ImageMime::Jpeg => "jpeg",
+            ImageMime::Webp => "webp",
+            ImageMime::Ico => "ico",
+        }
+    }
+}
+
+/// Whether images with a tag like humor or epilepsy are wanted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFilter {
+    #[default]
+    Any,
+    Hide,
+    Only,
+}
+
+impl ContentFilter {
+    pub const ALL: [ContentFilter; 3] =
+        [ContentFilter::Any, ContentFilter::Hide, ContentFilter::Only];
+
+    pub fn name(&self) -> &str {
+        match self {
+            ContentFilter::Any => "Show",
+            ContentFilter::Hide => "Hide",
+            ContentFilter::Only => "Only",
+        }
+    }
+
+    fn accepts(&self, tagged: bool) -> bool {
+        match self {
+            ContentFilter::Any => true,
+            ContentFilter::Hide => !tagged,
+            ContentFilter::Only => tagged,
+        }
+    }
+}
+
+/// Which SteamGridDB images to pick for one image type, empty lists allow everything
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImagePreferences {
+    pub styles: Vec<ImageStyle>,
+    /// Written like "920x430"
+    pub dimensions: Vec<String>,
+    pub mime_types: Vec<ImageMime>,
+    pub humor: ContentFilter,
+    pub epilepsy: ContentFilter,
+    /// Images with fewer votes than this are skipped
+    pub min_score: Option<i64>,
+}
+
+impl ImagePreferences {
+    /// Checks the preferences SteamGridDB can not filter on by itself
+    pub fn accepts(&self, image: &Image) -> bool {
+        let score_ok = self
+            .min_score
+            .map(|min_score| image.score as i64 >= min_score)
+            .unwrap_or(true);
+        score_ok && self.epilepsy.accepts(image.epilepsy)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImagePreferencesByType {
+    pub hero: ImagePreferences,
+    pub grid: ImagePreferences,
+    pub wide_grid: ImagePreferences,
+    pub big_picture: ImagePreferences,
+    pub logo: ImagePreferences,
+    pub icon: ImagePreferences,
+}
+
+impl Default for ImagePreferencesByType {
+    fn default() -> Self {
+        let wide = ImagePreferences {
+            dimensions: vec!["920x430".to_string(), "460x215".to_string()],
+            ..Default::default()
+        };
+        ImagePreferencesByType {
+            hero: ImagePreferences::default(),
+            grid: ImagePreferences::default(),
+            wide_grid: wide.clone(),
+            big_picture: wide,
+            logo: ImagePreferences::default(),
+            icon: ImagePreferences::default(),
+        }
+    }
+}
+
+impl ImagePreferencesByType {
+    pub fn get(&self, image_type: &ImageType) -> &ImagePreferences {
+        match image_type {
+            ImageType::Hero => &self.hero,
+            ImageType::Grid => &self.grid,
+            ImageType::WideGrid => &self.wide_grid,
+            ImageType::BigPicture => &self.big_picture,
+            ImageType::Logo => &self.logo,
+            ImageType::Icon => &self.icon,
+        }
+    }
+
+    pub fn get_mut(&mut self, image_type: &ImageType) -> &mut ImagePreferences {
+        match image_type {
+            ImageType::Hero => &mut self.hero,
+            ImageType::Grid => &mut self.grid,
+            ImageType::WideGrid => &mut self.wide_grid,
+            ImageType::BigPicture => &mut self.big_picture,
+            ImageType::Logo => &mut self.logo,
+            ImageType::Icon => &mut self.icon,
+        }
+    }
+}
+
+/// The styles SteamGridDB has for an image type
+pub fn available_styles(image_type: &ImageType) -> &'static [ImageStyle] {
+    match image_type {
+        ImageType::Grid | ImageType::WideGrid | ImageType::BigPicture => &[
+            ImageStyle::Alternate,
+            ImageStyle::Blurred,
+            ImageStyle::WhiteLogo,
+            ImageStyle::NoLogo,
+            ImageStyle::Material,
+        ],
+        ImageType::Hero => &[
+            ImageStyle::Alternate,
+            ImageStyle::Blurred,
+            ImageStyle::Material,
+        ],
+        ImageType::Logo => &[
+            ImageStyle::Official,
+            ImageStyle::White,
+            ImageStyle::Black,
+            ImageStyle::Custom,
+        ],
+        ImageType::Icon => &[ImageStyle::Official, ImageStyle::Custom],
+    }
+}
+
+/// The dimensions SteamGridDB can filter on for an image type
+pub fn available_dimensions(image_type: &ImageType) -> &'static [&'static str] {
+    match image_type {
+        ImageType::Grid => &["600x900", "342x482", "660x930", "512x512", "1024x1024"],
+        ImageType::WideGrid | ImageType::BigPicture => &["920x430", "460x215"],
+        ImageType::Hero => &["1920x620", "3840x1240", "1600x650"],
+        ImageType::Logo | ImageType::Icon => &[],
+    }
+}
+
+pub fn available_mime_types(image_type: &ImageType) -> &'static [ImageMime] {
+    match image_type {
+        ImageType::Logo => &[ImageMime::Png, ImageMime::Webp],
+        ImageType::Icon => &[ImageMime::Png, ImageMime::Ico],
+        _ => &[ImageMime::Png, ImageMime::Jpeg, ImageMime::Webp],
+    }
+}
+
+/// The query parameters for one image type, they are borrowed by `get_query_type`
+pub struct ImageQuery {
+    pub(crate) image_type: ImageType,
+    pub(crate) animation: Vec<AnimtionType>,
+    pub(crate) nsfw: Nsfw,
+    pub(crate) humor: Humor,
+    pub(crate) grid_styles: Vec<GridStyle>,
+    pub(crate) hero_styles: Vec<HeroStyle>,
+    pub(crate) logo_styles: Vec<LogoStyle>,
+    pub(crate) icon_styles: Vec<IconStyle>,
+    pub(crate) grid_dimensions: Vec<GridDimentions>,
+    pub(crate) hero_dimensions: Vec<HeroDimentions>,
+    pub(crate) mimes: Vec<MimeType>,
+    pub(crate) logo_mimes: Vec<MimeTypeLogo>,
+    pub(crate) icon_mimes: Vec<MimeTypeIcon>,
+}
+
+impl ImageQuery {
+    pub fn new(
+        download_animated: bool,
+        image_type: &ImageType,
+        settings: &SteamGridDbSettings,
+    ) -> Self {
+        let preferences = settings.image_preferences.get(image_type);
+        let styles = &preferences.styles;
+        let dimensions = &preferences.dimensions;
+        let mimes = &preferences.mime_types;
+        ImageQuery {
+            image_type: *image_type,
+            animation: if download_animated {
+                vec![AnimtionType::Animated]
+            } else {
+                vec![]
+            },
+            nsfw: if settings.allow_nsfw {
+                Nsfw::Any
+            } else {
+                Nsfw::False
+            },
+            humor: match preferences.humor {
+                ContentFilter::Any => Humor::Any,
+                ContentFilter::Hide => Humor::False,
+                ContentFilter::Only => Humor::True,
+            },
+            grid_styles: styles.iter().filter_map(grid_style).collect(),
+            hero_styles: styles.iter().filter_map(hero_style).collect(),
+            logo_styles: styles.iter().filter_map(logo_style).collect(),
+            icon_styles: styles.iter().filter_map(icon_style).collect(),
+            grid_dimensions: dimensions
+                .iter()
+                .filter_map(|d| grid_dimension(d))
+                .collect(),
+            hero_dimensions: dimensions
+                .iter()
+                .filter_map(|d| hero_dimension(d))
+                .collect(),
+            mimes: mimes.iter().filter_map(mime).collect(),
+            logo_mimes: mimes.iter().filter_map(logo_mime).collect(),
+            icon_mimes: mimes.iter().filter_map(icon_mime).collect(),
+        }
+    }
+}
+
+fn grid_style(style: &ImageStyle) -> Option<GridStyle> {
+    match style {
+        ImageStyle::Alternate => Some(GridStyle::Alternate),
+        ImageStyle::Blurred => Some(GridStyle::Blurred),
+        ImageStyle::WhiteLogo => Some(GridStyle::WhiteLogo),
+        ImageStyle::NoLogo => Some(GridStyle::NoLogo),
+        ImageStyle::Material => Some(GridStyle::Material),
+        _ => None,
+    }
+}
+
+fn hero_style(style: &ImageStyle) -> Option<HeroStyle> {
+    match style {
+        ImageStyle::Alternate => Some(HeroStyle::Alternate),
+        ImageStyle::Blurred => Some(HeroStyle::Blurred),
+        ImageStyle::Material => Some(HeroStyle::Material),
+        _ => None,
+    }
+}
+
+fn logo_style(style: &ImageStyle) -> Option<LogoStyle> {
+    match style {
+        ImageStyle::Official => Some(LogoStyle::Official),
+        ImageStyle::White => Some(LogoStyle::White),
+        ImageStyle::Black => Some(LogoStyle::Black),
+        ImageStyle::Custom => Some(LogoStyle::Custom),
+        _ => None,
+    }
+}
+
+fn icon_style(style: &ImageStyle) -> Option<IconStyle> {
+    match style {
+        ImageStyle::Official => Some(IconStyle::Official),
+        ImageStyle::Custom => Some(IconStyle::Custom),
+        _ => None,
+    }
+}
+
+fn grid_dimension(dimension: &str) -> Option<GridDimentions> {
+    match dimension {
+        "460x215" => Some(GridDimentions::D460x215),
+        "920x430" => Some(GridDimentions::D920x430),
+        "600x900" => Some(GridDimentions::D600x900),
+        "342x482" => Some(GridDimentions::D342x482),
+        "660x930" => Some(GridDimentions::D660x930),
+        "512x512" => Some(GridDimentions::D512x512),
+        "1024x1024" => Some(GridDimentions::D1024x1024),
+        _ => None,
+    }
+}
+
+fn hero_dimension(dimension: &str) -> Option<HeroDimentions> {
+    match dimension {
+        "1920x620" => Some(HeroDimentions::D1920x620),
+        "3840x1240" => Some(HeroDimentions::D3840x1240),
+        "1600x650" => Some(HeroDimentions::D1600x650),
+        _ => None,
+    }
+}
+
+fn mime(mime: &ImageMime) -> Option<MimeType> {
+    match mime {
+        ImageMime::Png => Some(MimeType::Png),
+        ImageMime::Jpeg => Some(MimeType::Jpeg),
+        ImageMime::Webp => Some(MimeType::Webp),
+        ImageMime::Ico => None,
+    }
+}
+
+fn logo_mime(mime: &ImageMime) -> Option<MimeTypeLogo> {
+    match mime {
+        ImageMime::Png => Some(MimeTypeLogo::Png),
+        ImageMime::Webp => Some(MimeTypeLogo::Webp),
+        _ => None,
+    }
+}
+
+fn icon_mime(mime: &ImageMime) -> Option<MimeTypeIcon> {
+    match mime {
+        ImageMime::Png => Some(MimeTypeIcon::Png),
+        ImageMime::Ico => Some(MimeTypeIcon::Icon),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_filter() {
+        assert!(ContentFilter::Any.accepts(true));
+        assert!(ContentFilter::Any.accepts(false));
+        assert!(!ContentFilter::Hide.accepts(true));
+        assert!(ContentFilter::Only.accepts(true));
+        assert!(!ContentFilter::Only.accepts(false));
+    }
+
+    #[test]
+    fn wide_images_default_to_steam_dimensions() {
+        let preferences = ImagePreferencesByType::default();
+        for image_type in [ImageType::WideGrid, ImageType::BigPicture] {
+            let dimensions = &preferences.get(&image_type).dimensions;
+            assert_eq!(dimensions, &["920x430", "460x215"]);
+        }
+        assert!(preferences.get(&ImageType::Grid).dimensions.is_empty());
+    }
+
+    #[test]
+    fn only_offers_what_steamgriddb_has() {
+        for image_type in ImageType::all() {
+            for dimension in available_dimensions(image_type) {
+                let known = match image_type {
+                    ImageType::Hero => hero_dimension(dimension).is_some(),
+                    _ => grid_dimension(dimension).is_some(),
+                };
+                assert!(known, "{} is not a known dimension", dimension);
+            }
+            for style in available_styles(image_type) {
+                let known = match image_type {
+                    ImageType::Hero => hero_style(style).is_some(),
+                    ImageType::Logo => logo_style(style).is_some(),
+                    ImageType::Icon => icon_style(style).is_some(),
+                    _ => grid_style(style).is_some(),
+                };
+                assert!(known, "{:?} is not a known style", style);
+            }
+        }
+    }
+
+    #[test]
+    fn preferences_load_from_partial_toml() {
+        let preferences: ImagePreferencesByType = toml::from_str(
+            r#"
+            [logo]
+            styles = ["white", "official"]
+            humor = "hide"
+            min_score = 2
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            preferences.logo.styles,
+            vec![ImageStyle::White, ImageStyle::Official]
+        );
+        assert_eq!(preferences.logo.humor, ContentFilter::Hide);
+        assert_eq!(preferences.logo.min_score, Some(2));
+        assert_eq!(preferences.big_picture.dimensions.len(), 2);
+    }
+}
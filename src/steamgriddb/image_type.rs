@@ -1,4 +1,6 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ImageType {
     Hero,
     Grid,
@@ -49,28 +51,51 @@ impl ImageType {
         }
     }
 
-    pub fn steam_url<S: AsRef<str>>(&self, steam_app_id: S, mtime: u64) -> String {
+    /// Checks that an image has a usable size and the orientation Steam expects.
+    /// Square images are allowed everywhere, since some sources only have those.
+    pub fn accepts_dimensions(&self, width: u32, height: u32) -> bool {
+        match self {
+            ImageType::Icon | ImageType::Logo => width >= 16 && height >= 16,
+            ImageType::Grid => width >= 32 && height >= width,
+            ImageType::Hero | ImageType::WideGrid | ImageType::BigPicture => {
+                height >= 32 && width >= height
+            }
+        }
+    }
+
+    /// The largest size Steam shows this type of image at
+    pub fn max_dimensions(&self) -> (u32, u32) {
+        match self {
+            ImageType::Hero => (3840, 1240),
+            ImageType::Grid => (600, 900),
+            ImageType::WideGrid | ImageType::BigPicture => (920, 430),
+            ImageType::Logo => (1280, 720),
+            ImageType::Icon => (256, 256),
+        }
+    }
+
+    pub fn steam_url<S: AsRef<str>>(&self, cdn_url: &str, steam_app_id: S, mtime: u64) -> String {
         let steam_app_id = steam_app_id.as_ref();
         match self {
             ImageType::Hero => format!(
-                "https://cdn.cloudflare.steamstatic.com/steam/apps/{}/library_hero.jpg?t={}",
-                steam_app_id, mtime
+                "{}/steam/apps/{}/library_hero.jpg?t={}",
+                cdn_url, steam_app_id, mtime
             ),
             ImageType::Grid => format!(
-                "https://cdn.cloudflare.steamstatic.com/steam/apps/{}/library_600x900_2x.jpg?t={}",
-                steam_app_id, mtime
+                "{}/steam/apps/{}/library_600x900_2x.jpg?t={}",
+                cdn_url, steam_app_id, mtime
             ),
             ImageType::WideGrid => format!(
-                "https://cdn.cloudflare.steamstatic.com/steam/apps/{}/header.jpg?t={}",
-                steam_app_id, mtime
+                "{}/steam/apps/{}/header.jpg?t={}",
+                cdn_url, steam_app_id, mtime
             ),
             ImageType::Logo => format!(
-                "https://cdn.cloudflare.steamstatic.com/steam/apps/{}/logo.png?t={}",
-                steam_app_id, mtime
+                "{}/steam/apps/{}/logo.png?t={}",
+                cdn_url, steam_app_id, mtime
             ),
             ImageType::BigPicture => format!(
-                "https://cdn.cloudflare.steamstatic.com/steam/apps/{}/header.jpg?t={}",
-                steam_app_id, mtime
+                "{}/steam/apps/{}/header.jpg?t={}",
+                cdn_url, steam_app_id, mtime
             ),
             // This should not happen
             _ => "".to_string(),
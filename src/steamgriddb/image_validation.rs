@@ -0,0 +1,242 @@
+use std::{fmt::Display, io::Cursor, path::Path};
+
+use image::{imageops::FilterType, DynamicImage, ImageFormat, ImageOutputFormat};
+
+use super::{ImageType, SteamGridDbSettings};
+
+/// What to do with a downloaded image before it is placed in the grid folder
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImageProcessing {
+    /// Decode the image and check that it fits the image type
+    pub validate: bool,
+    /// Store webp images as png, older Steam clients can not show webp
+    pub transcode_webp: bool,
+    /// Scale images down to the largest size Steam uses for the image type
+    pub resize_large_images: bool,
+}
+
+impl From<&SteamGridDbSettings> for ImageProcessing {
+    fn from(settings: &SteamGridDbSettings) -> Self {
+        ImageProcessing {
+            validate: true,
+            transcode_webp: settings.transcode_webp,
+            resize_large_images: settings.resize_large_images,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ValidatedImage {
+    pub bytes: Vec<u8>,
+    /// Set if the image was converted and has to be saved with another extension
+    pub extension: Option<&'static str>,
+}
+
+#[derive(Debug)]
+pub enum InvalidImage {
+    NotAnImage,
+    UnsupportedFormat(ImageFormat),
+    Corrupt(image::ImageError),
+    WrongDimensions { width: u32, height: u32 },
+    Encode(image::ImageError),
+}
+
+impl Display for InvalidImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidImage::NotAnImage => write!(f, "the downloaded file is not an image"),
+            InvalidImage::UnsupportedFormat(format) => {
+                write!(f, "{:?} images can not be used by Steam", format)
+            }
+            InvalidImage::Corrupt(e) => write!(f, "the image could not be decoded: {}", e),
+            InvalidImage::WrongDimensions { width, height } => {
+                write!(f, "a {}x{} image does not fit here", width, height)
+            }
+            InvalidImage::Encode(e) => write!(f, "the image could not be converted: {}", e),
+        }
+    }
+}
+
+pub fn validate_image(
+    content: &[u8],
+    image_type: &ImageType,
+    processing: &ImageProcessing,
+) -> Result<ValidatedImage, InvalidImage> {
+    let unchanged = || ValidatedImage {
+        bytes: content.to_vec(),
+        extension: None,
+    };
+    if !processing.validate {
+        return Ok(unchanged());
+    }
+    let format = image::guess_format(content).map_err(|_| InvalidImage::NotAnImage)?;
+    match format {
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP => {}
+        // We can not decode icons, so we trust them as long as they look like one
+        ImageFormat::Ico if *image_type == ImageType::Icon => return Ok(unchanged()),
+        format => return Err(InvalidImage::UnsupportedFormat(format)),
+    }
+    // The image crate can not decode animations, Steam can show them as they are when their size fits
+    if is_animated(content, format) {
+        let (width, height) = image::io::Reader::with_format(Cursor::new(content), format)
+            .into_dimensions()
+            .map_err(InvalidImage::Corrupt)?;
+        check_dimensions(image_type, width, height)?;
+        return Ok(unchanged());
+    }
+    let image =
+        image::load_from_memory_with_format(content, format).map_err(InvalidImage::Corrupt)?;
+    let (width, height) = (image.width(), image.height());
+    check_dimensions(image_type, width, height)?;
+
+    let (max_width, max_height) = image_type.max_dimensions();
+    if processing.resize_large_images && (width > max_width || height > max_height) {
+        let resized = image.resize(max_width, max_height, FilterType::Lanczos3);
+        return match format {
+            ImageFormat::Jpeg => encode(&resized, ImageOutputFormat::Jpeg(90), "jpg"),
+            _ => encode(&resized, ImageOutputFormat::Png, "png"),
+        };
+    }
+    if processing.transcode_webp && format == ImageFormat::WebP {
+        return encode(&image, ImageOutputFormat::Png, "png");
+    }
+    Ok(unchanged())
+}
+
+fn check_dimensions(image_type: &ImageType, width: u32, height: u32) -> Result<(), InvalidImage> {
+    if image_type.accepts_dimensions(width, height) {
+        Ok(())
+    } else {
+        Err(InvalidImage::WrongDimensions { width, height })
+    }
+}
+
+fn encode(
+    image: &DynamicImage,
+    format: ImageOutputFormat,
+    extension: &'static str,
+) -> Result<ValidatedImage, InvalidImage> {
+    let mut bytes = Cursor::new(vec![]);
+    image
+        .write_to(&mut bytes, format)
+        .map_err(InvalidImage::Encode)?;
+    Ok(ValidatedImage {
+        bytes: bytes.into_inner(),
+        extension: Some(extension),
+    })
+}
+
+/// Checks if an image in the grid folder is animated
+pub fn is_animated_file(path: &Path) -> bool {
+    match std::fs::read(path) {
+        Ok(content) => image::guess_format(&content)
+            .map(|format| is_animated(&content, format))
+            .unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+fn is_animated(content: &[u8], format: ImageFormat) -> bool {
+    match format {
+        // Extended webp files have a flags byte, bit 2 marks an animation
+        ImageFormat::WebP => {
+            content.get(12..16) == Some(&b"VP8X"[..])
+                && content.get(20).map(|flags| flags & 0x02 != 0) == Some(true)
+        }
+        // Animated pngs have an animation control chunk
+        ImageFormat::Png => content.windows(4).any(|chunk| chunk == b"acTL"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processing() -> ImageProcessing {
+        ImageProcessing {
+            validate: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_images() {
+        let content = include_bytes!("../testdata/brokenimage.webp");
+        let res = validate_image(content, &ImageType::Grid, &processing());
+        assert!(matches!(res, Err(InvalidImage::NotAnImage)));
+
+        let html = b"<html><body>Service unavailable</body></html>";
+        let res = validate_image(html, &ImageType::Grid, &processing());
+        assert!(matches!(res, Err(InvalidImage::NotAnImage)));
+    }
+
+    #[test]
+    fn skips_validation_when_disabled() {
+        let content = include_bytes!("../testdata/brokenimage.webp");
+        let res = validate_image(content, &ImageType::Grid, &ImageProcessing::default());
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn checks_dimensions_against_image_type() {
+        // spider.webp is a 600x900 grid
+        let content = include_bytes!("../testdata/spider.webp");
+        assert!(validate_image(content, &ImageType::Grid, &processing()).is_ok());
+        let res = validate_image(content, &ImageType::Hero, &processing());
+        assert!(matches!(
+            res,
+            Err(InvalidImage::WrongDimensions {
+                width: 600,
+                height: 900
+            })
+        ));
+    }
+
+    #[test]
+    fn keeps_animated_images() {
+        let content = include_bytes!("../testdata/hollow.webp");
+        let processing = ImageProcessing {
+            transcode_webp: true,
+            ..processing()
+        };
+        let res = validate_image(content, &ImageType::Icon, &processing).unwrap();
+        assert_eq!(res.extension, None);
+        assert_eq!(res.bytes.len(), content.len());
+    }
+
+    #[test]
+    fn detects_animated_files() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/testdata");
+        assert!(is_animated_file(&testdata.join("hollow.webp")));
+        assert!(!is_animated_file(&testdata.join("spider.webp")));
+        assert!(!is_animated_file(&testdata.join("missing.webp")));
+    }
+
+    #[test]
+    fn transcodes_webp_to_png() {
+        let content = include_bytes!("../testdata/spider.webp");
+        let processing = ImageProcessing {
+            transcode_webp: true,
+            ..processing()
+        };
+        let res = validate_image(content, &ImageType::Grid, &processing).unwrap();
+        assert_eq!(res.extension, Some("png"));
+        assert_eq!(image::guess_format(&res.bytes).unwrap(), ImageFormat::Png);
+    }
+
+    #[test]
+    fn resizes_large_images() {
+        // smallpng.png is 1541x1213
+        let content = include_bytes!("../testdata/smallpng.png");
+        let processing = ImageProcessing {
+            resize_large_images: true,
+            ..processing()
+        };
+        let res = validate_image(content, &ImageType::WideGrid, &processing).unwrap();
+        assert_eq!(res.extension, Some("png"));
+        let resized = image::load_from_memory(&res.bytes).unwrap();
+        let (max_width, max_height) = ImageType::WideGrid.max_dimensions();
+        assert!(resized.width() <= max_width && resized.height() <= max_height);
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::{get_manual_picks_file, load_json_file, save_json_file};
+use crate::platforms::ExternalId;
+
+use super::ImageType;
+
+/// An image that was picked by hand on the images page.
+///
+/// Picks are remembered by the game and not by the app id,
+/// so they survive shortcuts getting a new app id or the grid folder being cleared.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManualPick {
+    pub app_name: String,
+    pub external_id: Option<ExternalId>,
+    pub image_type: ImageType,
+    pub image_id: u32,
+    pub url: String,
+    pub extension: String,
+}
+
+impl ManualPick {
+    /// Games are the same if they have the same external id, or the same name if one of them has none
+    fn is_for_game(&self, app_name: &str, external_id: Option<&ExternalId>) -> bool {
+        match (&self.external_id, external_id) {
+            (Some(pick_id), Some(external_id)) => pick_id == external_id,
+            _ => self.app_name.to_lowercase() == app_name.to_lowercase(),
+        }
+    }
+}
+
+pub fn load_manual_picks() -> Vec<ManualPick> {
+    load_json_file(&get_manual_picks_file())
+}
+
+fn save_manual_picks(picks: &[ManualPick]) {
+    save_json_file(&get_manual_picks_file(), picks, "manual picks");
+}
+
+/// Remembers a pick, replacing the one picked before for the same game and image type
+pub fn save_manual_pick(pick: ManualPick) {
+    let mut picks = load_manual_picks();
+    picks.retain(|p| {
+        p.image_type != pick.image_type || !p.is_for_game(&pick.app_name, pick.external_id.as_ref())
+    });
+    picks.push(pick);
+    save_manual_picks(&picks);
+}
+
+/// Forgets all picks for a game so its images are chosen automatically again,
+/// returns the picks that were removed
+pub fn reset_manual_picks(app_name: &str, external_id: Option<&ExternalId>) -> Vec<ManualPick> {
+    let (removed, kept): (Vec<ManualPick>, Vec<ManualPick>) = load_manual_picks()
+        .into_iter()
+        .partition(|p| p.is_for_game(app_name, external_id));
+    if !removed.is_empty() {
+        save_manual_picks(&kept);
+    }
+    removed
+}
+
+pub fn find_manual_pick<'a>(
+    picks: &'a [ManualPick],
+    app_name: &str,
+    external_id: Option<&ExternalId>,
+    image_type: &ImageType,
+) -> Option<&'a ManualPick> {
+    picks
+        .iter()
+        .find(|p| p.image_type == *image_type && p.is_for_game(app_name, external_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platforms::ExternalPlatform;
+
+    fn pick(app_name: &str, external_id: Option<ExternalId>, image_id: u32) -> ManualPick {
+        ManualPick {
+            app_name: app_name.to_string(),
+            external_id,
+            image_type: ImageType::Hero,
+            image_id,
+            url: format!("https://cdn.example.com/{}.png", image_id),
+            extension: "png".to_string(),
+        }
+    }
+
+    #[test]
+    fn finds_picks_by_external_id_before_name() {
+        let gog = ExternalId::new(ExternalPlatform::Gog, "1");
+        let picks = vec![
+            pick("Renamed Game", Some(gog.clone()), 1),
+            pick("Celeste", None, 2),
+        ];
+
+        let by_id = find_manual_pick(&picks, "Original Name", Some(&gog), &ImageType::Hero);
+        assert_eq!(by_id.map(|p| p.image_id), Some(1));
+        let by_name = find_manual_pick(&picks, "celeste", None, &ImageType::Hero);
+        assert_eq!(by_name.map(|p| p.image_id), Some(2));
+        assert!(find_manual_pick(&picks, "Celeste", None, &ImageType::Logo).is_none());
+        let other_id = ExternalId::new(ExternalPlatform::Gog, "2");
+        assert!(
+            find_manual_pick(&picks, "Renamed Game", Some(&other_id), &ImageType::Hero).is_none()
+        );
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn picks_are_replaced_and_reset() {
+        let _steam = crate::sync::fake_steam::FakeSteam::new("manual_picks");
+        save_manual_pick(pick("Celeste", None, 1));
+        save_manual_pick(pick("CELESTE", None, 2));
+        save_manual_pick(pick("Tunic", None, 3));
+        let picks = load_manual_picks();
+        assert_eq!(picks.len(), 2);
+        assert_eq!(
+            find_manual_pick(&picks, "Celeste", None, &ImageType::Hero).map(|p| p.image_id),
+            Some(2)
+        );
+
+        let removed = reset_manual_picks("celeste", None);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(load_manual_picks(), vec![pick("Tunic", None, 3)]);
+    }
+}
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+/// Matches scoring at least this are used without asking the user
+pub const AUTO_ACCEPT_SCORE: f32 = 0.85;
+
+const VERIFIED_BONUS: f32 = 0.05;
+const YEAR_MATCH_BONUS: f32 = 0.1;
+const YEAR_MISMATCH_PENALTY: f32 = 0.3;
+
+/// A SteamGridDB game and how well it matches the name we searched for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoredMatch {
+    pub id: usize,
+    pub name: String,
+    pub verified: bool,
+    /// Between 0 and 1, 1 is a perfect match
+    pub score: f32,
+}
+
+/// Scores all search results, best match first
+pub fn score_results(
+    query: &str,
+    results: &[steamgriddb_api::search::SearchResult],
+) -> Vec<ScoredMatch> {
+    let mut matches: Vec<ScoredMatch> = results
+        .iter()
+        .map(|result| ScoredMatch {
+            id: result.id,
+            name: result.name.clone(),
+            verified: result.verified,
+            score: score_match(query, &result.name, result.verified),
+        })
+        .collect();
+    // Stable sort, so SteamGridDB's own order decides ties
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches
+}
+
+pub fn score_match(query: &str, name: &str, verified: bool) -> f32 {
+    let mut score = title_similarity(&normalize_title(query), &normalize_title(name));
+    // Remakes and reboots often share a name, the year tells them apart
+    match (release_year(query), release_year(name)) {
+        (Some(query_year), Some(name_year)) if query_year == name_year => score += YEAR_MATCH_BONUS,
+        (Some(_), Some(_)) => score -= YEAR_MISMATCH_PENALTY,
+        _ => {}
+    }
+    if verified {
+        score += VERIFIED_BONUS;
+    }
+    score.clamp(0.0, 1.0)
+}
+
+/// Lowercases the title and drops symbols, punctuation and years
+fn normalize_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .filter(|word| parse_year(word).is_none())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn release_year(title: &str) -> Option<u32> {
+    title
+        .split(|c: char| !c.is_ascii_digit())
+        .find_map(parse_year)
+}
+
+fn parse_year(word: &str) -> Option<u32> {
+    if word.len() != 4 {
+        return None;
+    }
+    word.parse()
+        .ok()
+        .filter(|year| (1970..=2100).contains(year))
+}
+
+fn title_similarity(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f32 / longest as f32
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_case_symbols_and_punctuation() {
+        let score = score_match(
+            "The Witcher® 3: Wild Hunt",
+            "the witcher 3 - wild hunt",
+            false,
+        );
+        assert_eq!(score, 1.0);
+    }
+
+    #[test]
+    fn demos_and_sequels_are_not_confident() {
+        assert!(score_match("Tunic", "Tunic Demo", true) < AUTO_ACCEPT_SCORE);
+        assert!(score_match("Hollow Knight", "Hollow Knight: Silksong", true) < AUTO_ACCEPT_SCORE);
+    }
+
+    #[test]
+    fn release_year_picks_the_right_remake() {
+        let right = score_match("Doom (2016)", "DOOM (2016)", false);
+        let wrong = score_match("Doom (2016)", "DOOM (1993)", false);
+        assert!(right >= AUTO_ACCEPT_SCORE);
+        assert!(wrong < AUTO_ACCEPT_SCORE);
+    }
+
+    #[test]
+    fn verified_games_win_ties() {
+        assert!(
+            score_match("Celeste", "Celeste Classic", true)
+                > score_match("Celeste", "Celeste Classic", false)
+        );
+    }
+
+    #[test]
+    fn levenshtein_distance() {
+        let distance = |a: &str, b: &str| {
+            levenshtein(
+                &a.chars().collect::<Vec<char>>(),
+                &b.chars().collect::<Vec<char>>(),
+            )
+        };
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("same", "same"), 0);
+    }
+}
@@ -0,0 +1,350 @@
+//! A small local stand-in for SteamGridDB and the Steam CDN.
+//!
+//! It serves canned search results, image lists and image bytes, so the image
+//! pipeline can be exercised without network access. It is used by the tests
+//! and can be started with `--sgdb-base-url mock`.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+const MOCK_IMAGE: &[u8] = include_bytes!("../../resources/logo32.png");
+
+#[derive(Clone, Debug)]
+pub struct MockGame {
+    pub id: usize,
+    pub name: String,
+    /// If false, every image list for this game is empty, so BoilR has to fall back to Steam
+    pub has_images: bool,
+    /// The steam app id returned by the public game endpoint
+    pub steam_app_id: Option<String>,
+    /// Overrides the bytes served for this games images
+    pub image_bytes: Option<Vec<u8>>,
+    /// (platform, id) pairs this game can be looked up by
+    pub platform_ids: Vec<(String, String)>,
+}
+
+impl MockGame {
+    pub fn new<S: Into<String>>(id: usize, name: S) -> Self {
+        MockGame {
+            id,
+            name: name.into(),
+            has_images: true,
+            steam_app_id: None,
+            image_bytes: None,
+            platform_ids: vec![],
+        }
+    }
+}
+
+/// The games the mock knows about.
+/// If there are none, every search matches a made up game that has all images.
+#[derive(Clone, Debug, Default)]
+pub struct MockData {
+    pub games: Vec<MockGame>,
+    /// The first this many image requests are answered with 429 Too Many Requests
+    pub rate_limited_image_requests: usize,
+    /// Searches match every game that contains the search term, instead of only exact names
+    pub fuzzy_search: bool,
+}
+
+impl MockData {
+    #[cfg(test)]
+    pub fn with_games(games: Vec<MockGame>) -> Self {
+        MockData {
+            games,
+            ..Default::default()
+        }
+    }
+
+    fn find_by_name(&self, name: &str) -> Vec<MockGame> {
+        if self.games.is_empty() {
+            return vec![MockGame::new(1, name)];
+        }
+        self.games
+            .iter()
+            .filter(|g| {
+                let game_name = g.name.to_lowercase();
+                let name = name.to_lowercase();
+                game_name == name || (self.fuzzy_search && game_name.contains(&name))
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn find_by_platform_id(&self, platform: &str, id: &str) -> Option<MockGame> {
+        self.games
+            .iter()
+            .find(|g| g.platform_ids.iter().any(|(p, i)| p == platform && i == id))
+            .cloned()
+    }
+
+    fn find_by_id(&self, id: usize) -> Option<MockGame> {
+        if self.games.is_empty() {
+            return Some(MockGame::new(id, format!("Game {}", id)));
+        }
+        self.games.iter().find(|g| g.id == id).cloned()
+    }
+}
+
+pub struct MockServer {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    /// Starts the server on a random local port, it runs until the runtime stops
+    pub async fn start(data: MockData) -> std::io::Result<MockServer> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let base_url = format!("http://{}", listener.local_addr()?);
+        let requests = Arc::new(Mutex::new(vec![]));
+        let data = Arc::new(data);
+        let image_requests = Arc::new(AtomicUsize::new(0));
+        let server_requests = requests.clone();
+        let server_base_url = base_url.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let data = data.clone();
+                let requests = server_requests.clone();
+                let base_url = server_base_url.clone();
+                let image_requests = image_requests.clone();
+                tokio::spawn(async move {
+                    let result =
+                        handle_connection(stream, &data, &requests, &image_requests, &base_url)
+                            .await;
+                    if let Err(e) = result {
+                        println!("Mock SteamGridDB request failed: {}", e);
+                    }
+                });
+            }
+        });
+        Ok(MockServer { base_url, requests })
+    }
+
+    /// All request paths (including query) served so far
+    #[cfg(test)]
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    data: &MockData,
+    requests: &Mutex<Vec<String>>,
+    image_requests: &AtomicUsize,
+    base_url: &str,
+) -> std::io::Result<()> {
+    let mut request = vec![];
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let target = request
+        .lines()
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .unwrap_or("/")
+        .to_string();
+    if let Ok(mut requests) = requests.lock() {
+        requests.push(target.clone());
+    }
+    let path = target.split('?').next().unwrap_or_default();
+    let (status, content_type, body) = if is_rate_limited(path, data, image_requests) {
+        ("429 Too Many Requests", "application/json", b"{}".to_vec())
+    } else {
+        route(path, data, base_url)
+    };
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nRetry-After: 0\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await
+}
+
+fn is_rate_limited(path: &str, data: &MockData, image_requests: &AtomicUsize) -> bool {
+    path.starts_with("/images/")
+        && image_requests.fetch_add(1, Ordering::SeqCst) < data.rate_limited_image_requests
+}
+
+fn route(path: &str, data: &MockData, base_url: &str) -> (&'static str, &'static str, Vec<u8>) {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["api", "v2", "search", "autocomplete", term] => {
+            let term = percent_decode(term);
+            let results: Vec<Value> = data
+                .find_by_name(&term)
+                .iter()
+                .map(|g| json!({"id": g.id, "name": g.name, "types": [], "verified": true}))
+                .collect();
+            json_response(json!({"success": true, "data": results}))
+        }
+        ["api", "v2", "games", platform, id] => {
+            match data.find_by_platform_id(platform, &percent_decode(id)) {
+                Some(game) => json_response(json!({
+                    "success": true,
+                    "data": {"id": game.id, "name": game.name, "types": [], "verified": true}
+                })),
+                None => not_found(),
+            }
+        }
+        ["api", "v2", kind, "game", ids] => {
+            let ids: Vec<usize> = ids.split(',').filter_map(|id| id.parse().ok()).collect();
+            if ids.len() == 1 {
+                let images: Vec<Value> = data
+                    .find_by_id(ids[0])
+                    .filter(|g| g.has_images)
+                    .map(|game| image_json(&game, kind, base_url))
+                    .into_iter()
+                    .collect();
+                json_response(json!({"success": true, "data": images}))
+            } else {
+                let results: Vec<Value> = ids
+                    .iter()
+                    .map(|id| match data.find_by_id(*id).filter(|g| g.has_images) {
+                        Some(game) => json!({
+                            "success": true,
+                            "status": 200,
+                            "data": [image_json(&game, kind, base_url)]
+                        }),
+                        None => json!({
+                            "success": false,
+                            "status": 404,
+                            "errors": ["Game not found"]
+                        }),
+                    })
+                    .collect();
+                json_response(json!({"success": true, "data": results}))
+            }
+        }
+        ["api", "public", "game", id] => {
+            let game = id.parse().ok().and_then(|id| data.find_by_id(id));
+            match game.and_then(|g| g.steam_app_id) {
+                Some(steam_app_id) => json_response(json!({
+                    "success": true,
+                    "data": {
+                        "platforms": {
+                            "steam": {
+                                "id": steam_app_id,
+                                "metadata": {
+                                    "store_asset_mtime": 1,
+                                    "clienticon": "icon"
+                                }
+                            }
+                        }
+                    }
+                })),
+                None => json_response(json!({"success": true, "data": {"platforms": {}}})),
+            }
+        }
+        ["images", file_name] => {
+            let game = file_name
+                .split('-')
+                .next()
+                .and_then(|id| id.parse().ok())
+                .and_then(|id| data.find_by_id(id));
+            let bytes = game
+                .and_then(|g| g.image_bytes)
+                .unwrap_or_else(|| MOCK_IMAGE.to_vec());
+            ("200 OK", "image/png", bytes)
+        }
+        ["steam", "apps", ..] | ["steamcommunity", ..] => {
+            ("200 OK", "image/png", MOCK_IMAGE.to_vec())
+        }
+        _ => not_found(),
+    }
+}
+
+fn image_json(game: &MockGame, kind: &str, base_url: &str) -> Value {
+    let url = format!("{}/images/{}-{}.png", base_url, game.id, kind);
+    json!({
+        "id": game.id,
+        "score": 0,
+        "style": "alternate",
+        "width": 600,
+        "height": 900,
+        "nsfw": false,
+        "humor": false,
+        "epilepsy": false,
+        "notes": null,
+        "mime": "image/png",
+        "language": "en",
+        "url": url,
+        "thumb": url,
+        "lock": false,
+        "upvotes": 0,
+        "downvotes": 0,
+        "author": {
+            "name": "BoilR",
+            "steam64": "0",
+            "avatar": ""
+        }
+    })
+}
+
+fn json_response(value: Value) -> (&'static str, &'static str, Vec<u8>) {
+    ("200 OK", "application/json", value.to_string().into_bytes())
+}
+
+fn not_found() -> (&'static str, &'static str, Vec<u8>) {
+    (
+        "404 Not Found",
+        "application/json",
+        json!({"success": false, "errors": ["Not found"]})
+            .to_string()
+            .into_bytes(),
+    )
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut result = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        let decoded = if bytes[index] == b'%' && index + 3 <= bytes.len() {
+            std::str::from_utf8(&bytes[index + 1..index + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+        match decoded {
+            Some(byte) => {
+                result.push(byte);
+                index += 3;
+            }
+            None => {
+                result.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&result).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_escapes_at_the_end() {
+        assert_eq!(percent_decode("Hello%20World%21"), "Hello World!");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+}
@@ -1,9 +1,39 @@
+mod bulk_artwork;
+mod cache_cli;
 mod cached_search;
 mod downloader;
+mod grid_images;
+mod image_preferences;
 mod image_type;
+mod image_validation;
+mod manual_picks;
+mod match_score;
+mod mock_server;
+mod platform_lookup;
 mod settings;
 
-pub use cached_search::CachedSearch;
+pub use bulk_artwork::{
+    download_bulk_artwork, find_bulk_targets, BulkGameKind, BulkTarget, BULK_IMAGE_TYPES,
+};
+pub use cache_cli::run_cache_command;
+pub use cached_search::{
+    clear_cache, get_cache_entries, get_needs_review, remove_cache_entry, set_cache_entry,
+    CacheEntry, CachedSearch, NeedsReview,
+};
 pub use downloader::*;
+pub use grid_images::{find_grid_image, link_or_copy, remove_grid_images, GRID_EXTENSIONS};
+pub use image_preferences::{
+    available_dimensions, available_mime_types, available_styles, ContentFilter, ImagePreferences,
+    ImagePreferencesByType, ImageQuery,
+};
 pub use image_type::ImageType;
+pub use image_validation::{is_animated_file, ImageProcessing};
+pub use manual_picks::{
+    find_manual_pick, load_manual_picks, reset_manual_picks, save_manual_pick, ManualPick,
+};
+pub use match_score::{score_results, ScoredMatch};
+#[cfg(test)]
+pub use mock_server::MockGame;
+pub use mock_server::{MockData, MockServer};
+pub use platform_lookup::{load_external_ids, save_external_ids, PlatformLookup};
 pub use settings::SteamGridDbSettings;
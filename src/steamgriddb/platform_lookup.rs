@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::config::{get_external_ids_file, load_json_file, save_json_file};
+use crate::platforms::ExternalId;
+
+use super::SteamGridDbSettings;
+
+/// Finds SteamGridDB games by the id a store knows them by
+pub struct PlatformLookup {
+    base_url: String,
+    auth_key: String,
+    /// The external ids of the imported games, by app id
+    external_ids: HashMap<u32, ExternalId>,
+}
+
+#[derive(Deserialize)]
+struct GameResponse {
+    success: bool,
+    data: Option<GameResponseData>,
+}
+
+#[derive(Deserialize)]
+struct GameResponseData {
+    id: usize,
+}
+
+impl PlatformLookup {
+    pub fn new<S: Into<String>>(settings: &SteamGridDbSettings, auth_key: S) -> Self {
+        PlatformLookup {
+            base_url: settings.base_url().to_string(),
+            auth_key: auth_key.into(),
+            external_ids: load_external_ids(),
+        }
+    }
+
+    /// Adds ids for games that are not imported shortcuts, like Steam's own games
+    pub fn with_external_ids(mut self, external_ids: HashMap<u32, ExternalId>) -> Self {
+        self.external_ids.extend(external_ids);
+        self
+    }
+
+    pub fn has_external_id(&self, app_id: u32) -> bool {
+        self.external_ids.contains_key(&app_id)
+    }
+
+    /// Returns the SteamGridDB game id for a shortcut, if we know its external id and SteamGridDB does too
+    pub async fn find_game(&self, app_id: u32) -> Result<Option<usize>, reqwest::Error> {
+        let external_id = match self.external_ids.get(&app_id) {
+            Some(external_id) => external_id,
+            None => return Ok(None),
+        };
+        let url = format!(
+            "{}/api/v2/games/{}/{}",
+            self.base_url,
+            external_id.platform.steamgriddb_name(),
+            external_id.id
+        );
+        let response = reqwest::Client::new()
+            .get(url)
+            .bearer_auth(&self.auth_key)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+        let response = response.json::<GameResponse>().await?;
+        if !response.success {
+            return Ok(None);
+        }
+        Ok(response.data.map(|data| data.id))
+    }
+}
+
+pub fn load_external_ids() -> HashMap<u32, ExternalId> {
+    load_json_file(&get_external_ids_file())
+}
+
+/// Adds the given ids to the saved ones, ids for the same app id are replaced
+pub fn save_external_ids(external_ids: HashMap<u32, ExternalId>) {
+    let mut all_ids = load_external_ids();
+    all_ids.extend(external_ids);
+    save_json_file(&get_external_ids_file(), &all_ids, "external ids");
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use super::ImageType;
+use super::{ImagePreferencesByType, ImageType};
+
+pub const DEFAULT_BASE_URL: &str = "https://www.steamgriddb.com";
+pub const DEFAULT_STEAM_CDN_URL: &str = "https://cdn.cloudflare.steamstatic.com";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SteamGridDbSettings {
@@ -10,9 +13,61 @@ pub struct SteamGridDbSettings {
     pub banned_images: Vec<String>,
     pub only_download_boilr_images: bool,
     pub allow_nsfw: bool,
+    /// How many images are downloaded at the same time
+    pub concurrent_downloads: usize,
+    /// How many times a failed download is retried before giving up
+    pub download_retries: usize,
+    pub transcode_webp: bool,
+    pub resize_large_images: bool,
+    /// Games are searched again when their match is older than this, 0 keeps matches forever
+    pub cache_max_age_days: u64,
+    /// Images are put in the grid folder of every Steam user that has the game
+    #[serde(default)]
+    pub apply_to_all_users: bool,
+    //Only needed when testing against a local server instead of steamgriddb.com
+    pub base_url: Option<String>,
+    pub steam_cdn_url: Option<String>,
+    /// Set from the command line for a single run, used for both urls and never saved
+    #[serde(skip)]
+    pub url_override: Option<String>,
+    #[serde(default)]
+    pub image_preferences: ImagePreferencesByType,
 }
 
 impl SteamGridDbSettings {
+    pub fn base_url(&self) -> &str {
+        self.url_override
+            .as_deref()
+            .or(self.base_url.as_deref())
+            .unwrap_or(DEFAULT_BASE_URL)
+            .trim_end_matches('/')
+    }
+
+    pub fn steam_cdn_url(&self) -> &str {
+        self.url_override
+            .as_deref()
+            .or(self.steam_cdn_url.as_deref())
+            .unwrap_or(DEFAULT_STEAM_CDN_URL)
+            .trim_end_matches('/')
+    }
+
+    pub fn concurrent_downloads(&self) -> usize {
+        self.concurrent_downloads.max(1)
+    }
+
+    pub fn public_game_url(&self, game_id: usize) -> String {
+        format!("{}/api/public/game/{}/", self.base_url(), game_id)
+    }
+
+    /// Creates a SteamGridDB client that talks to the configured base url
+    pub fn client<S: Into<String>>(&self, auth_key: S) -> steamgriddb_api::Client {
+        let mut client = steamgriddb_api::Client::new(auth_key);
+        if self.url_override.is_some() || self.base_url.is_some() {
+            client.set_base_url(format!("{}/api/v2", self.base_url()));
+        }
+        client
+    }
+
     pub fn is_image_banned(&self, image_type: &ImageType, app_id: u32) -> bool {
         let ban_id = format!("{}-{}", app_id, image_type.name());
         self.banned_images.contains(&ban_id)
@@ -0,0 +1,380 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use crate::{
+    config::get_backups_flder,
+    settings::Settings,
+    steam::{
+        backup_collections_db, controller_config_key, get_shortcuts_for_user, get_shortcuts_paths,
+        has_collections_db, is_collections_db_locked, read_boilr_collections, write_collections,
+        write_controller_templates, SteamSettings, SteamUsersInfo,
+    },
+    steamgriddb::{ImageType, GRID_EXTENSIONS},
+};
+
+use super::{
+    synchronization::{remove_old_shortcuts, save_shortcuts},
+    IsBoilRShortcut,
+};
+
+const USAGE: &str = "Usage: boilr --remove-everything [--yes]
+    --remove-everything           shows what would be removed
+    --remove-everything --yes     removes every shortcut, collection and image BoilR created";
+
+/// Everything BoilR created for one Steam user
+#[derive(Debug, Clone, Default)]
+pub struct UserCleanup {
+    pub user_id: String,
+    /// The app ids and names of the shortcuts BoilR made
+    pub shortcuts: Vec<(u32, String)>,
+    pub images: Vec<PathBuf>,
+    pub collections: Vec<String>,
+}
+
+/// What removing everything BoilR created will remove, shown before anything is removed
+#[derive(Debug, Clone, Default)]
+pub struct CleanupPreview {
+    pub users: Vec<UserCleanup>,
+    pub links_folder: Option<PathBuf>,
+}
+
+impl CleanupPreview {
+    pub fn is_empty(&self) -> bool {
+        self.links_folder.is_none()
+            && self.users.iter().all(|user| {
+                user.shortcuts.is_empty() && user.images.is_empty() && user.collections.is_empty()
+            })
+    }
+
+    /// A line per thing that is removed
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![];
+        for user in &self.users {
+            for (app_id, name) in &user.shortcuts {
+                lines.push(format!(
+                    "User {}: shortcut {} ({})",
+                    user.user_id, name, app_id
+                ));
+            }
+            for collection in &user.collections {
+                lines.push(format!("User {}: collection {}", user.user_id, collection));
+            }
+            if !user.images.is_empty() {
+                lines.push(format!(
+                    "User {}: {} images",
+                    user.user_id,
+                    user.images.len()
+                ));
+            }
+        }
+        if let Some(links_folder) = &self.links_folder {
+            lines.push(format!("The links folder {:?}", links_folder));
+        }
+        lines
+    }
+}
+
+/// Finds every shortcut, collection, image and link BoilR created, without removing them
+pub fn preview_cleanup(settings: &Settings) -> eyre::Result<CleanupPreview> {
+    let users = get_shortcuts_paths(&settings.steam)
+        .map_err(|e| eyre::format_err!("Could not find steam shortcuts; {e}"))?;
+    Ok(CleanupPreview {
+        users: users
+            .iter()
+            .map(|user| preview_user(&settings.steam, user))
+            .collect(),
+        links_folder: links_folder(),
+    })
+}
+
+fn preview_user(settings: &SteamSettings, user: &SteamUsersInfo) -> UserCleanup {
+    let shortcut_info = get_shortcuts_for_user(user);
+    let shortcuts: Vec<(u32, String)> = shortcut_info
+        .shortcuts
+        .iter()
+        .filter(|shortcut| shortcut.is_boilr_shortcut())
+        .map(|shortcut| (shortcut.app_id, shortcut.app_name.clone()))
+        .collect();
+    let grid_folder = Path::new(&user.steam_user_data_folder)
+        .join("config")
+        .join("grid");
+    let images = shortcuts
+        .iter()
+        .flat_map(|(app_id, _)| {
+            ImageType::all().iter().flat_map(|image_type| {
+                GRID_EXTENSIONS
+                    .iter()
+                    .map(|ext| grid_folder.join(image_type.file_name(*app_id, ext)))
+            })
+        })
+        .filter(|path| path.exists())
+        .collect();
+    let collections = match read_boilr_collections(settings, &user.user_id) {
+        Ok(collections) => collections.into_iter().map(|c| c.name).collect(),
+        Err(e) => {
+            eprintln!(
+                "Could not read the collections of user {}: {}",
+                user.user_id, e
+            );
+            vec![]
+        }
+    };
+    UserCleanup {
+        user_id: user.user_id.clone(),
+        shortcuts,
+        images,
+        collections,
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn links_folder() -> Option<PathBuf> {
+    let links_folder = crate::config::get_boilr_links_path();
+    links_folder.exists().then_some(links_folder)
+}
+
+#[cfg(not(target_family = "unix"))]
+fn links_folder() -> Option<PathBuf> {
+    None
+}
+
+/// Removes every shortcut, collection, image and link BoilR created.
+/// The shortcuts, images, collections and Steam configs are backed up first,
+/// and Steam has to be stopped so the collections can be written.
+/// Returns what was removed.
+pub fn remove_everything(settings: &Settings) -> eyre::Result<CleanupPreview> {
+    if is_collections_db_locked(&settings.steam) {
+        return Err(eyre::format_err!(
+            "Steam is running, stop it before removing the collections"
+        ));
+    }
+    let users = get_shortcuts_paths(&settings.steam)
+        .map_err(|e| eyre::format_err!("Could not find steam shortcuts; {e}"))?;
+    let preview = preview_cleanup(settings)?;
+    let backup_folder = backup_before_cleanup(settings, &users, &preview)?;
+    println!("Backed up what is removed at: {:?}", backup_folder);
+    crate::ui::backup_shortcuts(&settings.steam);
+
+    for (user, user_cleanup) in users.iter().zip(&preview.users) {
+        let mut shortcut_info = get_shortcuts_for_user(user);
+        if !user_cleanup.shortcuts.is_empty() {
+            remove_old_shortcuts(&mut shortcut_info);
+            save_shortcuts(&shortcut_info.shortcuts, Path::new(&shortcut_info.path));
+        }
+        // Removes the BoilR collections and the games BoilR added to collections made in Steam
+        if has_collections_db(&settings.steam) {
+            if let Err(e) = write_collections(&settings.steam, &user.user_id, Some(&[]), &[]) {
+                eprintln!(
+                    "Could not remove the collections of user {}: {}",
+                    user.user_id, e
+                );
+            }
+        }
+        let boilr_games: Vec<String> = user_cleanup
+            .shortcuts
+            .iter()
+            .map(|(_, name)| controller_config_key(name))
+            .collect();
+        if let Err(e) = write_controller_templates(
+            &settings.steam,
+            &user.user_id,
+            &HashMap::new(),
+            &boilr_games,
+        ) {
+            eprintln!(
+                "Could not remove the controller templates of user {}: {}",
+                user.user_id, e
+            );
+        }
+        for image in &user_cleanup.images {
+            if let Err(e) = std::fs::remove_file(image) {
+                eprintln!("Could not remove {:?}: {}", image, e);
+            }
+        }
+    }
+
+    #[cfg(target_family = "unix")]
+    {
+        let app_ids: Vec<String> = preview
+            .users
+            .iter()
+            .flat_map(|user| user.shortcuts.iter().map(|(app_id, _)| app_id.to_string()))
+            .collect();
+        crate::steam::remove_proton_games(&settings.steam, &app_ids);
+    }
+
+    if let Some(links_folder) = &preview.links_folder {
+        if let Err(e) = std::fs::remove_dir_all(links_folder) {
+            eprintln!("Could not remove {:?}: {}", links_folder, e);
+        }
+    }
+    Ok(preview)
+}
+
+/// Copies the images, the collections database and the Steam configs that are changed
+/// to a new folder in the backups, nothing is removed if that fails
+fn backup_before_cleanup(
+    settings: &Settings,
+    users: &[SteamUsersInfo],
+    preview: &CleanupPreview,
+) -> eyre::Result<PathBuf> {
+    let date_string = Local::now().format("%Y-%m-%d-%H-%M-%S");
+    let backup_folder = get_backups_flder().join(format!("remove-everything-{}", date_string));
+    for (user, user_cleanup) in users.iter().zip(&preview.users) {
+        let user_backup = backup_folder.join(&user.user_id);
+        for image in &user_cleanup.images {
+            if let Some(file_name) = image.file_name() {
+                backup_file(image, &user_backup.join("grid").join(file_name))?;
+            }
+        }
+        let localconfig = Path::new(&user.steam_user_data_folder)
+            .join("config")
+            .join("localconfig.vdf");
+        if localconfig.exists() {
+            backup_file(&localconfig, &user_backup.join("localconfig.vdf"))?;
+        }
+    }
+    // Without a level database there are no collections to back up or remove
+    if has_collections_db(&settings.steam) {
+        backup_collections_db(&settings.steam, &backup_folder.join("leveldb"))
+            .map_err(|e| eyre::format_err!("Could not back up the collections; {e}"))?;
+    }
+    #[cfg(target_family = "unix")]
+    {
+        let config_vdf = crate::steam::get_config_vdf_path(&settings.steam);
+        if let Some(config_vdf) = config_vdf.filter(|path| path.exists()) {
+            backup_file(&config_vdf, &backup_folder.join("config.vdf"))?;
+        }
+    }
+    Ok(backup_folder)
+}
+
+fn backup_file(file: &Path, target: &Path) -> eyre::Result<()> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(file, target)
+        .map_err(|e| eyre::format_err!("Could not back up {:?}; {e}", file))?;
+    Ok(())
+}
+
+pub fn run_cleanup_command(args: &[String]) {
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    let confirmed = match args.as_slice() {
+        ["--remove-everything"] => false,
+        ["--remove-everything", "--yes"] => true,
+        _ => {
+            eprintln!("{}", USAGE);
+            return;
+        }
+    };
+    let settings = match Settings::new() {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Could not load the settings: {}", e);
+            return;
+        }
+    };
+    let result = if confirmed {
+        remove_everything(&settings)
+    } else {
+        preview_cleanup(&settings)
+    };
+    match result {
+        Ok(preview) if preview.is_empty() => println!("BoilR has not created anything"),
+        Ok(preview) => {
+            let verb = if confirmed { "Removed" } else { "Will remove" };
+            for line in preview.describe() {
+                println!("{} {}", verb, line);
+            }
+            if !confirmed {
+                println!("Run boilr --remove-everything --yes to remove it");
+            }
+        }
+        Err(e) => eprintln!("Could not remove what BoilR created: {}", e),
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod tests {
+    use super::*;
+    use crate::config::get_boilr_links_path;
+    use crate::steam::Collection;
+    use crate::sync::fake_steam::{shortcut, FakeSteam};
+    use crate::sync::BOILR_TAG;
+
+    #[test]
+    fn removes_everything_boilr_created() {
+        let steam = FakeSteam::new("cleanup");
+        let other = shortcut("Emulator", "/usr/bin/emulator");
+        let mut boilr = shortcut("Imported", "/games/imported");
+        boilr.tags.push(BOILR_TAG.to_string());
+        steam.add_user("100", Some(&[other.clone(), boilr.clone()][..]));
+        let grid_folder = steam.user_folder("100").join("config").join("grid");
+        std::fs::create_dir_all(&grid_folder).unwrap();
+        let boilr_image = grid_folder.join(ImageType::Hero.file_name(boilr.app_id, "png"));
+        let other_image = grid_folder.join(ImageType::Hero.file_name(other.app_id, "png"));
+        std::fs::write(&boilr_image, "hero").unwrap();
+        std::fs::write(&other_image, "hero").unwrap();
+        std::fs::create_dir_all(get_boilr_links_path()).unwrap();
+        let collection = Collection {
+            name: "Heroic".to_string(),
+            game_ids: vec![boilr.app_id as usize],
+        };
+        let settings = steam.settings();
+        write_collections(&settings.steam, "100", Some(&[collection]), &[]).unwrap();
+
+        let preview = preview_cleanup(&settings).unwrap();
+        assert_eq!(
+            preview.users[0].shortcuts,
+            vec![(boilr.app_id, "Imported".to_string())]
+        );
+        assert_eq!(preview.users[0].images, vec![boilr_image.clone()]);
+        assert_eq!(preview.users[0].collections, vec!["Heroic".to_string()]);
+        assert!(preview.links_folder.is_some());
+        assert_eq!(steam.read_shortcuts("100").len(), 2);
+
+        remove_everything(&settings).unwrap();
+        let shortcuts = steam.read_shortcuts("100");
+        assert_eq!(shortcuts.len(), 1);
+        assert_eq!(shortcuts[0].app_name, "Emulator");
+        assert!(!boilr_image.exists());
+        assert!(other_image.exists());
+        assert!(steam.read_collections("100").is_empty());
+        assert!(!get_boilr_links_path().exists());
+        assert_eq!(std::fs::read_dir(get_backups_flder()).unwrap().count(), 2);
+        let backup_folder = std::fs::read_dir(get_backups_flder())
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| path.is_dir())
+            .unwrap();
+        let backed_up_image = backup_folder
+            .join("100")
+            .join("grid")
+            .join(boilr_image.file_name().unwrap());
+        assert_eq!(std::fs::read_to_string(backed_up_image).unwrap(), "hero");
+        assert!(backup_folder.join("100").join("localconfig.vdf").exists());
+        assert!(backup_folder.join("leveldb").is_dir());
+        assert!(backup_folder.join("config.vdf").exists());
+        assert!(preview_cleanup(&settings).unwrap().is_empty());
+    }
+
+    #[test]
+    fn removes_shortcuts_without_a_collections_database() {
+        let steam = FakeSteam::new("cleanup_no_leveldb");
+        let mut boilr = shortcut("Imported", "/games/imported");
+        boilr.tags.push(BOILR_TAG.to_string());
+        steam.add_user("100", Some(&[boilr][..]));
+        std::fs::remove_dir_all(steam.level_db_path()).unwrap();
+        let settings = steam.settings();
+
+        remove_everything(&settings).unwrap();
+        assert!(steam.read_shortcuts("100").is_empty());
+    }
+}
@@ -0,0 +1,317 @@
+//! A throwaway Steam installation and home folder used by the synchronization tests.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use flate2::{write::GzEncoder, Compression};
+use rusty_leveldb::{Options, DB};
+use steam_shortcuts_util::{parse_shortcuts, shortcut::ShortcutOwned, shortcuts_to_bytes, Shortcut};
+
+use crate::{
+    config::{FolderOverride, FolderOverrideGuard},
+    platforms::{load_platform, GamesPlatform},
+    settings::Settings,
+};
+
+static FIXTURE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub struct FakeSteam {
+    pub root: PathBuf,
+    pub home: PathBuf,
+    pub config: PathBuf,
+    pub steam: PathBuf,
+    _guard: FolderOverrideGuard,
+}
+
+impl FakeSteam {
+    /// Creates an empty Steam root inside a fake home folder,
+    /// and points the config and home lookups of this thread at it.
+    pub fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!(
+            "boilr-test-{}-{}-{}",
+            name,
+            std::process::id(),
+            FIXTURE_COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        let home = root.join("home");
+        let config = home.join(".config").join("boilr");
+        let steam = home.join(".steam").join("steam");
+        std::fs::create_dir_all(&config).unwrap();
+        std::fs::create_dir_all(steam.join("config")).unwrap();
+        std::fs::create_dir_all(steam.join("steamapps")).unwrap();
+
+        let guard = FolderOverride {
+            home: home.clone(),
+            config: config.clone(),
+        }
+        .install();
+
+        let fake = FakeSteam {
+            root,
+            home,
+            config,
+            steam,
+            _guard: guard,
+        };
+        fake.write_steam_config();
+        fake
+    }
+
+    fn write_steam_config(&self) {
+        std::fs::write(
+            self.steam.join("config").join("config.vdf"),
+            include_str!("../testdata/vdf/testconfig.vdf"),
+        )
+        .unwrap();
+        let library = format!(
+            "\"libraryfolders\"\n{{\n\t\"0\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t}}\n}}\n",
+            self.steam.to_string_lossy()
+        );
+        std::fs::write(
+            self.steam.join("steamapps").join("libraryfolders.vdf"),
+            library,
+        )
+        .unwrap();
+        std::fs::write(
+            self.steam.join("steamapps").join("appmanifest_763890.acf"),
+            include_str!("../testdata/acf/appmanifest_763890.acf"),
+        )
+        .unwrap();
+    }
+
+    pub fn settings(&self) -> Settings {
+        let mut settings = Settings::new().unwrap();
+        settings.steam.location = Some(self.steam.to_string_lossy().to_string());
+        settings.steam.create_collections = true;
+        settings.steamgrid_db.enabled = false;
+        settings
+    }
+
+    pub fn user_folder<S: AsRef<str>>(&self, user_id: S) -> PathBuf {
+        self.steam.join("userdata").join(user_id.as_ref())
+    }
+
+    /// Adds a steam user, a shortcuts.vdf is only written if shortcuts are given
+    pub fn add_user<S: AsRef<str>>(&self, user_id: S, shortcuts: Option<&[ShortcutOwned]>) {
+        let config_folder = self.user_folder(&user_id).join("config");
+        std::fs::create_dir_all(&config_folder).unwrap();
+        std::fs::write(
+            config_folder.join("localconfig.vdf"),
+            "\"UserLocalConfigStore\"\n{\n\t\"WebStorage\"\n\t{\n\t\"user-collections\"\t\t\"{}\"\n\t}\n}\n",
+        )
+        .unwrap();
+        if let Some(shortcuts) = shortcuts {
+            write_shortcuts(&config_folder.join("shortcuts.vdf"), shortcuts);
+        }
+        self.add_collections_namespace(user_id.as_ref());
+    }
+
+    pub fn read_shortcuts<S: AsRef<str>>(&self, user_id: S) -> Vec<ShortcutOwned> {
+        let path = self
+            .user_folder(user_id)
+            .join("config")
+            .join("shortcuts.vdf");
+        let content = std::fs::read(path).unwrap();
+        parse_shortcuts(content.as_slice())
+            .unwrap()
+            .iter()
+            .map(|s| s.to_owned())
+            .collect()
+    }
+
+    pub fn level_db_path(&self) -> PathBuf {
+        self.steam
+            .join("config")
+            .join("htmlcache")
+            .join("Local Storage")
+            .join("leveldb")
+    }
+
+    fn namespace_prefix(user_id: &str) -> String {
+        format!(
+            "_https://steamloopback.host\u{0000}\u{0001}U{}-cloud-storage-namespace",
+            user_id
+        )
+    }
+
+    fn add_collections_namespace(&self, user_id: &str) {
+        let path = self.level_db_path();
+        std::fs::create_dir_all(&path).unwrap();
+        let mut db = DB::open(path, Options::default()).unwrap();
+        let prefix = Self::namespace_prefix(user_id);
+        db.put(
+            format!("{}s", prefix).as_bytes(),
+            "\u{1}[[1,\"0\"]]".as_bytes(),
+        )
+        .unwrap();
+        db.put(format!("{}-1", prefix).as_bytes(), "\u{1}[]".as_bytes())
+            .unwrap();
+        db.flush().unwrap();
+    }
+
+    /// Adds a collection made in the Steam client, like Favorites, for a user
+    pub fn add_steam_collection(&self, user_id: &str, id: &str, name: &str, added: &[usize]) {
+        let mut db = DB::open(self.level_db_path(), Options::default()).unwrap();
+        let key = format!("{}-1", Self::namespace_prefix(user_id));
+        let data = db.get(key.as_bytes()).unwrap();
+        let data = String::from_utf8_lossy(&data);
+        let data = data.strip_prefix('\u{1}').unwrap_or(&data);
+        let mut collections: Vec<(String, serde_json::Value)> = serde_json::from_str(data).unwrap();
+        let collection_key = format!("user-collections.{}", id);
+        let value = serde_json::json!({ "id": id, "name": name, "added": added, "removed": [] });
+        let collection = serde_json::json!({
+            "key": collection_key,
+            "timestamp": 1,
+            "value": value.to_string(),
+        });
+        collections.push((collection_key, collection));
+        let data = format!("\u{1}{}", serde_json::to_string(&collections).unwrap());
+        db.put(key.as_bytes(), data.as_bytes()).unwrap();
+        db.flush().unwrap();
+    }
+
+    /// Reads the collections stored for a user, as (name, game ids) pairs
+    pub fn read_collections<S: AsRef<str>>(&self, user_id: S) -> Vec<(String, Vec<usize>)> {
+        let mut db = DB::open(self.level_db_path(), Options::default()).unwrap();
+        let key = format!("{}-1", Self::namespace_prefix(user_id.as_ref()));
+        let data = db.get(key.as_bytes()).unwrap();
+        let data = String::from_utf8_lossy(&data);
+        let data = data.strip_prefix('\u{1}').unwrap_or(&data);
+        let collections: Vec<(String, serde_json::Value)> = serde_json::from_str(data).unwrap();
+        collections
+            .iter()
+            .filter_map(|(_key, collection)| {
+                let value = collection.get("value")?.as_str()?;
+                let value: serde_json::Value = serde_json::from_str(value).ok()?;
+                let name = value.get("name")?.as_str()?.to_string();
+                let added = value
+                    .get("added")?
+                    .as_array()?
+                    .iter()
+                    .filter_map(|id| id.as_u64().map(|id| id as usize))
+                    .collect();
+                Some((name, added))
+            })
+            .collect()
+    }
+
+    /// Adds a game installed through Heroic (legendary), returns the platform that finds it
+    pub fn add_heroic_game(&self, app_name: &str, title: &str) -> Box<dyn GamesPlatform> {
+        let install_path = self.home.join("Games").join("Heroic").join(title);
+        std::fs::create_dir_all(&install_path).unwrap();
+        std::fs::write(install_path.join("game.exe"), "").unwrap();
+        let legendary_folder = self.home.join(".config").join("legendary");
+        std::fs::create_dir_all(&legendary_folder).unwrap();
+        let installed = serde_json::json!({
+            app_name: {
+                "app_name": app_name,
+                "title": title,
+                "is_dlc": false,
+                "install_path": install_path,
+                "executable": "game.exe",
+                "launch_parameters": "",
+            }
+        });
+        std::fs::write(
+            legendary_folder.join("installed.json"),
+            installed.to_string(),
+        )
+        .unwrap();
+        load_platform(
+            "heroic",
+            "enabled = true\nlaunch_games_through_heroic = []\ndefault_launch_through_heroic = false\n",
+        )
+        .unwrap()
+    }
+
+    /// Adds a GOG Galaxy installation with a single game, returns the platform that finds it
+    pub fn add_gog_game(&self, game_id: &str, name: &str) -> Box<dyn GamesPlatform> {
+        let galaxy = self.home.join("Galaxy");
+        let games_folder = self.home.join("GOG Games");
+        let game_folder = games_folder.join(name);
+        std::fs::create_dir_all(&galaxy).unwrap();
+        std::fs::create_dir_all(&game_folder).unwrap();
+        let config = serde_json::json!({ "installationPaths": [games_folder] });
+        std::fs::write(galaxy.join("config.json"), config.to_string()).unwrap();
+        let info = serde_json::json!({
+            "name": name,
+            "gameId": game_id,
+            "playTasks": [{
+                "category": "game",
+                "isPrimary": true,
+                "path": "game.exe",
+                "type": "FileTask",
+            }]
+        });
+        std::fs::write(
+            game_folder.join(format!("goggame-{}.info", game_id)),
+            info.to_string(),
+        )
+        .unwrap();
+        load_platform(
+            "gog",
+            format!(
+                "enabled = true\nlocation = {:?}\ncreate_symlinks = false\n",
+                galaxy.to_string_lossy()
+            ),
+        )
+        .unwrap()
+    }
+
+    /// Adds an itch installation with a single game, returns the platform that finds it
+    #[cfg(target_family = "unix")]
+    pub fn add_itch_game(&self, title: &str) -> Box<dyn GamesPlatform> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let itch = self.home.join(".config").join("itch");
+        let game_folder = itch.join("apps").join(title);
+        std::fs::create_dir_all(itch.join("db")).unwrap();
+        std::fs::create_dir_all(game_folder.join(".itch")).unwrap();
+
+        let executable = game_folder.join("game.sh");
+        std::fs::write(&executable, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&executable, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let receipt = serde_json::json!({ "game": { "title": title } });
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(receipt.to_string().as_bytes()).unwrap();
+        std::fs::write(
+            game_folder.join(".itch").join("receipt.json.gz"),
+            encoder.finish().unwrap(),
+        )
+        .unwrap();
+
+        let db = format!(
+            "{{\"basePath\":\"{}\",\"totalSize\":1,\"candidates\":[{{\"path\":\"game.sh\"}}]}}",
+            game_folder.to_string_lossy()
+        );
+        std::fs::write(itch.join("db").join("butler.db-wal"), db).unwrap();
+        load_platform(
+            "itch",
+            format!(
+                "enabled = true\nlocation = {:?}\ncreate_symlinks = false\n",
+                itch.to_string_lossy()
+            ),
+        )
+        .unwrap()
+    }
+}
+
+impl Drop for FakeSteam {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+pub fn shortcut(name: &str, exe: &str) -> ShortcutOwned {
+    Shortcut::new("0", name, exe, "", "", "", "").to_owned()
+}
+
+pub fn write_shortcuts(path: &Path, shortcuts: &[ShortcutOwned]) {
+    let shortcut_refs: Vec<Shortcut> = shortcuts.iter().map(|s| s.borrow()).collect();
+    std::fs::write(path, shortcuts_to_bytes(&shortcut_refs)).unwrap();
+}
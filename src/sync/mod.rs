@@ -1,9 +1,14 @@
+mod cleanup;
+#[cfg(all(test, target_family = "unix"))]
+pub(crate) mod fake_steam;
+mod progress;
 #[cfg(target_family = "unix")]
 pub mod symlinks;
 mod synchronization;
 
+pub use cleanup::*;
+pub use progress::*;
 pub use synchronization::download_images;
 
 pub use synchronization::IsBoilRShortcut;
-pub use synchronization::SyncProgress;
 pub use synchronization::*;
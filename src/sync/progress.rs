@@ -0,0 +1,117 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use tokio::sync::watch::Sender;
+
+#[derive(Debug, Clone)]
+pub enum SyncProgress {
+    NotStarted,
+    Starting,
+    FoundGames {
+        games_found: usize,
+        platforms: Vec<(String, usize)>,
+    },
+    SyncingUser {
+        user_id: String,
+        user_index: usize,
+        users_total: usize,
+    },
+    FindingImages,
+    DownloadingImages {
+        to_download: usize,
+        completed: usize,
+        failed: usize,
+        current_game: String,
+    },
+    Cancelled,
+    Failed(String),
+    Done,
+}
+
+impl SyncProgress {
+    pub fn is_running(&self) -> bool {
+        !matches!(
+            self,
+            SyncProgress::NotStarted
+                | SyncProgress::Cancelled
+                | SyncProgress::Failed(_)
+                | SyncProgress::Done
+        )
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            SyncProgress::NotStarted => "".to_string(),
+            SyncProgress::Starting => "Starting Import".to_string(),
+            SyncProgress::FoundGames {
+                games_found,
+                platforms,
+            } => {
+                let per_platform = platforms
+                    .iter()
+                    .map(|(name, count)| format!("{name}: {count}"))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                if per_platform.is_empty() {
+                    format!("Found {games_found} games to import")
+                } else {
+                    format!("Found {games_found} games to import ({per_platform})")
+                }
+            }
+            SyncProgress::SyncingUser {
+                user_id,
+                user_index,
+                users_total,
+            } => format!(
+                "Writing shortcuts for user {user_id} ({}/{users_total})",
+                user_index + 1
+            ),
+            SyncProgress::FindingImages => "Searching for images".to_string(),
+            SyncProgress::DownloadingImages {
+                to_download,
+                completed,
+                failed,
+                current_game,
+            } => {
+                let mut status = format!("Downloading images {completed}/{to_download}");
+                if *failed > 0 {
+                    status.push_str(&format!(" ({failed} failed)"));
+                }
+                if !current_game.is_empty() {
+                    status.push_str(&format!(" - {current_game}"));
+                }
+                status
+            }
+            SyncProgress::Cancelled => "Import was cancelled".to_string(),
+            SyncProgress::Failed(error) => format!("Import failed: {error}"),
+            SyncProgress::Done => "Done importing games".to_string(),
+        }
+    }
+}
+
+pub fn send_progress(sender: &Option<Sender<SyncProgress>>, progress: SyncProgress) {
+    if let Some(sender) = sender {
+        let _ = sender.send(progress);
+    }
+}
+
+/// Shared flag used to stop a running synchronization.
+///
+/// The sync only checks it between steps, so a cancel never interrupts
+/// a write to shortcuts.vdf or to an image file.
+#[derive(Clone, Default, Debug)]
+pub struct SyncCancel {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SyncCancel {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
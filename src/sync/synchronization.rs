@@ -5,29 +5,25 @@ use steam_shortcuts_util::{
 use tokio::sync::watch::Sender;
 
 use crate::{
+    local_artwork::apply_local_artwork,
     platforms::{GamesPlatform, ShortcutToImport},
     settings::Settings,
     steam::{
-        get_shortcuts_for_user, get_shortcuts_paths, write_collections, Collection, ShortcutInfo,
-        SteamUsersInfo,
+        controller_config_key, get_shortcuts_for_user, get_shortcuts_paths,
+        is_collections_db_locked, write_collections, write_controller_templates, Collection,
+        CollectionRules, ControllerTemplate, ShortcutInfo, SteamSettings, SteamUsersInfo,
+        HIDDEN_COLLECTION,
     },
-    steamgriddb::{download_images_for_users, ImageType},
+    steamgriddb::{download_images_for_users, save_external_ids, ImageType},
 };
 
 use std::{collections::HashMap, error::Error};
 
 use std::{fs::File, io::Write, path::Path};
 
-pub const BOILR_TAG: &str = "boilr";
+use super::{send_progress, SyncCancel, SyncProgress};
 
-pub enum SyncProgress {
-    NotStarted,
-    Starting,
-    FoundGames { games_found: usize },
-    FindingImages,
-    DownloadingImages { to_download: usize },
-    Done,
-}
+pub const BOILR_TAG: &str = "boilr";
 
 pub fn disconnect_shortcut(settings: &Settings, app_id: u32) -> Result<(), String> {
     let mut userinfo_shortcuts = get_shortcuts_paths(&settings.steam)
@@ -53,40 +49,75 @@ pub fn sync_shortcuts(
     platform_shortcuts: &[(String, Vec<ShortcutOwned>)],
     sender: &mut Option<Sender<SyncProgress>>,
     renames: &HashMap<u32, String>,
+    cancel: &SyncCancel,
 ) -> Result<Vec<SteamUsersInfo>, String> {
     let mut userinfo_shortcuts = get_shortcuts_paths(&settings.steam)
         .map_err(|e| format!("Getting shortcut paths failed: {e}"))?;
-    let mut all_shortcuts: Vec<ShortcutOwned> = platform_shortcuts
+    // The shortcuts that are written, by platform, so the collections get the same games and app ids
+    let mut platform_games: Vec<(String, Vec<ShortcutOwned>)> = platform_shortcuts
         .iter()
-        .flat_map(|s| s.1.clone())
-        .filter(|s| !settings.blacklisted_games.contains(&s.app_id))
+        .map(|(platform, shortcuts)| {
+            let shortcuts = shortcuts
+                .iter()
+                .filter(|s| !settings.blacklisted_games.contains(&s.app_id))
+                .map(|s| prepare_shortcut(settings, platform, s))
+                .collect();
+            (platform.clone(), shortcuts)
+        })
         .collect();
-    for shortcut in &mut all_shortcuts {
-        shortcut.dev_kit_game_id = BOILR_TAG.to_string();
-    }
-    if let Some(sender) = &sender {
-        let _ = sender.send(SyncProgress::FoundGames {
-            games_found: all_shortcuts.len(),
-        });
-    }
-    for shortcut in &mut all_shortcuts {
-        if let Some(rename) = renames.get(&shortcut.app_id) {
-            shortcut.app_name = rename.clone();
-            let new_shortcut = Shortcut::new(
-                "0",
-                shortcut.app_name.as_str(),
-                &shortcut.exe,
-                "",
-                "",
-                "",
-                "",
-            );
-            shortcut.app_id = calculate_app_id_for_shortcut(&new_shortcut);
+    let platforms = platform_games
+        .iter()
+        .map(|(name, shortcuts)| (name.clone(), shortcuts.len()))
+        .collect();
+    let games_found = platform_games.iter().map(|(_, s)| s.len()).sum();
+    send_progress(
+        sender,
+        SyncProgress::FoundGames {
+            games_found,
+            platforms,
+        },
+    );
+    let controller_templates = get_controller_templates(settings, platform_shortcuts, renames);
+    for (_, shortcuts) in &mut platform_games {
+        for shortcut in shortcuts {
+            apply_rename(shortcut, renames);
+            println!("Appid: {} name: {}", shortcut.app_id, shortcut.app_name);
         }
-        println!("Appid: {} name: {}", shortcut.app_id, shortcut.app_name);
+    }
+    let all_shortcuts: Vec<ShortcutOwned> = platform_games
+        .iter()
+        .flat_map(|(_, shortcuts)| shortcuts.iter().cloned())
+        .collect();
+    let hidden = Collection {
+        name: HIDDEN_COLLECTION.to_string(),
+        game_ids: all_shortcuts
+            .iter()
+            .filter(|s| s.is_hidden)
+            .map(|s| s.app_id as usize)
+            .collect(),
+    };
+    // Checked before any shortcuts are written, instead of failing after they are
+    let wants_collections = settings.steam.create_collections || !hidden.game_ids.is_empty();
+    let can_write_collections = !wants_collections || !is_collections_db_locked(&settings.steam);
+    if !can_write_collections {
+        eprintln!("Steam is running, so collections and hidden games are not written. Close Steam or turn on \"Stop Steam before import\"");
     }
     println!("Found {} user(s)", userinfo_shortcuts.len());
-    for user in userinfo_shortcuts.iter_mut() {
+    let users_total = userinfo_shortcuts.len();
+    for (user_index, user) in userinfo_shortcuts.iter_mut().enumerate() {
+        // Only stop between users, so a shortcuts file is never left half written
+        if cancel.is_cancelled() {
+            println!("Synchronization cancelled");
+            break;
+        }
+        send_progress(
+            sender,
+            SyncProgress::SyncingUser {
+                user_id: user.user_id.clone(),
+                user_index,
+                users_total,
+            },
+        );
         let start_time = std::time::Instant::now();
 
         let mut shortcut_info = get_shortcuts_for_user(user);
@@ -96,15 +127,45 @@ pub fn sync_shortcuts(
             user.user_id
         );
 
+        // Templates of shortcuts that are no longer imported are taken out again
+        let boilr_games: Vec<String> = shortcut_info
+            .shortcuts
+            .iter()
+            .chain(all_shortcuts.iter())
+            .filter(|s| s.is_boilr_shortcut())
+            .map(|s| controller_config_key(&s.app_name))
+            .collect();
         remove_old_shortcuts(&mut shortcut_info);
         remove_shortcuts_with_same_appid(&mut shortcut_info, &all_shortcuts);
 
-        shortcut_info.shortcuts.extend(all_shortcuts.clone());     
+        shortcut_info.shortcuts.extend(all_shortcuts.clone());
 
         save_shortcuts(&shortcut_info.shortcuts, Path::new(&shortcut_info.path));
 
-        if settings.steam.create_collections {
-            match write_shortcut_collections(&user.user_id, platform_shortcuts) {
+        if let Err(e) = write_controller_templates(
+            &settings.steam,
+            &user.user_id,
+            &controller_templates,
+            &boilr_games,
+        ) {
+            eprintln!(
+                "Could not write the controller templates for user {}: {}",
+                user.user_id, e
+            );
+        }
+
+        if can_write_collections {
+            let collection_rules = settings
+                .steam
+                .create_collections
+                .then_some(&settings.steam.collection_rules);
+            match write_shortcut_collections(
+                &settings.steam,
+                &user.user_id,
+                &platform_games,
+                collection_rules,
+                &hidden,
+            ) {
                 Ok(_) => (),
                 Err(_e) => eprintln!("Could not write collections, make sure steam is shut down"),
             }
@@ -116,17 +177,106 @@ pub fn sync_shortcuts(
     Ok(userinfo_shortcuts)
 }
 
+/// Turns a game found on a platform into the shortcut BoilR writes, before it is renamed
+fn prepare_shortcut(
+    settings: &Settings,
+    platform: &str,
+    shortcut: &ShortcutOwned,
+) -> ShortcutOwned {
+    let mut shortcut = shortcut.clone();
+    settings.steam.launch_options.apply(platform, &mut shortcut);
+    shortcut.dev_kit_game_id = BOILR_TAG.to_string();
+    shortcut.is_hidden = settings.hidden_games.contains(&shortcut.app_id);
+    shortcut
+}
+
+/// The shortcut a sync writes for a game, with every setting and rename applied
+pub fn generated_shortcut(
+    settings: &Settings,
+    platform: &str,
+    shortcut: &ShortcutOwned,
+    renames: &HashMap<u32, String>,
+) -> ShortcutOwned {
+    let mut shortcut = prepare_shortcut(settings, platform, shortcut);
+    apply_rename(&mut shortcut, renames);
+    shortcut
+}
+
+fn apply_rename(shortcut: &mut ShortcutOwned, renames: &HashMap<u32, String>) {
+    if let Some(rename) = renames.get(&shortcut.app_id) {
+        shortcut.app_name = rename.clone();
+        let new_shortcut = Shortcut::new(
+            "0",
+            shortcut.app_name.as_str(),
+            &shortcut.exe,
+            "",
+            "",
+            "",
+            "",
+        );
+        shortcut.app_id = calculate_app_id_for_shortcut(&new_shortcut);
+    }
+}
+
+/// The Steam Input template of each imported game, by the name it has after it is renamed
+fn get_controller_templates(
+    settings: &Settings,
+    platform_shortcuts: &[(String, Vec<ShortcutOwned>)],
+    renames: &HashMap<u32, String>,
+) -> HashMap<String, ControllerTemplate> {
+    let templates = &settings.steam.controller_templates;
+    let mut result = HashMap::new();
+    for (platform, shortcuts) in platform_shortcuts {
+        for shortcut in shortcuts
+            .iter()
+            .filter(|s| !settings.blacklisted_games.contains(&s.app_id))
+        {
+            if let Some(template) = templates.template_for(platform, shortcut.app_id) {
+                let mut shortcut = shortcut.clone();
+                apply_rename(&mut shortcut, renames);
+                result.insert(controller_config_key(&shortcut.app_name), *template);
+            }
+        }
+    }
+    result
+}
+
+/// Remembers the platform ids of the games being imported,
+/// so their images can be found by id instead of by name.
+pub fn save_shortcut_external_ids(
+    platform_shortcuts: &[(String, Vec<ShortcutToImport>)],
+    renames: &HashMap<u32, String>,
+) {
+    let external_ids = platform_shortcuts
+        .iter()
+        .flat_map(|(_, shortcuts)| shortcuts)
+        .filter_map(|info| {
+            let external_id = info.external_id.clone()?;
+            let mut shortcut = info.shortcut.clone();
+            apply_rename(&mut shortcut, renames);
+            Some((shortcut.app_id, external_id))
+        })
+        .collect();
+    save_external_ids(external_ids);
+}
+
 pub async fn download_images(
     settings: &Settings,
     userinfo_shortcuts: &[SteamUsersInfo],
     sender: &mut Option<Sender<SyncProgress>>,
+    cancel: &SyncCancel,
 ) {
+    // Local artwork goes first, so SteamGridDB only fills in what is missing
+    apply_local_artwork(&settings.local_artwork, userinfo_shortcuts);
     if settings.steamgrid_db.enabled {
         if settings.steamgrid_db.prefer_animated {
             println!("downloading animated images");
-            download_images_for_users(settings, userinfo_shortcuts, true, sender).await;
+            download_images_for_users(settings, userinfo_shortcuts, true, sender, cancel).await;
         }
-        download_images_for_users(settings, userinfo_shortcuts, false, sender).await;
+        if cancel.is_cancelled() {
+            return;
+        }
+        download_images_for_users(settings, userinfo_shortcuts, false, sender, cancel).await;
     }
 }
 
@@ -151,7 +301,7 @@ fn remove_shortcuts_with_same_appid(
         .retain(|shortcut| !app_ids.contains(&shortcut.app_id));
 }
 
-fn remove_old_shortcuts(shortcut_info: &mut ShortcutInfo) {
+pub(super) fn remove_old_shortcuts(shortcut_info: &mut ShortcutInfo) {
     shortcut_info
         .shortcuts
         .retain(|shortcut| !shortcut.is_boilr_shortcut());
@@ -203,20 +353,29 @@ fn fix_shortcut_icons(
 }
 
 fn write_shortcut_collections<S: AsRef<str>>(
+    settings: &SteamSettings,
     steam_id: S,
     platform_results: &[(String, Vec<ShortcutOwned>)],
+    rules: Option<&CollectionRules>,
+    hidden: &Collection,
 ) -> Result<(), Box<dyn Error>> {
-    let mut collections = vec![];
-
-    for (name, shortcuts) in platform_results {
-        let game_ids = shortcuts.iter().map(|s| (s.app_id as usize)).collect();
-        collections.push(Collection {
-            name: name.clone(),
-            game_ids,
-        });
-    }
-    println!("Writing {} collections ", collections.len());
-    write_collections(steam_id.as_ref(), &collections)?;
+    // Hidden games go in the hidden collection of Steam, also when BoilR makes no collections
+    let (collections, mut additions) = match rules {
+        Some(rules) => {
+            let collections = rules.layout_collections(platform_results);
+            println!("Writing {} collections ", collections.len());
+            let additions = rules.existing_collection_additions(platform_results);
+            (Some(collections), additions)
+        }
+        None => (None, vec![]),
+    };
+    additions.push(hidden.clone());
+    write_collections(
+        settings,
+        steam_id.as_ref(),
+        collections.as_deref(),
+        &additions,
+    )?;
     Ok(())
 }
 
@@ -230,7 +389,7 @@ pub fn get_platform_shortcuts(
     }
 }
 
-fn save_shortcuts(shortcuts: &[ShortcutOwned], path: &Path) {
+pub(super) fn save_shortcuts(shortcuts: &[ShortcutOwned], path: &Path) {
     let mut shortcuts_refs = vec![];
     for shortcut in shortcuts {
         shortcuts_refs.push(shortcut.borrow());
@@ -256,3 +415,489 @@ fn save_shortcuts(shortcuts: &[ShortcutOwned], path: &Path) {
         }
     }
 }
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod tests {
+    use super::super::fake_steam::{shortcut, FakeSteam};
+    use super::*;
+    use crate::platforms::{ExternalId, ExternalPlatform, GamesPlatform};
+    use crate::steam::template_from_text;
+
+    fn platform_shortcuts(
+        platforms: Vec<Box<dyn GamesPlatform>>,
+    ) -> Vec<(String, Vec<ShortcutOwned>)> {
+        platforms
+            .into_iter()
+            .map(|platform| {
+                let name = platform.name().to_string();
+                let shortcuts = get_platform_shortcuts(platform)
+                    .unwrap()
+                    .into_iter()
+                    .map(|s| s.shortcut)
+                    .collect();
+                (name, shortcuts)
+            })
+            .collect()
+    }
+
+    fn names(shortcuts: &[ShortcutOwned]) -> Vec<String> {
+        let mut names: Vec<String> = shortcuts.iter().map(|s| s.app_name.clone()).collect();
+        names.sort();
+        names
+    }
+
+    fn fixture_with_games(name: &str) -> (FakeSteam, Vec<(String, Vec<ShortcutOwned>)>) {
+        let steam = FakeSteam::new(name);
+        let manual = shortcut("Manual Game", "/usr/bin/manual");
+        let mut old_boilr = shortcut("Uninstalled Game", "/usr/bin/uninstalled");
+        old_boilr.dev_kit_game_id = BOILR_TAG.to_string();
+        steam.add_user("100", Some(&[manual, old_boilr][..]));
+        steam.add_user("200", None);
+
+        let platforms = vec![
+            steam.add_heroic_game("Fish", "Heroic Game"),
+            steam.add_gog_game("1207658924", "Gog Game"),
+            steam.add_itch_game("Itch Game"),
+        ];
+        let games = platform_shortcuts(platforms);
+        (steam, games)
+    }
+
+    #[test]
+    fn fixture_platforms_find_games() {
+        let (_steam, games) = fixture_with_games("platforms");
+        let found: Vec<(String, usize)> = games
+            .iter()
+            .map(|(name, shortcuts)| (name.clone(), shortcuts.len()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Heroic".to_string(), 1),
+                ("GOG".to_string(), 1),
+                ("Itch".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn sync_writes_shortcuts_for_all_users() {
+        let (steam, games) = fixture_with_games("sync");
+        let settings = steam.settings();
+
+        let users = sync_shortcuts(
+            &settings,
+            &games,
+            &mut None,
+            &HashMap::new(),
+            &SyncCancel::default(),
+        )
+        .unwrap();
+        assert_eq!(users.len(), 2);
+
+        let first_user = steam.read_shortcuts("100");
+        assert_eq!(
+            names(&first_user),
+            vec!["Gog Game", "Heroic Game", "Itch Game", "Manual Game"]
+        );
+        for shortcut in first_user.iter().filter(|s| s.app_name != "Manual Game") {
+            assert!(shortcut.is_boilr_shortcut());
+        }
+        let manual = first_user
+            .iter()
+            .find(|s| s.app_name == "Manual Game")
+            .unwrap();
+        assert!(!manual.is_boilr_shortcut());
+
+        let second_user = steam.read_shortcuts("200");
+        assert_eq!(
+            names(&second_user),
+            vec!["Gog Game", "Heroic Game", "Itch Game"]
+        );
+    }
+
+    #[test]
+    fn sync_writes_collections() {
+        let (steam, games) = fixture_with_games("collections");
+        let settings = steam.settings();
+
+        sync_shortcuts(
+            &settings,
+            &games,
+            &mut None,
+            &HashMap::new(),
+            &SyncCancel::default(),
+        )
+        .unwrap();
+
+        let mut collections = steam.read_collections("100");
+        collections.sort();
+        let collection_names: Vec<&str> = collections.iter().map(|c| c.0.as_str()).collect();
+        assert_eq!(collection_names, vec!["GOG", "Heroic", "Itch"]);
+        let gog_id = games[1].1[0].app_id as usize;
+        assert_eq!(collections[0].1, vec![gog_id]);
+    }
+
+    #[test]
+    fn sync_writes_collections_from_rules() {
+        let (steam, games) = fixture_with_games("collection_rules");
+        let mut settings = steam.settings();
+        let rules = &mut settings.steam.collection_rules;
+        rules.name_template = "BoilR: {platform}".to_string();
+        rules.merged_platforms.push(crate::steam::MergedPlatforms {
+            name: "Galaxy".to_string(),
+            platforms: vec!["GOG".to_string(), "Heroic".to_string()],
+        });
+        rules.tag_collections.push(crate::steam::TagCollection {
+            name: "Installed".to_string(),
+            tags: vec!["installed".to_string()],
+        });
+
+        sync_shortcuts(
+            &settings,
+            &games,
+            &mut None,
+            &HashMap::new(),
+            &SyncCancel::default(),
+        )
+        .unwrap();
+
+        let mut collections = steam.read_collections("100");
+        collections.sort();
+        let sizes: Vec<(&str, usize)> = collections
+            .iter()
+            .map(|c| (c.0.as_str(), c.1.len()))
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![("BoilR: Galaxy", 2), ("BoilR: Itch", 1), ("Installed", 3)]
+        );
+    }
+
+    #[test]
+    fn sync_adds_games_to_existing_collections() {
+        let (steam, games) = fixture_with_games("existing_collections");
+        let heroic_id = games[0].1[0].app_id as usize;
+        let gog_id = games[1].1[0].app_id as usize;
+        let itch_id = games[2].1[0].app_id as usize;
+        steam.add_steam_collection("100", "favorite", "Favorites", &[1, heroic_id]);
+        steam.add_steam_collection("100", "uc-couch", "Couch", &[2]);
+        let mut settings = steam.settings();
+        let rules = &mut settings.steam.collection_rules;
+        rules.existing_collections = vec![
+            crate::steam::ExistingCollection {
+                name: "favorites".to_string(),
+                platforms: vec!["GOG".to_string(), "Heroic".to_string()],
+                tags: vec![],
+                games: vec![],
+            },
+            crate::steam::ExistingCollection {
+                name: "Couch".to_string(),
+                platforms: vec![],
+                tags: vec![],
+                games: vec!["Itch Game".to_string()],
+            },
+        ];
+        let sync = |settings: &Settings| {
+            sync_shortcuts(
+                settings,
+                &games,
+                &mut None,
+                &HashMap::new(),
+                &SyncCancel::default(),
+            )
+            .unwrap();
+            let mut collections = steam.read_collections("100");
+            collections.sort();
+            collections
+        };
+
+        let collections = sync(&settings);
+        let couch = collections.iter().find(|c| c.0 == "Couch").unwrap();
+        assert_eq!(couch.1, vec![2, itch_id]);
+        let favorites = collections.iter().find(|c| c.0 == "Favorites").unwrap();
+        assert_eq!(favorites.1, vec![1, heroic_id, gog_id]);
+
+        // Only the games BoilR added are taken out again
+        settings.steam.collection_rules.existing_collections.clear();
+        let collections = sync(&settings);
+        let couch = collections.iter().find(|c| c.0 == "Couch").unwrap();
+        assert_eq!(couch.1, vec![2]);
+        let favorites = collections.iter().find(|c| c.0 == "Favorites").unwrap();
+        assert_eq!(favorites.1, vec![1, heroic_id]);
+    }
+
+    #[test]
+    fn sync_imports_hidden_games() {
+        let (steam, games) = fixture_with_games("hidden");
+        let mut settings = steam.settings();
+        settings.steam.create_collections = false;
+        let itch_id = games[2].1[0].app_id;
+        settings.hidden_games.push(itch_id);
+
+        sync_shortcuts(
+            &settings,
+            &games,
+            &mut None,
+            &HashMap::new(),
+            &SyncCancel::default(),
+        )
+        .unwrap();
+
+        let shortcuts = steam.read_shortcuts("100");
+        for shortcut in &shortcuts {
+            assert_eq!(shortcut.is_hidden, shortcut.app_id == itch_id);
+        }
+        let collections = steam.read_collections("100");
+        assert_eq!(
+            collections,
+            vec![(HIDDEN_COLLECTION.to_string(), vec![itch_id as usize])]
+        );
+    }
+
+    #[test]
+    fn sync_writes_controller_templates() {
+        let (steam, games) = fixture_with_games("controller_templates");
+        let mut settings = steam.settings();
+        let heroic_id = games[0].1[0].app_id;
+        let gog_id = games[1].1[0].app_id;
+        let templates = &mut settings.steam.controller_templates;
+        templates.set_platform_template("Heroic", Some(ControllerTemplate::KeyboardAndMouse));
+        templates.set_game_template(gog_id, Some(ControllerTemplate::Gamepad));
+        let config_set = steam
+            .steam
+            .join("steamapps")
+            .join("common")
+            .join("Steam Controller Configs")
+            .join("100")
+            .join("config")
+            .join("configset_controller_neptune.vdf");
+
+        let sync = |settings: &Settings| {
+            sync_shortcuts(
+                settings,
+                &games,
+                &mut None,
+                &HashMap::new(),
+                &SyncCancel::default(),
+            )
+            .unwrap();
+        };
+        sync(&settings);
+        let content = std::fs::read_to_string(&config_set).unwrap();
+        assert!(content
+            .contains("\"heroic game\"\n\t{\n\t\t\"template\"\t\t\"controller_neptune_wasd.vdf\""));
+        assert!(content.contains(
+            "\"gog game\"\n\t{\n\t\t\"template\"\t\t\"controller_neptune_gamepad_joystick.vdf\""
+        ));
+
+        settings.blacklisted_games.push(heroic_id);
+        sync(&settings);
+        let content = std::fs::read_to_string(&config_set).unwrap();
+        assert!(!content.contains("\"heroic game\""));
+        assert!(content.contains("\"gog game\""));
+    }
+
+    #[test]
+    fn sync_applies_launch_option_templates() {
+        let (steam, games) = fixture_with_games("launch_options");
+        let mut settings = steam.settings();
+        let gog_id = games[1].1[0].app_id;
+        let templates = &mut settings.steam.launch_options;
+        templates.set_platform_template("GOG", template_from_text("gamemoderun %command%"));
+        templates.set_platform_template(
+            "Itch",
+            template_from_text("DXVK_ASYNC=1 %command% -fullscreen"),
+        );
+        templates.set_game_template(gog_id, template_from_text("mangohud %command%"));
+
+        sync_shortcuts(
+            &settings,
+            &games,
+            &mut None,
+            &HashMap::new(),
+            &SyncCancel::default(),
+        )
+        .unwrap();
+
+        let shortcuts = steam.read_shortcuts("100");
+        let launch_options = |name: &str| {
+            let shortcut = shortcuts.iter().find(|s| s.app_name == name).unwrap();
+            shortcut.launch_options.clone()
+        };
+        let original = |index: usize| games[index].1[0].launch_options.clone();
+        assert_eq!(launch_options("Heroic Game"), original(0));
+        assert!(launch_options("Gog Game").starts_with("mangohud %command%"));
+        assert!(launch_options("Itch Game").starts_with("DXVK_ASYNC=1 %command%"));
+        assert!(launch_options("Itch Game").ends_with("-fullscreen"));
+    }
+
+    #[test]
+    fn sync_respects_blacklist_and_renames() {
+        let (steam, games) = fixture_with_games("renames");
+        let mut settings = steam.settings();
+        let heroic_id = games[0].1[0].app_id;
+        let gog_id = games[1].1[0].app_id;
+        settings.blacklisted_games.push(heroic_id);
+        let mut renames = HashMap::new();
+        renames.insert(gog_id, "Renamed Game".to_string());
+
+        sync_shortcuts(
+            &settings,
+            &games,
+            &mut None,
+            &renames,
+            &SyncCancel::default(),
+        )
+        .unwrap();
+
+        let shortcuts = steam.read_shortcuts("200");
+        assert_eq!(names(&shortcuts), vec!["Itch Game", "Renamed Game"]);
+        let renamed = shortcuts
+            .iter()
+            .find(|s| s.app_name == "Renamed Game")
+            .unwrap();
+        assert_ne!(renamed.app_id, gog_id);
+    }
+
+    #[test]
+    fn sync_writes_collections_of_renamed_games() {
+        let (steam, games) = fixture_with_games("renamed_collections");
+        let mut settings = steam.settings();
+        let heroic_id = games[0].1[0].app_id;
+        let gog_id = games[1].1[0].app_id;
+        settings.blacklisted_games.push(heroic_id);
+        settings
+            .steam
+            .collection_rules
+            .set_game_collections("Renamed Game", &["Favourites".to_string()]);
+        let mut renames = HashMap::new();
+        renames.insert(gog_id, "Renamed Game".to_string());
+
+        sync_shortcuts(
+            &settings,
+            &games,
+            &mut None,
+            &renames,
+            &SyncCancel::default(),
+        )
+        .unwrap();
+
+        let renamed_id = steam
+            .read_shortcuts("100")
+            .iter()
+            .find(|s| s.app_name == "Renamed Game")
+            .unwrap()
+            .app_id as usize;
+        let mut collections = steam.read_collections("100");
+        collections.sort();
+        assert_eq!(
+            collections,
+            vec![
+                ("Favourites".to_string(), vec![renamed_id]),
+                ("GOG".to_string(), vec![renamed_id]),
+                ("Itch".to_string(), vec![games[2].1[0].app_id as usize]),
+            ]
+        );
+    }
+
+    #[test]
+    fn generated_shortcut_is_the_shortcut_sync_writes() {
+        let (steam, games) = fixture_with_games("generated");
+        let mut settings = steam.settings();
+        let gog = &games[1].1[0];
+        settings.hidden_games.push(gog.app_id);
+        settings
+            .steam
+            .launch_options
+            .set_game_template(gog.app_id, template_from_text("gamemoderun %command%"));
+        let mut renames = HashMap::new();
+        renames.insert(gog.app_id, "Renamed Game".to_string());
+        settings
+            .steam
+            .collection_rules
+            .set_game_collections("Renamed Game", &["Shooters".to_string()]);
+
+        let generated = generated_shortcut(&settings, "GOG", gog, &renames);
+        sync_shortcuts(
+            &settings,
+            &games,
+            &mut None,
+            &renames,
+            &SyncCancel::default(),
+        )
+        .unwrap();
+
+        let shortcuts = steam.read_shortcuts("100");
+        let written = shortcuts
+            .iter()
+            .find(|s| s.app_id == generated.app_id)
+            .unwrap();
+        assert_eq!(written.app_name, "Renamed Game");
+        assert_eq!(written.launch_options, generated.launch_options);
+        assert!(generated.launch_options.starts_with("gamemoderun"));
+        assert!(written.is_hidden && generated.is_hidden);
+        assert!(generated.is_boilr_shortcut());
+        let collections = steam.read_collections("100");
+        let shooters = collections.iter().find(|c| c.0 == "Shooters").unwrap();
+        assert_eq!(shooters.1, vec![generated.app_id as usize]);
+    }
+
+    #[test]
+    fn cancelled_sync_leaves_shortcuts_untouched() {
+        let (steam, games) = fixture_with_games("cancel");
+        let settings = steam.settings();
+        let before = std::fs::read(steam.user_folder("100").join("config").join("shortcuts.vdf"))
+            .unwrap();
+
+        let cancel = SyncCancel::default();
+        cancel.cancel();
+        sync_shortcuts(&settings, &games, &mut None, &HashMap::new(), &cancel).unwrap();
+
+        let after = std::fs::read(steam.user_folder("100").join("config").join("shortcuts.vdf"))
+            .unwrap();
+        assert_eq!(before, after);
+        assert!(!steam
+            .user_folder("200")
+            .join("config")
+            .join("shortcuts.vdf")
+            .exists());
+    }
+
+    #[test]
+    fn saves_external_ids_of_imported_games() {
+        let steam = FakeSteam::new("external_ids");
+        let platforms = vec![
+            steam.add_heroic_game("Fish", "Heroic Game"),
+            steam.add_gog_game("1207658924", "Gog Game"),
+            steam.add_itch_game("Itch Game"),
+        ];
+        let games: Vec<(String, Vec<ShortcutToImport>)> = platforms
+            .into_iter()
+            .map(|platform| {
+                let name = platform.name().to_string();
+                (name, get_platform_shortcuts(platform).unwrap())
+            })
+            .collect();
+        let heroic_id = games[0].1[0].shortcut.app_id;
+        let gog_id = games[1].1[0].shortcut.app_id;
+        let mut renames = HashMap::new();
+        renames.insert(gog_id, "Renamed Game".to_string());
+
+        save_shortcut_external_ids(&games, &renames);
+
+        let saved = crate::steamgriddb::load_external_ids();
+        assert_eq!(saved.len(), 2);
+        assert_eq!(
+            saved.get(&heroic_id),
+            Some(&ExternalId::new(ExternalPlatform::Egs, "Fish"))
+        );
+        let mut renamed = games[1].1[0].shortcut.clone();
+        apply_rename(&mut renamed, &renames);
+        assert_eq!(
+            saved.get(&renamed.app_id),
+            Some(&ExternalId::new(ExternalPlatform::Gog, "1207658924"))
+        );
+    }
+}
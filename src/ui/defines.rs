@@ -65,6 +65,25 @@ pub mod ui_images {
         }
     }
 
+    /// Decodes an image on the current thread, shrunk to at most the given height
+    pub fn load_thumbnail_from_path(
+        path: &std::path::Path,
+        max_height: u32,
+    ) -> eyre::Result<egui::ColorImage> {
+        let image = image::io::Reader::open(path)?
+            .with_guessed_format()?
+            .decode()?;
+        let image = if image.height() > max_height {
+            image.thumbnail(u32::MAX, max_height)
+        } else {
+            image
+        };
+        let size = [image.width() as _, image.height() as _];
+        let image_buffer = image.to_rgba8();
+        let pixels = image_buffer.as_flat_samples();
+        Ok(ColorImage::from_rgba_unmultiplied(size, pixels.as_slice()))
+    }
+
     pub fn load_image_from_memory(image_data: &[u8]) -> Result<ColorImage, image::ImageError> {
         let image = image::load_from_memory(image_data)?;
         let size = [image.width() as _, image.height() as _];
@@ -76,7 +95,7 @@ pub mod ui_images {
 
 #[cfg(test)]
 mod tests {
-    use super::ui_images::load_image_from_path;
+    use super::ui_images::{load_image_from_path, load_thumbnail_from_path};
 
     #[test]
     pub fn test_image_load_that_is_broken() {
@@ -90,6 +109,12 @@ mod tests {
         assert!(res.is_ok());
     }
 
+    #[test]
+    pub fn test_thumbnail_load_shrinks_large_images() {
+        let res = load_thumbnail_from_path(std::path::Path::new("src/testdata/spider.webp"), 8);
+        assert_eq!(res.unwrap().size[1], 8);
+    }
+
     #[test]
     pub fn test_image_load_that_works_webp() {
         let res = load_image_from_path(std::path::Path::new("src/testdata/spider.webp"));
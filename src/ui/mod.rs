@@ -1,15 +1,23 @@
 mod defines;
 mod ui_backup;
+mod ui_cache;
 mod ui_disconnect;
+mod ui_game_details;
 mod ui_image_download;
+mod ui_image_gallery;
 mod ui_import_games;
+mod ui_import_list;
 mod ui_settings;
 mod uiapp;
 
 pub use defines::*;
 pub use ui_backup::*;
+pub use ui_cache::*;
 pub use ui_disconnect::*;
+pub use ui_game_details::*;
 pub use ui_image_download::*;
+pub use ui_image_gallery::*;
 pub use ui_import_games::*;
+pub use ui_import_list::*;
 pub use ui_settings::*;
 pub use uiapp::*;
@@ -5,7 +5,9 @@ use egui::ScrollArea;
 
 use crate::{
     config::get_backups_flder,
+    local_artwork::{export_artwork_pack, import_artwork_pack},
     steam::{get_shortcuts_paths, SteamSettings},
+    sync::{preview_cleanup, remove_everything, CleanupPreview},
 };
 
 use super::MyEguiApp;
@@ -14,6 +16,11 @@ use super::MyEguiApp;
 pub struct BackupState {
     pub available_backups: Option<Vec<PathBuf>>,
     pub last_restore: Option<PathBuf>,
+    pub available_packs: Option<Vec<PathBuf>>,
+    pub pack_path: String,
+    pub pack_message: Option<String>,
+    pub cleanup_preview: Option<CleanupPreview>,
+    pub cleanup_message: Option<String>,
 }
 
 impl MyEguiApp {
@@ -58,7 +65,151 @@ impl MyEguiApp {
                     }
                 });
         }
+
+        ui.add_space(15.0);
+        self.render_artwork_packs(ui);
+        ui.add_space(15.0);
+        self.render_cleanup(ui);
+    }
+
+    fn render_cleanup(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Remove everything BoilR created");
+        ui.label("Removes all shortcuts, collections, images and links BoilR created, your shortcuts, images and collections are backed up first");
+        ui.add_space(15.0);
+
+        if let Some(message) = self.backup_state.cleanup_message.as_ref() {
+            ui.label(message);
+        }
+
+        if ui.button("Show what will be removed").clicked() {
+            match preview_cleanup(&self.settings) {
+                Ok(preview) => {
+                    self.backup_state.cleanup_message = None;
+                    self.backup_state.cleanup_preview = Some(preview);
+                }
+                Err(e) => {
+                    self.backup_state.cleanup_message = Some(format!("{}", e));
+                    self.backup_state.cleanup_preview = None;
+                }
+            }
+        }
+
+        let mut remove = false;
+        if let Some(preview) = self.backup_state.cleanup_preview.as_ref() {
+            if preview.is_empty() {
+                ui.label("BoilR has not created anything");
+            } else {
+                ScrollArea::vertical()
+                    .id_source("cleanup_preview")
+                    .max_height(200.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        for line in preview.describe() {
+                            ui.label(line);
+                        }
+                    });
+                remove = ui.button("Remove everything").clicked();
+            }
+        }
+
+        if remove {
+            if self.settings.steam.stop_steam {
+                crate::steam::ensure_steam_stopped();
+            }
+            let message = match remove_everything(&self.settings) {
+                Ok(_) => format!(
+                    "Removed everything BoilR created, your shortcuts were backed up to {:?}",
+                    get_backups_flder()
+                ),
+                Err(e) => format!("Could not remove everything: {}", e),
+            };
+            self.backup_state.cleanup_message = Some(message);
+            self.backup_state.cleanup_preview = None;
+            self.backup_state.available_backups = None;
+        }
     }
+
+    fn render_artwork_packs(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Artwork packs");
+        ui.label("Save the images of all your shortcuts to a single file, and use it to get the same images on another computer");
+        ui.label("Games are matched by their platform id or name, so they do not need to have the same shortcut ids");
+        ui.add_space(15.0);
+
+        if let Some(message) = self.backup_state.pack_message.as_ref() {
+            ui.label(message);
+        }
+
+        if ui.button("Export artwork pack").clicked() {
+            let file_name = format!(
+                "artwork-{}.tar.gz",
+                Local::now().format("%Y-%m-%d-%H-%M-%S")
+            );
+            let pack_path = get_backups_flder().join(file_name);
+            let message = match get_shortcuts_paths(&self.settings.steam) {
+                Ok(users) => match export_artwork_pack(&users, &pack_path) {
+                    Ok(count) => format!("Exported {} images to {:?}", count, pack_path),
+                    Err(e) => format!("Could not export artwork: {}", e),
+                },
+                Err(e) => format!("Could not find the Steam users: {}", e),
+            };
+            self.backup_state.pack_message = Some(message);
+            self.backup_state.available_packs = None;
+        }
+
+        let mut to_import = None;
+        ui.horizontal(|ui| {
+            ui.label("Import from: ");
+            ui.text_edit_singleline(&mut self.backup_state.pack_path);
+            if ui.button("Import").clicked() && !self.backup_state.pack_path.is_empty() {
+                to_import = Some(PathBuf::from(&self.backup_state.pack_path));
+            }
+        });
+        let available_packs = self
+            .backup_state
+            .available_packs
+            .get_or_insert_with(load_artwork_packs);
+        for pack_path in available_packs.iter() {
+            if ui
+                .button(format!("Import {}", pack_path.to_string_lossy()))
+                .clicked()
+            {
+                to_import = Some(pack_path.clone());
+            }
+        }
+
+        if let Some(pack_path) = to_import {
+            let message = match get_shortcuts_paths(&self.settings.steam) {
+                Ok(users) => match import_artwork_pack(&users, &pack_path) {
+                    Ok(result) if result.unmatched.is_empty() => {
+                        format!("Imported {} images", result.placed)
+                    }
+                    Ok(result) => format!(
+                        "Imported {} images, no shortcut found for: {}",
+                        result.placed,
+                        result.unmatched.join(", ")
+                    ),
+                    Err(e) => format!("Could not import artwork: {}", e),
+                },
+                Err(e) => format!("Could not find the Steam users: {}", e),
+            };
+            self.backup_state.pack_message = Some(message);
+        }
+    }
+}
+
+fn load_artwork_packs() -> Vec<PathBuf> {
+    let mut result: Vec<PathBuf> = std::fs::read_dir(get_backups_flder())
+        .map(|files| {
+            files
+                .flatten()
+                .map(|file| file.path())
+                .filter(|path| path.to_string_lossy().ends_with(".tar.gz"))
+                .collect()
+        })
+        .unwrap_or_default();
+    result.sort();
+    result.reverse();
+    result
 }
 
 pub fn restore_backup(steam_settings: &SteamSettings, shortcut_path: &Path) -> bool {
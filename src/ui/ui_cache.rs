@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use chrono::{Local, TimeZone};
+use egui::ScrollArea;
+
+use crate::steamgriddb::{
+    clear_cache, get_cache_entries, remove_cache_entry, set_cache_entry, CacheEntry,
+};
+
+use super::MyEguiApp;
+
+#[derive(Default)]
+pub struct CacheState {
+    pub entries: Option<Vec<(u32, CacheEntry)>>,
+    pub filter: String,
+    /// Grid ids being typed in, by app id
+    pub edits: HashMap<u32, String>,
+}
+
+enum CacheAction {
+    Set(u32, String, usize),
+    Remove(u32),
+    Clear,
+}
+
+impl MyEguiApp {
+    pub fn render_cache(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Search cache");
+        ui.label("BoilR remembers which SteamGridDB game each shortcut was matched to");
+        ui.label("Change the game id if a shortcut got the wrong images, or delete the entry to search for it again");
+        ui.add_space(15.0);
+
+        let state = &mut self.cache_state;
+        ui.horizontal(|ui| {
+            ui.label("Search: ");
+            ui.text_edit_singleline(&mut state.filter);
+        });
+        let entries = state.entries.get_or_insert_with(get_cache_entries);
+        let filter = state.filter.to_lowercase();
+
+        let mut action = None;
+        if !entries.is_empty() && ui.button("Clear all").clicked() {
+            action = Some(CacheAction::Clear);
+        }
+        ui.add_space(15.0);
+
+        if entries.is_empty() {
+            ui.label("The cache is empty, it is filled when images are downloaded");
+        } else {
+            ScrollArea::vertical()
+                .stick_to_right(true)
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    egui::Grid::new("cache_entries")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Name");
+                            ui.label("App id");
+                            ui.label("SteamGridDB id");
+                            ui.label("Matched");
+                            ui.end_row();
+                            for (app_id, entry) in entries.iter().filter(|(app_id, entry)| {
+                                filter.is_empty()
+                                    || entry.name.to_lowercase().contains(&filter)
+                                    || app_id.to_string().contains(&filter)
+                            }) {
+                                ui.label(&entry.name);
+                                ui.label(app_id.to_string());
+                                let edit = state
+                                    .edits
+                                    .entry(*app_id)
+                                    .or_insert_with(|| entry.grid_id.to_string());
+                                let response = ui.text_edit_singleline(edit);
+                                if response.lost_focus() {
+                                    match edit.parse::<usize>() {
+                                        Ok(grid_id) if grid_id != entry.grid_id => {
+                                            action = Some(CacheAction::Set(
+                                                *app_id,
+                                                entry.name.clone(),
+                                                grid_id,
+                                            ));
+                                        }
+                                        Ok(_) => {}
+                                        Err(_) => *edit = entry.grid_id.to_string(),
+                                    }
+                                }
+                                ui.label(describe_match(entry));
+                                if ui.button("Delete").clicked() {
+                                    action = Some(CacheAction::Remove(*app_id));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+        }
+
+        if let Some(action) = action {
+            match action {
+                CacheAction::Set(app_id, name, grid_id) => set_cache_entry(app_id, name, grid_id),
+                CacheAction::Remove(app_id) => {
+                    remove_cache_entry(app_id);
+                }
+                CacheAction::Clear => clear_cache(),
+            }
+            state.entries = None;
+            state.edits.clear();
+        }
+    }
+}
+
+fn describe_match(entry: &CacheEntry) -> String {
+    let date = Local
+        .timestamp_opt(entry.updated as i64, 0)
+        .single()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    if entry.manual {
+        format!("{} (by hand)", date)
+    } else {
+        date
+    }
+}
@@ -0,0 +1,283 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use egui::ScrollArea;
+use steam_shortcuts_util::shortcut::ShortcutOwned;
+
+use crate::{
+    platforms::ShortcutToImport,
+    settings::save_settings,
+    steam::{get_shortcuts_paths, template_from_text},
+    steamgriddb::ImageType,
+    sync::generated_shortcut,
+};
+
+use super::{
+    ui_image_download::HasImageKey, ui_image_gallery::render_gallery_thumbnail,
+    ui_import_games::save_rename_map, ui_settings::render_controller_template_combo, FetcStatus,
+    MyEguiApp, SECTION_SPACING,
+};
+
+const ARTWORK_TYPES: [ImageType; 4] = [
+    ImageType::Grid,
+    ImageType::Hero,
+    ImageType::Logo,
+    ImageType::Icon,
+];
+
+/// The game shown on the details page, with the overrides that are being edited
+#[derive(Default)]
+pub struct GameDetailsState {
+    /// The platform of the game and the app id it has before it is renamed
+    pub selected: Option<(String, u32)>,
+    name: String,
+    launch_options: String,
+    compat_tool: String,
+    collections: String,
+    /// The artwork of the first Steam user is shown
+    user_folder: Option<PathBuf>,
+    message: Option<String>,
+}
+
+impl MyEguiApp {
+    pub(super) fn open_game_details(&mut self, platform: String, shortcut: &ShortcutOwned) {
+        let app_id = shortcut.app_id;
+        let steam = &self.settings.steam;
+        let name = name_in_steam(&self.rename_map, shortcut);
+        self.game_details = GameDetailsState {
+            selected: Some((platform, app_id)),
+            name: name.clone(),
+            launch_options: steam
+                .launch_options
+                .game_template(app_id)
+                .cloned()
+                .unwrap_or_default(),
+            compat_tool: steam.compat_tool(app_id).unwrap_or_default().to_string(),
+            collections: steam.collection_rules.game_collections(&name).join(", "),
+            user_folder: get_shortcuts_paths(steam)
+                .ok()
+                .and_then(|users| users.into_iter().next())
+                .map(|user| PathBuf::from(user.steam_user_data_folder)),
+            message: None,
+        };
+    }
+
+    fn selected_game(&self) -> Option<(String, ShortcutToImport)> {
+        let (platform, app_id) = self.game_details.selected.as_ref()?;
+        let (_, status) = self
+            .games_to_sync
+            .iter()
+            .find(|(name, _)| name == platform)?;
+        let status = status.borrow();
+        match &*status {
+            FetcStatus::Fetched(Ok(shortcuts)) => shortcuts
+                .iter()
+                .find(|s| s.shortcut.app_id == *app_id)
+                .map(|s| (platform.clone(), s.clone())),
+            _ => None,
+        }
+    }
+
+    /// Shows the shortcut a sync writes for one game, and the settings that only apply to that game
+    pub(super) fn render_game_details(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Back to the games").clicked() {
+            self.game_details.selected = None;
+            return;
+        }
+        let (platform, game) = match self.selected_game() {
+            Some(game) => game,
+            None => {
+                ui.label("The game is no longer found");
+                return;
+            }
+        };
+        let original = &game.shortcut;
+        let app_id = original.app_id;
+        let generated = generated_shortcut(&self.settings, &platform, original, &self.rename_map);
+        ui.heading(&generated.app_name);
+
+        ScrollArea::vertical()
+            .stick_to_right(true)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                egui::Grid::new("game_details")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let native_id = game
+                            .external_id
+                            .as_ref()
+                            .map(|id| format!("{} {}", id.platform.steamgriddb_name(), id.id))
+                            .unwrap_or_else(|| "None".to_string());
+                        let details = [
+                            ("Platform", platform.clone()),
+                            ("Platform id", native_id),
+                            ("App id", generated.app_id.to_string()),
+                            ("Executable", generated.exe.clone()),
+                            ("Start dir", generated.start_dir.clone()),
+                            ("Launch options", generated.launch_options.clone()),
+                            ("Icon", generated.icon.clone()),
+                            ("Tags", generated.tags.join(", ")),
+                            ("Needs proton", yes_no(game.needs_proton)),
+                            ("Needs symlinks", yes_no(game.needs_symlinks)),
+                        ];
+                        for (name, value) in details {
+                            ui.label(name);
+                            ui.label(value);
+                            ui.end_row();
+                        }
+                    });
+                ui.add_space(SECTION_SPACING);
+
+                ui.heading("Artwork");
+                match &self.game_details.user_folder {
+                    Some(user_folder) => {
+                        ui.horizontal(|ui| {
+                            for image_type in ARTWORK_TYPES {
+                                ui.vertical(|ui| {
+                                    ui.label(image_type.name());
+                                    let (path, key) = generated.key(&image_type, user_folder);
+                                    render_gallery_thumbnail(
+                                        ui,
+                                        &self.rt,
+                                        &self.image_selected_state.image_handles,
+                                        &path,
+                                        &key,
+                                    );
+                                });
+                            }
+                        });
+                    }
+                    None => {
+                        ui.label("Could not find a Steam user to show the artwork of");
+                    }
+                }
+                ui.add_space(SECTION_SPACING);
+
+                ui.heading("Overrides");
+                ui.label("These are kept in the settings and applied every time games are imported");
+                let mut import_game = !self.settings.blacklisted_games.contains(&app_id);
+                if ui.checkbox(&mut import_game, "Import").changed() {
+                    if import_game {
+                        self.settings.blacklisted_games.retain(|id| *id != app_id);
+                    } else {
+                        self.settings.blacklisted_games.push(app_id);
+                    }
+                }
+                let mut hidden = self.settings.hidden_games.contains(&app_id);
+                if ui.checkbox(&mut hidden, "Hidden").changed() {
+                    if hidden {
+                        self.settings.hidden_games.push(app_id);
+                    } else {
+                        self.settings.hidden_games.retain(|id| *id != app_id);
+                    }
+                }
+                let state = &mut self.game_details;
+                egui::Grid::new("game_overrides")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Name");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut state.name)
+                                .hint_text(original.app_name.as_str()),
+                        );
+                        ui.end_row();
+
+                        ui.label("Launch options").on_hover_text(
+                            "Put around the launch options of the platform, instead of the platform template",
+                        );
+                        ui.add(
+                            egui::TextEdit::singleline(&mut state.launch_options)
+                                .hint_text("gamemoderun %command%"),
+                        );
+                        ui.end_row();
+
+                        #[cfg(target_family = "unix")]
+                        {
+                            ui.label("Compatibility tool").on_hover_text(
+                                "Like proton_experimental, \"native\" runs the game without one, and empty lets BoilR decide",
+                            );
+                            ui.add(
+                                egui::TextEdit::singleline(&mut state.compat_tool)
+                                    .hint_text("proton_experimental"),
+                            );
+                            ui.end_row();
+                        }
+
+                        ui.label("Collections").on_hover_text(
+                            "Extra collections the game is added to, separated by commas. Needs \"Create collections\" in the settings",
+                        );
+                        ui.add(
+                            egui::TextEdit::singleline(&mut state.collections)
+                                .hint_text("Favourites, Shooters"),
+                        );
+                        ui.end_row();
+
+                        ui.label("Controller layout");
+                        let templates = &mut self.settings.steam.controller_templates;
+                        let mut template = templates.game_template(app_id).copied();
+                        if render_controller_template_combo(
+                            ui,
+                            ("details", app_id),
+                            &mut template,
+                            "Platform controller layout",
+                        ) {
+                            templates.set_game_template(app_id, template);
+                        }
+                        ui.end_row();
+                    });
+                if ui.button("Save").clicked() {
+                    self.save_game_details(original);
+                }
+                if let Some(message) = &self.game_details.message {
+                    ui.label(message);
+                }
+            });
+    }
+
+    fn save_game_details(&mut self, original: &ShortcutOwned) {
+        let app_id = original.app_id;
+        let previous_name = name_in_steam(&self.rename_map, original);
+        let state = &mut self.game_details;
+        let name = state.name.trim();
+        if name.is_empty() || name == original.app_name {
+            self.rename_map.remove(&app_id);
+        } else {
+            self.rename_map.insert(app_id, name.to_string());
+        }
+        save_rename_map(&self.rename_map);
+        let name = name_in_steam(&self.rename_map, original);
+
+        let steam = &mut self.settings.steam;
+        steam
+            .launch_options
+            .set_game_template(app_id, template_from_text(&state.launch_options));
+        steam.set_compat_tool(app_id, &state.compat_tool);
+        let collections: Vec<String> = state
+            .collections
+            .split(',')
+            .map(|collection| collection.trim().to_string())
+            .filter(|collection| !collection.is_empty())
+            .collect();
+        // Collections list games by the name they have in Steam, so they follow a rename
+        let rules = &mut steam.collection_rules;
+        if previous_name != name {
+            rules.set_game_collections(&previous_name, &[]);
+        }
+        rules.set_game_collections(&name, &collections);
+        save_settings(&self.settings, &self.platforms);
+        state.message =
+            Some("Saved, the changes are applied the next time games are imported".to_string());
+    }
+}
+
+fn name_in_steam(renames: &HashMap<u32, String>, shortcut: &ShortcutOwned) -> String {
+    renames
+        .get(&shortcut.app_id)
+        .cloned()
+        .unwrap_or_else(|| shortcut.app_name.clone())
+}
+
+fn yes_no(value: bool) -> String {
+    let text = if value { "Yes" } else { "No" };
+    text.to_string()
+}
@@ -6,9 +6,15 @@ use std::{
 use crate::{
     config::get_thumbnails_folder,
     steam::{get_installed_games, SteamGameInfo},
-    steam::{get_shortcuts_paths, SteamUsersInfo},
-    steamgriddb::{get_image_extension, get_query_type, CachedSearch, ImageType, ToDownload},
-    sync::{download_images, SyncProgress},
+    steam::{get_shortcuts_for_user, get_shortcuts_paths, SteamUsersInfo},
+    steamgriddb::{
+        download_bulk_artwork, find_bulk_targets, get_image_extension, get_needs_review,
+        get_query_type, load_external_ids, reset_manual_picks, save_manual_pick, score_results,
+        share_image, BulkGameKind, BulkTarget, CachedSearch, ImageProcessing, ImageQuery,
+        ImageType, ManualPick, NeedsReview, PlatformLookup, ScoredMatch, ToDownload,
+        GRID_EXTENSIONS,
+    },
+    sync::{download_images, SyncCancel, SyncProgress},
 };
 use dashmap::DashMap;
 use egui::{Button, Grid, ImageButton, ScrollArea};
@@ -17,7 +23,10 @@ use steam_shortcuts_util::shortcut::ShortcutOwned;
 use steamgriddb_api::images::MimeTypes;
 use tokio::sync::watch::{self, Receiver};
 
-use super::{ui_images::load_image_from_path, FetcStatus, MyEguiApp};
+use super::{
+    ui_image_gallery::forget_image, ui_images::load_image_from_path, FetcStatus, GalleryState,
+    MyEguiApp,
+};
 
 pub struct ImageSelectState {
     pub selected_shortcut: Option<GameType>,
@@ -26,6 +35,8 @@ pub struct ImageSelectState {
     pub steam_user: Option<SteamUsersInfo>,
     pub settings_error: Option<String>,
     pub steam_users: Option<Vec<SteamUsersInfo>>,
+    /// Other users that get the images picked for the selected user
+    pub apply_to_users: Vec<String>,
     pub user_shortcuts: Option<Vec<ShortcutOwned>>,
     pub game_mode: GameMode,
     pub image_type_selected: Option<ImageType>,
@@ -33,7 +44,12 @@ pub struct ImageSelectState {
     pub steam_games: Option<Vec<crate::steam::SteamGameInfo>>,
     pub image_handles: std::sync::Arc<DashMap<String, TextureState>>,
 
-    pub possible_names: Option<Vec<steamgriddb_api::search::SearchResult>>,
+    pub possible_names: Option<Vec<ScoredMatch>>,
+    /// Games where the automatic search was not sure it found the right game
+    pub needs_review: Vec<NeedsReview>,
+    /// Steam games and other shortcuts that are missing images, shown before downloading them
+    pub bulk_targets: Option<Vec<BulkTarget>>,
+    pub gallery: GalleryState,
 }
 
 #[derive(Clone)]
@@ -82,11 +98,15 @@ impl Default for ImageSelectState {
             grid_id: Default::default(),
             steam_user: Default::default(),
             steam_users: Default::default(),
+            apply_to_users: Default::default(),
             settings_error: Default::default(),
             user_shortcuts: Default::default(),
             game_mode: GameMode::Shortcuts,
             image_type_selected: Default::default(),
             possible_names: None,
+            needs_review: vec![],
+            bulk_targets: None,
+            gallery: Default::default(),
             image_options: watch::channel(FetcStatus::NeedsFetched).1,
             image_handles: Arc::new(DashMap::new()),
             steam_games: None,
@@ -119,7 +139,9 @@ impl GameType {
 #[derive(Debug)]
 enum UserAction {
     CorrectGridId,
+    ResetToAutomatic,
     UserSelected(SteamUsersInfo),
+    ApplyToUserToggled(String),
     ShortcutSelected(GameType),
     ImageTypeSelected(ImageType),
     ImageTypeCleared(ImageType, bool),
@@ -130,6 +152,9 @@ enum UserAction {
     NoAction,
     ClearImages,
     DownloadAllImages,
+    FindBulkTargets,
+    DownloadBulkTargets,
+    CancelBulkTargets,
     RefreshImages,
 }
 
@@ -157,6 +182,10 @@ impl MyEguiApp {
             return action;
         }
 
+        if let Some(targets) = state.bulk_targets.as_ref() {
+            return render_bulk_targets(targets, ui).unwrap_or(UserAction::NoAction);
+        }
+
         if let Some(shortcut) = state.selected_shortcut.as_ref() {
             ui.heading(shortcut.name());
 
@@ -182,18 +211,18 @@ impl MyEguiApp {
             }
         }
 
-        match *self.status_reciever.borrow() {
+        match &*self.status_reciever.borrow() {
             crate::sync::SyncProgress::FindingImages => {
                 ui.spinner();
                 ui.label("Finding images to download");
                 ui.ctx().request_repaint();
             }
-            crate::sync::SyncProgress::DownloadingImages { to_download } => {
+            progress @ crate::sync::SyncProgress::DownloadingImages { .. } => {
                 ui.spinner();
-                ui.label(format!("Downloading {to_download} images"));
+                ui.label(progress.description());
                 ui.ctx().request_repaint();
             }
-            crate::sync::SyncProgress::Done => {
+            crate::sync::SyncProgress::Done | crate::sync::SyncProgress::Cancelled => {
                 ui.ctx().request_repaint();
                 return UserAction::RefreshImages;
             }
@@ -201,6 +230,13 @@ impl MyEguiApp {
                 if ui.button("Download images for all games").clicked() {
                     return UserAction::DownloadAllImages;
                 }
+                if ui
+                    .button("Find missing images for Steam games and other shortcuts")
+                    .on_hover_text("Shows what is missing before anything is downloaded")
+                    .clicked()
+                {
+                    return UserAction::FindBulkTargets;
+                }
             }
         }
 
@@ -217,6 +253,9 @@ impl MyEguiApp {
         let mut cur_column = 0;
         match shortcuts {
             Some(shortcuts) => {
+                if let Some(action) = self.render_needs_review(shortcuts, ui) {
+                    return Some(action);
+                }
                 let user_info = &self.image_selected_state.steam_user.as_ref().unwrap();
                 if let Some(action) = egui::Grid::new("ui_images")
                     .show(ui, |ui| {
@@ -246,6 +285,33 @@ impl MyEguiApp {
         None
     }
 
+    fn render_needs_review(
+        &self,
+        shortcuts: &[ShortcutOwned],
+        ui: &mut egui::Ui,
+    ) -> Option<UserAction> {
+        let needs_review = &self.image_selected_state.needs_review;
+        if needs_review.is_empty() {
+            return None;
+        }
+        ui.collapsing(format!("Needs review ({})", needs_review.len()), |ui| {
+            ui.label("We were not sure which game these are, click one to pick the right game");
+            for review in needs_review {
+                let shortcut = shortcuts.iter().find(|s| s.app_id == review.app_id);
+                if let Some(shortcut) = shortcut {
+                    if ui.button(&review.name).clicked() {
+                        return Some(UserAction::ShortcutSelected(GameType::Shortcut(
+                            shortcut.clone(),
+                        )));
+                    }
+                }
+            }
+            None
+        })
+        .body_returned
+        .flatten()
+    }
+
     fn render_image(
         &self,
         shortcut: &ShortcutOwned,
@@ -317,6 +383,9 @@ impl MyEguiApp {
         {
             return Some(action);
         }
+        if let Some(action) = render_apply_to_users(state, ui) {
+            return Some(action);
+        }
         let column_padding = 10.;
         let column_width = MAX_WIDTH * 0.75;
         let width = ui.available_width();
@@ -393,16 +462,21 @@ impl MyEguiApp {
                                             url: image.thumbnail_url.clone(),
                                             app_name: "Thumbnail".to_string(),
                                             image_type: *image_type,
+                                            image_id: None,
                                         };
                                         let image_handles = image_handles.clone();
                                         let image_key = image_key.clone();
                                         self.rt.spawn_blocking(move || {
-                                            block_on(crate::steamgriddb::download_to_download(
-                                                &to_download,
-                                            ))
-                                            .unwrap();
-                                            image_handles
-                                                .insert(image_key, TextureState::Downloaded);
+                                            let state = match block_on(
+                                                crate::steamgriddb::download_to_download(
+                                                    &to_download,
+                                                    &ImageProcessing::default(),
+                                                ),
+                                            ) {
+                                                Ok(_) => TextureState::Downloaded,
+                                                Err(_) => TextureState::Failed,
+                                            };
+                                            image_handles.insert(image_key, state);
                                         });
                                     } else {
                                         image_handles
@@ -457,6 +531,19 @@ impl MyEguiApp {
             return;
         }
 
+        let state = &mut self.image_selected_state;
+        let can_show_gallery = state.steam_user.is_some()
+            && state.selected_shortcut.is_none()
+            && state.bulk_targets.is_none()
+            && state.game_mode.is_shortcuts();
+        if can_show_gallery {
+            ui.checkbox(&mut state.gallery.enabled, "Gallery view");
+            if state.gallery.enabled {
+                self.render_image_gallery(ui);
+                return;
+            }
+        }
+
         let mut action = UserAction::NoAction;
         ScrollArea::vertical()
             .stick_to_right(true)
@@ -469,6 +556,14 @@ impl MyEguiApp {
             UserAction::UserSelected(user) => {
                 self.handle_user_selected(user, ui);
             }
+            UserAction::ApplyToUserToggled(user_id) => {
+                let apply_to_users = &mut self.image_selected_state.apply_to_users;
+                if apply_to_users.contains(&user_id) {
+                    apply_to_users.retain(|id| id != &user_id);
+                } else {
+                    apply_to_users.push(user_id);
+                }
+            }
             UserAction::ShortcutSelected(shortcut) => {
                 self.handle_shortcut_selected(shortcut, ui);
             }
@@ -491,6 +586,9 @@ impl MyEguiApp {
             UserAction::CorrectGridId => {
                 self.handle_correct_grid_request();
             }
+            UserAction::ResetToAutomatic => {
+                self.handle_reset_to_automatic();
+            }
             UserAction::ImageTypeCleared(image_type, should_ban) => {
                 let app_id = self
                     .image_selected_state
@@ -510,18 +608,47 @@ impl MyEguiApp {
                 self.handle_back_button_action();
             }
             UserAction::DownloadAllImages => {
-                if let Some(users) = &self.image_selected_state.steam_users {
+                if let Some(users) = self.image_selected_state.steam_users.clone() {
+                    self.start_image_download(users);
+                }
+            }
+            UserAction::FindBulkTargets => {
+                if let Some(user) = &self.image_selected_state.steam_user {
+                    let targets = find_bulk_targets(&self.settings, user);
+                    self.image_selected_state.bulk_targets = Some(targets);
+                }
+            }
+            UserAction::CancelBulkTargets => {
+                self.image_selected_state.bulk_targets = None;
+            }
+            UserAction::DownloadBulkTargets => {
+                let targets = self.image_selected_state.bulk_targets.take();
+                if let (Some(targets), Some(user)) =
+                    (targets, self.image_selected_state.steam_user.clone())
+                {
                     let (sender, reciever) = watch::channel(SyncProgress::FindingImages);
                     self.status_reciever = reciever;
+                    let cancel = SyncCancel::default();
+                    self.sync_cancel = cancel.clone();
                     let mut sender_op = Some(sender);
                     let settings = self.settings.clone();
-                    let users = users.clone();                                                         
                     self.rt.spawn_blocking(move || {
-                        let task = download_images(&settings, &users, &mut sender_op);
-                        block_on(task);
-                        let _ = sender_op.unwrap().send(SyncProgress::Done);
+                        let task = download_bulk_artwork(
+                            &settings,
+                            &user,
+                            &targets,
+                            &mut sender_op,
+                            &cancel,
+                        );
+                        let downloaded = block_on(task);
+                        println!("Downloaded {} images for other games", downloaded);
+                        let progress = if cancel.is_cancelled() {
+                            SyncProgress::Cancelled
+                        } else {
+                            SyncProgress::Done
+                        };
+                        let _ = sender_op.unwrap().send(progress);
                     });
-                        
                 }
             }
             UserAction::RefreshImages => {
@@ -530,11 +657,32 @@ impl MyEguiApp {
                 if let Some(user) = &user{
                     load_image_grids(user,&mut self.image_selected_state,ui);
                 }
+                self.image_selected_state.needs_review = get_needs_review();
                 self.status_reciever = reciever;                
             },
         };
     }
 
+    /// Downloads the missing images for the users in the background, the progress is shown on the page
+    pub(super) fn start_image_download(&mut self, users: Vec<SteamUsersInfo>) {
+        let (sender, reciever) = watch::channel(SyncProgress::FindingImages);
+        self.status_reciever = reciever;
+        let cancel = SyncCancel::default();
+        self.sync_cancel = cancel.clone();
+        let mut sender_op = Some(sender);
+        let settings = self.settings.clone();
+        self.rt.spawn_blocking(move || {
+            let task = download_images(&settings, &users, &mut sender_op, &cancel);
+            block_on(task);
+            let progress = if cancel.is_cancelled() {
+                SyncProgress::Cancelled
+            } else {
+                SyncProgress::Done
+            };
+            let _ = sender_op.unwrap().send(progress);
+        });
+    }
+
     fn handle_image_type_clear(&mut self, image_type: ImageType) {
         let data_folder = &self
             .image_selected_state
@@ -542,7 +690,7 @@ impl MyEguiApp {
             .as_ref()
             .unwrap()
             .steam_user_data_folder;
-        for ext in POSSIBLE_EXTENSIONS {
+        for ext in GRID_EXTENSIONS {
             let file_name = image_type.file_name(
                 self.image_selected_state
                     .selected_shortcut
@@ -559,11 +707,41 @@ impl MyEguiApp {
                 let _ = std::fs::remove_file(&path);
             }
             let key = path.to_string_lossy().to_string();
-            self.image_selected_state.image_handles.remove(&key);
+            forget_image(&self.image_selected_state.image_handles, &key);
         }
         self.image_selected_state.image_type_selected = None;
     }
 
+    fn handle_reset_to_automatic(&mut self) {
+        let state = &self.image_selected_state;
+        let (game, user) = match (&state.selected_shortcut, &state.steam_user) {
+            (Some(game), Some(user)) => (game, user),
+            _ => return,
+        };
+        let external_ids = load_external_ids();
+        let removed = reset_manual_picks(game.name(), external_ids.get(&game.app_id()));
+        // The other users have to lose the picked images too, or they would be shared back
+        let users = match &state.steam_users {
+            Some(users) => users.clone(),
+            None => vec![user.clone()],
+        };
+        for pick in removed {
+            for user in &users {
+                let grid_folder = Path::new(&user.steam_user_data_folder)
+                    .join("config")
+                    .join("grid");
+                for ext in GRID_EXTENSIONS {
+                    let path = grid_folder.join(pick.image_type.file_name(game.app_id(), ext));
+                    if path.exists() {
+                        let _ = std::fs::remove_file(&path);
+                    }
+                    let key = path.to_string_lossy().to_string();
+                    forget_image(&state.image_handles, &key);
+                }
+            }
+        }
+    }
+
     fn handle_correct_grid_request(&mut self) {
         let app_name = self
             .image_selected_state
@@ -577,9 +755,12 @@ impl MyEguiApp {
             .auth_key
             .clone()
             .unwrap_or_default();
-        let client = steamgriddb_api::Client::new(&auth_key);
+        let client = self.settings.steamgrid_db.client(&auth_key);
         let search_results = self.rt.block_on(client.search(app_name));
-        self.image_selected_state.possible_names = search_results.ok();
+        let possible_names = search_results
+            .ok()
+            .map(|results| score_results(app_name, &results));
+        self.image_selected_state.possible_names = possible_names;
     }
 
     fn handle_set_game_mode(&mut self, game_mode: GameMode) {
@@ -589,8 +770,14 @@ impl MyEguiApp {
     fn handle_grid_change(&mut self, grid_id: usize) {
         self.image_selected_state.grid_id = Some(grid_id);
         self.image_selected_state.possible_names = None;
+        if let Some(shortcut) = &self.image_selected_state.selected_shortcut {
+            let app_id = shortcut.app_id();
+            self.image_selected_state
+                .needs_review
+                .retain(|review| review.app_id != app_id);
+        }
         if let Some(auth_key) = &self.settings.steamgrid_db.auth_key {
-            let client = steamgriddb_api::Client::new(auth_key);
+            let client = self.settings.steamgrid_db.client(auth_key);
             let mut cache = CachedSearch::new(&client);
             if let Some(shortcut) = &self.image_selected_state.selected_shortcut {
                 cache.set_cache(shortcut.app_id(), shortcut.name(), grid_id);
@@ -603,6 +790,16 @@ impl MyEguiApp {
         let state = &mut self.image_selected_state;
         let shortcuts = load_image_grids(&user, state, ui);
         state.user_shortcuts = Some(shortcuts);
+        state.needs_review = get_needs_review();
+        let apply_to_all_users = self.settings.steamgrid_db.apply_to_all_users;
+        state.apply_to_users = match (&state.steam_users, apply_to_all_users) {
+            (Some(users), true) => users
+                .iter()
+                .filter(|other| other.user_id != user.user_id)
+                .map(|other| other.user_id.clone())
+                .collect(),
+            _ => vec![],
+        };
         state.steam_user = Some(user);
     }
 
@@ -618,12 +815,17 @@ impl MyEguiApp {
                 let image_type = image_type;
                 self.rt.spawn_blocking(move || {
                     let thumbnails_folder = get_thumbnails_folder();
-                    let client = steamgriddb_api::Client::new(auth_key);
-                    let query = get_query_type(false, &image_type, settings.steamgrid_db.allow_nsfw);
-                    let search_res = block_on(client.get_images_for_id(grid_id, &query));
+                    let client = settings.steamgrid_db.client(auth_key);
+                    let query = ImageQuery::new(false, &image_type, &settings.steamgrid_db);
+                    let search_res =
+                        block_on(client.get_images_for_id(grid_id, &get_query_type(&query)));
                     if let Ok(possible_images) = search_res {
+                        let preferences = settings.steamgrid_db.image_preferences.get(&image_type);
                         let mut result = vec![];
-                        for possible_image in &possible_images {
+                        for possible_image in possible_images
+                            .iter()
+                            .filter(|image| preferences.accepts(image))
+                        {
                             let ext = get_image_extension(&possible_image.mime);
                             let path =
                                 thumbnails_folder.join(format!("{}.{}", possible_image.id, ext));
@@ -675,10 +877,7 @@ impl MyEguiApp {
 
         //Put the loaded thumbnail into the image handler map, we can use that for preview
         let full_image_key = to_download_to_path.to_string_lossy().to_string();
-        let _ = self
-            .image_selected_state
-            .image_handles
-            .remove(&full_image_key);
+        forget_image(&self.image_selected_state.image_handles, &full_image_key);
         let thumbnail_key = image.thumbnail_path.to_string_lossy().to_string();
         let thumbnail = self
             .image_selected_state
@@ -696,9 +895,30 @@ impl MyEguiApp {
             url: image.full_url.clone(),
             app_name: app_name.to_string(),
             image_type: *selected_image_type,
+            image_id: Some(image.id),
+        };
+        let other_users = self.users_to_apply_image_to(selected_shortcut);
+        let pick = ManualPick {
+            app_name: app_name.to_string(),
+            external_id: load_external_ids()
+                .get(&selected_shortcut.app_id())
+                .cloned(),
+            image_type: *selected_image_type,
+            image_id: image.id,
+            url: image.full_url.clone(),
+            extension: ext.to_string(),
         };
+        let processing = ImageProcessing::from(&self.settings.steamgrid_db);
         self.rt.spawn_blocking(move || {
-            let _ = block_on(crate::steamgriddb::download_to_download(&to_download));
+            let result = block_on(crate::steamgriddb::download_to_download(
+                &to_download,
+                &processing,
+            ));
+            if let Ok(path) = result {
+                crate::steamgriddb::save_image_ids([&to_download]);
+                save_manual_pick(pick);
+                share_image(&path, &other_users);
+            }
         });
 
         self.clear_loaded_images();
@@ -708,6 +928,27 @@ impl MyEguiApp {
         }
     }
 
+    /// The other users that have the game and should get the picked image as well
+    fn users_to_apply_image_to(&self, game: &GameType) -> Vec<SteamUsersInfo> {
+        let state = &self.image_selected_state;
+        let users = match &state.steam_users {
+            Some(users) => users,
+            None => return vec![],
+        };
+        users
+            .iter()
+            .filter(|user| state.apply_to_users.contains(&user.user_id))
+            .filter(|user| match game {
+                GameType::Shortcut(shortcut) => get_shortcuts_for_user(user)
+                    .shortcuts
+                    .iter()
+                    .any(|s| s.app_id == shortcut.app_id),
+                GameType::SteamGame(_) => true,
+            })
+            .cloned()
+            .collect()
+    }
+
     fn get_shortcut_image_path(&self, data_folder: &Path) -> String {
         self.image_selected_state
             .selected_shortcut
@@ -734,14 +975,25 @@ impl MyEguiApp {
         //We must have a user to make see this action;
         let user = state.steam_user.as_ref().unwrap();
         if let Some(auth_key) = &self.settings.steamgrid_db.auth_key {
-            let client = steamgriddb_api::Client::new(auth_key);
-            let search = CachedSearch::new(&client);
+            let client = self.settings.steamgrid_db.client(auth_key);
+            let lookup = PlatformLookup::new(&self.settings.steamgrid_db, auth_key);
+            let search = CachedSearch::new(&client)
+                .with_platform_lookup(lookup)
+                .with_max_age_days(self.settings.steamgrid_db.cache_max_age_days);
             state.grid_id = self
                 .rt
                 .block_on(search.search(shortcut.app_id(), shortcut.name()))
                 .ok()
                 .flatten();
         }
+        if state.grid_id.is_none() {
+            //Let the user pick the game right away, if we were not sure about it
+            state.possible_names = state
+                .needs_review
+                .iter()
+                .find(|review| review.app_id == shortcut.app_id())
+                .map(|review| review.candidates.clone());
+        }
         state.selected_shortcut = Some(shortcut.clone());
 
         for image_type in ImageType::all() {
@@ -824,7 +1076,7 @@ fn render_shortcut_mode_select(state: &ImageSelectState, ui: &mut egui::Ui) -> O
 }
 
 fn render_possible_names(
-    possible_names: &Vec<steamgriddb_api::search::SearchResult>,
+    possible_names: &Vec<ScoredMatch>,
     ui: &mut egui::Ui,
     state: &ImageSelectState,
 ) -> Option<UserAction> {
@@ -838,7 +1090,14 @@ fn render_possible_names(
     };
 
     for possible in possible_names {
-        if ui.button(&possible.name).clicked() {
+        let verified = if possible.verified { " ✔" } else { "" };
+        let label = format!(
+            "{}{} ({:.0}% match)",
+            possible.name,
+            verified,
+            possible.score * 100.
+        );
+        if ui.button(label).clicked() {
             return Some(UserAction::GridIdChanged(possible.id));
         }
     }
@@ -941,6 +1200,15 @@ fn render_shortcut_images(ui: &mut egui::Ui, state: &ImageSelectState) -> Option
     {
         return Some(UserAction::CorrectGridId);
     }
+    if ui
+        .button("Reset images to automatic")
+        .on_hover_text(
+            "Forget the images picked by hand for this game, new ones are downloaded next time",
+        )
+        .clicked()
+    {
+        return Some(UserAction::ResetToAutomatic);
+    }
     x
 }
 
@@ -979,6 +1247,70 @@ fn texture_from_iamge_type(
     })
 }
 
+fn render_bulk_targets(targets: &[BulkTarget], ui: &mut egui::Ui) -> Option<UserAction> {
+    ui.heading("Missing images");
+    if targets.is_empty() {
+        ui.label("All installed Steam games and other shortcuts have their images");
+    } else {
+        let image_count: usize = targets.iter().map(|t| t.missing.len()).sum();
+        ui.label(format!(
+            "BoilR will look for {} images for {} games",
+            image_count,
+            targets.len()
+        ));
+        Grid::new("bulk_targets").striped(true).show(ui, |ui| {
+            ui.label("Game");
+            ui.label("Kind");
+            ui.label("Missing");
+            ui.end_row();
+            for target in targets {
+                ui.label(&target.name);
+                ui.label(match target.kind {
+                    BulkGameKind::SteamGame => "Steam game",
+                    BulkGameKind::Shortcut => "Shortcut",
+                });
+                let missing: Vec<&str> = target.missing.iter().map(|t| t.name()).collect();
+                ui.label(missing.join(", "));
+                ui.end_row();
+            }
+        });
+    }
+    ui.horizontal(|ui| {
+        if !targets.is_empty() && ui.button("Download missing images").clicked() {
+            return Some(UserAction::DownloadBulkTargets);
+        }
+        if ui.button("Cancel").clicked() {
+            return Some(UserAction::CancelBulkTargets);
+        }
+        None
+    })
+    .inner
+}
+
+fn render_apply_to_users(state: &ImageSelectState, ui: &mut egui::Ui) -> Option<UserAction> {
+    let current_user = state.steam_user.as_ref()?;
+    let other_users: Vec<&SteamUsersInfo> = state
+        .steam_users
+        .as_ref()?
+        .iter()
+        .filter(|user| user.user_id != current_user.user_id)
+        .collect();
+    if other_users.is_empty() {
+        return None;
+    }
+    ui.horizontal(|ui| {
+        ui.label("Also use for:");
+        for user in other_users {
+            let mut selected = state.apply_to_users.contains(&user.user_id);
+            if ui.checkbox(&mut selected, &user.user_id).changed() {
+                return Some(UserAction::ApplyToUserToggled(user.user_id.clone()));
+            }
+        }
+        None
+    })
+    .inner
+}
+
 fn render_user_select(state: &ImageSelectState, ui: &mut egui::Ui) -> Option<UserAction> {
     if state.steam_user.is_none() {
         if let Some(users) = &state.steam_users {
@@ -1034,10 +1366,9 @@ fn clamp_to_width(size: &mut egui::Vec2, max_width: f32) {
     size.y = y;
 }
 
-trait HasImageKey {
+pub(super) trait HasImageKey {
     fn key(&self, image_type: &ImageType, user_path: &Path) -> (PathBuf, String);
 }
-const POSSIBLE_EXTENSIONS: [&str; 4] = ["png", "jpg", "ico", "webp"];
 
 impl HasImageKey for GameType {
     fn key(&self, image_type: &ImageType, user_path: &Path) -> (PathBuf, String) {
@@ -1049,7 +1380,7 @@ impl HasImageKey for GameType {
 }
 impl HasImageKey for SteamGameInfo {
     fn key(&self, image_type: &ImageType, user_path: &Path) -> (PathBuf, String) {
-        let mut keys = POSSIBLE_EXTENSIONS
+        let mut keys = GRID_EXTENSIONS
             .iter()
             .map(|ext| key_from_extension(self.appid, image_type, user_path, ext));
         let first = keys.next().unwrap();
@@ -1061,7 +1392,7 @@ impl HasImageKey for SteamGameInfo {
 
 impl HasImageKey for ShortcutOwned {
     fn key(&self, image_type: &ImageType, user_path: &Path) -> (PathBuf, String) {
-        let mut keys = POSSIBLE_EXTENSIONS
+        let mut keys = GRID_EXTENSIONS
             .iter()
             .map(|ext| key_from_extension(self.app_id, image_type, user_path, ext));
         let first = keys.next().unwrap();
@@ -0,0 +1,353 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Arc,
+};
+
+use dashmap::DashMap;
+use egui::{Image, ScrollArea};
+use steam_shortcuts_util::shortcut::ShortcutOwned;
+use tokio::runtime::Runtime;
+
+use crate::steamgriddb::{is_animated_file, set_cache_entry, ImageType, GRID_EXTENSIONS};
+
+use super::{
+    ui_image_download::HasImageKey, ui_images::load_thumbnail_from_path, MyEguiApp, TextureState,
+};
+
+const GALLERY_TYPES: [ImageType; 4] = [
+    ImageType::Grid,
+    ImageType::Hero,
+    ImageType::Logo,
+    ImageType::Icon,
+];
+const THUMBNAIL_HEIGHT: f32 = 60.;
+/// Twice the shown height, so thumbnails stay sharp on high dpi screens
+const THUMBNAIL_PIXELS: u32 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GalleryFilter {
+    All,
+    MissingHero,
+    MissingIcon,
+    Animated,
+}
+
+impl GalleryFilter {
+    const ALL: [GalleryFilter; 4] = [
+        GalleryFilter::All,
+        GalleryFilter::MissingHero,
+        GalleryFilter::MissingIcon,
+        GalleryFilter::Animated,
+    ];
+
+    fn name(&self) -> &str {
+        match self {
+            GalleryFilter::All => "All shortcuts",
+            GalleryFilter::MissingHero => "Missing hero",
+            GalleryFilter::MissingIcon => "Missing icon",
+            GalleryFilter::Animated => "Animated",
+        }
+    }
+}
+
+pub struct GalleryState {
+    pub enabled: bool,
+    pub filter: GalleryFilter,
+    /// The app ids of the selected shortcuts
+    pub selected: HashSet<u32>,
+    /// The image types the bulk actions work on
+    pub action_types: Vec<ImageType>,
+    pub grid_id: String,
+    pub message: Option<String>,
+    /// Reading every image to see if it is animated is slow, so it is only done once per image
+    animated: HashMap<String, bool>,
+}
+
+impl Default for GalleryState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            filter: GalleryFilter::All,
+            selected: HashSet::new(),
+            action_types: GALLERY_TYPES.to_vec(),
+            grid_id: String::new(),
+            message: None,
+            animated: HashMap::new(),
+        }
+    }
+}
+
+enum GalleryAction {
+    Clear,
+    DownloadAgain,
+    Ban,
+    ChangeGridId(usize),
+}
+
+impl MyEguiApp {
+    /// Shows every shortcut of the selected user with its current images,
+    /// so images can be cleared, banned or downloaded again for many shortcuts at once
+    pub(super) fn render_image_gallery(&mut self, ui: &mut egui::Ui) {
+        let (user, shortcuts) = match (
+            &self.image_selected_state.steam_user,
+            &self.image_selected_state.user_shortcuts,
+        ) {
+            (Some(user), Some(shortcuts)) => (user.clone(), shortcuts.clone()),
+            _ => return,
+        };
+        let user_folder = Path::new(&user.steam_user_data_folder);
+        let state = &mut self.image_selected_state;
+        let image_handles = state.image_handles.clone();
+        let rt = &self.rt;
+        let gallery = &mut state.gallery;
+
+        let visible: Vec<&ShortcutOwned> = shortcuts
+            .iter()
+            .filter(|shortcut| matches_filter(gallery, shortcut, user_folder))
+            .collect();
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::new("GalleryFilter", "")
+                .selected_text(gallery.filter.name())
+                .show_ui(ui, |ui| {
+                    for filter in GalleryFilter::ALL {
+                        ui.selectable_value(&mut gallery.filter, filter, filter.name());
+                    }
+                });
+            if ui.button("Select all shown").clicked() {
+                gallery
+                    .selected
+                    .extend(visible.iter().map(|shortcut| shortcut.app_id));
+            }
+            if ui.button("Select none").clicked() {
+                gallery.selected.clear();
+            }
+        });
+
+        let mut action = None;
+        if !gallery.selected.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} selected, for:", gallery.selected.len()));
+                for image_type in GALLERY_TYPES {
+                    let mut checked = gallery.action_types.contains(&image_type);
+                    if ui.checkbox(&mut checked, image_type.name()).changed() {
+                        if checked {
+                            gallery.action_types.push(image_type);
+                        } else {
+                            gallery.action_types.retain(|t| *t != image_type);
+                        }
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Clear images").clicked() {
+                    action = Some(GalleryAction::Clear);
+                }
+                if ui.button("Download again").clicked() {
+                    action = Some(GalleryAction::DownloadAgain);
+                }
+                if ui
+                    .button("Stop downloading")
+                    .on_hover_text("Clears the images and never downloads them again")
+                    .clicked()
+                {
+                    action = Some(GalleryAction::Ban);
+                }
+                ui.label("SteamGridDB game id:");
+                ui.add(egui::TextEdit::singleline(&mut gallery.grid_id).desired_width(80.));
+                if ui.button("Change game").clicked() {
+                    match gallery.grid_id.trim().parse() {
+                        Ok(grid_id) => action = Some(GalleryAction::ChangeGridId(grid_id)),
+                        Err(_) => gallery.message = Some("That is not a game id".to_string()),
+                    }
+                }
+            });
+        }
+        if let Some(message) = &gallery.message {
+            ui.label(message);
+        }
+        ui.add_space(10.);
+
+        ScrollArea::vertical()
+            .stick_to_right(true)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                egui::Grid::new("image_gallery")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.label("Name");
+                        for image_type in GALLERY_TYPES {
+                            ui.label(image_type.name());
+                        }
+                        ui.end_row();
+                        for shortcut in &visible {
+                            let mut selected = gallery.selected.contains(&shortcut.app_id);
+                            if ui.checkbox(&mut selected, "").changed() {
+                                if selected {
+                                    gallery.selected.insert(shortcut.app_id);
+                                } else {
+                                    gallery.selected.remove(&shortcut.app_id);
+                                }
+                            }
+                            ui.label(&shortcut.app_name);
+                            for image_type in GALLERY_TYPES {
+                                let (path, key) = shortcut.key(&image_type, user_folder);
+                                render_gallery_thumbnail(ui, rt, &image_handles, &path, &key);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if let Some(action) = action {
+            let download_again = matches!(
+                action,
+                GalleryAction::DownloadAgain | GalleryAction::ChangeGridId(_)
+            );
+            self.apply_gallery_action(action, &shortcuts, user_folder);
+            if download_again {
+                self.start_image_download(vec![user.clone()]);
+            }
+        }
+    }
+
+    fn apply_gallery_action(
+        &mut self,
+        action: GalleryAction,
+        shortcuts: &[ShortcutOwned],
+        user_folder: &Path,
+    ) {
+        let state = &mut self.image_selected_state;
+        let selected: Vec<&ShortcutOwned> = shortcuts
+            .iter()
+            .filter(|shortcut| state.gallery.selected.contains(&shortcut.app_id))
+            .collect();
+        let image_types = state.gallery.action_types.clone();
+        match &action {
+            GalleryAction::Ban => {
+                for shortcut in &selected {
+                    for image_type in &image_types {
+                        self.settings.steamgrid_db.set_image_banned(
+                            image_type,
+                            shortcut.app_id,
+                            true,
+                        );
+                    }
+                }
+            }
+            GalleryAction::ChangeGridId(grid_id) => {
+                for shortcut in &selected {
+                    set_cache_entry(shortcut.app_id, shortcut.app_name.clone(), *grid_id);
+                }
+            }
+            GalleryAction::Clear | GalleryAction::DownloadAgain => {}
+        }
+        for shortcut in &selected {
+            for image_type in &image_types {
+                for ext in GRID_EXTENSIONS {
+                    let path = user_folder
+                        .join("config")
+                        .join("grid")
+                        .join(image_type.file_name(shortcut.app_id, ext));
+                    if path.exists() {
+                        let _ = std::fs::remove_file(&path);
+                    }
+                    let key = path.to_string_lossy().to_string();
+                    forget_image(&state.image_handles, &key);
+                    state.gallery.animated.remove(&key);
+                }
+            }
+        }
+        state.gallery.message = match action {
+            GalleryAction::Clear => {
+                Some(format!("Cleared images for {} shortcuts", selected.len()))
+            }
+            GalleryAction::Ban => Some(format!(
+                "Stopped downloading images for {} shortcuts",
+                selected.len()
+            )),
+            GalleryAction::DownloadAgain | GalleryAction::ChangeGridId(_) => None,
+        };
+    }
+}
+
+fn matches_filter(
+    gallery: &mut GalleryState,
+    shortcut: &ShortcutOwned,
+    user_folder: &Path,
+) -> bool {
+    let image_exists = |image_type: &ImageType| shortcut.key(image_type, user_folder).0.exists();
+    match gallery.filter {
+        GalleryFilter::All => true,
+        GalleryFilter::MissingHero => !image_exists(&ImageType::Hero),
+        GalleryFilter::MissingIcon => !image_exists(&ImageType::Icon),
+        GalleryFilter::Animated => GALLERY_TYPES.iter().any(|image_type| {
+            let (path, key) = shortcut.key(image_type, user_folder);
+            *gallery
+                .animated
+                .entry(key)
+                .or_insert_with(|| is_animated_file(&path))
+        }),
+    }
+}
+
+/// Thumbnails are kept apart from the full size image of the same file
+fn thumbnail_key(key: &str) -> String {
+    format!("{}#thumbnail", key)
+}
+
+/// Forgets the loaded image and its thumbnail, so both are loaded from disk again
+pub(super) fn forget_image(image_handles: &DashMap<String, TextureState>, key: &str) {
+    image_handles.remove(key);
+    image_handles.remove(&thumbnail_key(key));
+}
+
+/// Images are decoded and shrunk in the background, a spinner is shown until they are ready
+pub(super) fn render_gallery_thumbnail(
+    ui: &mut egui::Ui,
+    rt: &Runtime,
+    image_handles: &Arc<DashMap<String, TextureState>>,
+    path: &Path,
+    key: &str,
+) {
+    let key = thumbnail_key(key);
+    let needs_loading = match image_handles.get(&key).as_deref() {
+        None | Some(TextureState::Downloaded) => path.exists(),
+        _ => false,
+    };
+    if needs_loading {
+        image_handles.insert(key.clone(), TextureState::Downloading);
+        let image_handles = image_handles.clone();
+        let ctx = ui.ctx().clone();
+        let path = path.to_path_buf();
+        let key = key.clone();
+        rt.spawn_blocking(move || {
+            let state = match load_thumbnail_from_path(&path, THUMBNAIL_PIXELS) {
+                Ok(image) => {
+                    TextureState::Loaded(ctx.load_texture(&key, image, egui::TextureFilter::Linear))
+                }
+                Err(_) => TextureState::Failed,
+            };
+            image_handles.insert(key, state);
+            ctx.request_repaint();
+        });
+    }
+    match image_handles.get(&key).as_deref() {
+        Some(TextureState::Loaded(texture)) => {
+            let size = texture.size_vec2();
+            let width = size.x * THUMBNAIL_HEIGHT / size.y.max(1.);
+            ui.add(Image::new(texture, [width, THUMBNAIL_HEIGHT]));
+        }
+        Some(TextureState::Downloading) => {
+            ui.spinner();
+        }
+        Some(TextureState::Failed) => {
+            ui.label("Can not show");
+        }
+        _ => {
+            ui.label("Missing");
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use eframe::egui;
 use egui::ScrollArea;
 use futures::executor::block_on;
@@ -8,14 +10,19 @@ use tokio::sync::watch;
 use crate::config::get_renames_file;
 use crate::platforms::ShortcutToImport;
 #[cfg(target_family = "unix")]
-use crate::steam::setup_proton_games;
+use crate::settings::Settings;
+use crate::steam::is_collections_db_locked;
+#[cfg(target_family = "unix")]
+use crate::steam::{remove_proton_games, set_compat_tools, setup_proton_games, NATIVE_COMPAT_TOOL};
 use crate::sync;
 
-use crate::sync::{download_images, SyncProgress};
+use crate::sync::{download_images, SyncCancel, SyncProgress};
 
 use super::{all_ready, backup_shortcuts, get_all_games};
 use super::{
     ui_colors::{BACKGROUND_COLOR, EXTRA_BACKGROUND_COLOR},
+    ui_import_list::visible_games,
+    ui_settings::render_controller_template_combo,
     MyEguiApp,
 };
 
@@ -39,7 +46,12 @@ impl<T> FetcStatus<T> {
 
 impl MyEguiApp {
     pub(crate) fn render_import_games(&mut self, ui: &mut egui::Ui) {
+        if self.game_details.selected.is_some() {
+            self.render_game_details(ui);
+            return;
+        }
         ui.heading("Import Games");
+        self.render_import_list_controls(ui);
 
         let mut scroll_style = ui.style_mut();
         scroll_style.visuals.extreme_bg_color = BACKGROUND_COLOR;
@@ -48,6 +60,7 @@ impl MyEguiApp {
         scroll_style.visuals.selection.bg_fill = EXTRA_BACKGROUND_COLOR;
         scroll_style.visuals.widgets.hovered.bg_fill = EXTRA_BACKGROUND_COLOR;
 
+        let mut open_details = None;
         ScrollArea::vertical()
         .stick_to_right(true)
         .auto_shrink([false,true])
@@ -55,72 +68,147 @@ impl MyEguiApp {
             ui.reset_style();
             ui.label("Select the games you want to import into steam");
             for (name,status) in &self.games_to_sync{
-                ui.heading(name);
-                match &*status.borrow(){
-                    FetcStatus::NeedsFetched => {ui.label("Need to find games");},
-                    FetcStatus::Fetching => {
-                        ui.horizontal(|ui|{
-                            ui.spinner();
-                            ui.label("Finding installed games");
-                        });
+                if !self.import_list.shows_platform(name){
+                    continue;
+                }
+                let status = status.borrow();
+                let header = match &*status{
+                    FetcStatus::Fetched(Ok(shortcuts)) => {
+                        let imported = shortcuts.iter().filter(|info| !self.settings.blacklisted_games.contains(&info.shortcut.app_id)).count();
+                        format!("{} ({} of {} imported)", name, imported, shortcuts.len())
                     },
-                    FetcStatus::Fetched(shortcuts) => {
-                        match shortcuts{
-                            Ok(shortcuts) => {
-                                if shortcuts.is_empty(){
-                                    ui.label("Did not find any games");
-                                }
-                                for shortcut_to_import in shortcuts {
-                                    let shortcut = &shortcut_to_import.shortcut;
-                                    let mut import_game = !self.settings.blacklisted_games.contains(&shortcut.app_id);
-                                    ui.horizontal(|ui|{
-                                        if self.current_edit == Option::Some(shortcut.app_id){
-                                            if let Some(new_name) = self.rename_map.get_mut(&shortcut.app_id){
-                                                ui.text_edit_singleline(new_name).request_focus();
-                                                if ui.button("Rename").clicked() {
-                                                    if new_name.is_empty(){
-                                                        *new_name = shortcut.app_name.to_string();
+                    _ => name.clone(),
+                };
+                egui::CollapsingHeader::new(egui::RichText::new(header).heading())
+                .id_source(name)
+                .default_open(true)
+                .show(ui, |ui| {
+                    match &*status{
+                        FetcStatus::NeedsFetched => {ui.label("Need to find games");},
+                        FetcStatus::Fetching => {
+                            ui.horizontal(|ui|{
+                                ui.spinner();
+                                ui.label("Finding installed games");
+                            });
+                        },
+                        FetcStatus::Fetched(shortcuts) => {
+                            match shortcuts{
+                                Ok(shortcuts) => {
+                                    if shortcuts.is_empty(){
+                                        ui.label("Did not find any games");
+                                    }
+                                    let visible = visible_games(&self.import_list, &self.settings, &self.rename_map, name, shortcuts);
+                                    if !shortcuts.is_empty() && visible.is_empty(){
+                                        ui.label("No games match the search");
+                                    }
+                                    if !visible.is_empty(){
+                                        ui.horizontal(|ui|{
+                                            let is_visible = |app_id: &u32| visible.iter().any(|info| info.shortcut.app_id == *app_id);
+                                            if ui.small_button("Select all").on_hover_text("Imports every game shown from this platform").clicked(){
+                                                self.settings.blacklisted_games.retain(|id| !is_visible(id));
+                                            }
+                                            if ui.small_button("Select none").on_hover_text("Imports none of the games shown from this platform").clicked(){
+                                                self.settings.blacklisted_games.retain(|id| !is_visible(id));
+                                                self.settings.blacklisted_games.extend(visible.iter().map(|info| info.shortcut.app_id));
+                                            }
+                                        });
+                                    }
+                                    for shortcut_to_import in visible {
+                                        let platform = name;
+                                        let shortcut = &shortcut_to_import.shortcut;
+                                        let mut import_game = !self.settings.blacklisted_games.contains(&shortcut.app_id);
+                                        ui.horizontal(|ui|{
+                                            if self.current_edit == Option::Some(shortcut.app_id){
+                                                if let Some(new_name) = self.rename_map.get_mut(&shortcut.app_id){
+                                                    ui.text_edit_singleline(new_name).request_focus();
+                                                    if ui.button("Rename").clicked() {
+                                                        if new_name.is_empty(){
+                                                            *new_name = shortcut.app_name.to_string();
+                                                        }
+                                                        self.current_edit = Option::None;
+                                                        save_rename_map(&self.rename_map);
+                                                    }
+                                                }
+                                            }  else {
+                                                let name = self.rename_map.get(&shortcut.app_id).unwrap_or(&shortcut.app_name);
+                                                let checkbox = egui::Checkbox::new(&mut import_game,name);
+                                                let response = ui.add(checkbox);
+                                                if response.double_clicked(){
+                                                    self.rename_map.entry(shortcut.app_id).or_insert_with(|| shortcut.app_name.to_owned());
+                                                    self.current_edit = Option::Some(shortcut.app_id);
+                                                }
+                                                if response.clicked(){
+                                                    if !self.settings.blacklisted_games.contains(&shortcut.app_id){
+                                                        self.settings.blacklisted_games.push(shortcut.app_id);
+                                                    } else {
+                                                        self.settings.blacklisted_games.retain(|id| *id != shortcut.app_id);
+                                                    }
+                                                }
+                                                if import_game {
+                                                    let mut hidden = self.settings.hidden_games.contains(&shortcut.app_id);
+                                                    let hidden_checkbox = ui.checkbox(&mut hidden, "Hidden")
+                                                        .on_hover_text("Imports the game hidden, it can be launched but is not shown in the library by default");
+                                                    if hidden_checkbox.changed(){
+                                                        if hidden {
+                                                            self.settings.hidden_games.push(shortcut.app_id);
+                                                        } else {
+                                                            self.settings.hidden_games.retain(|id| *id != shortcut.app_id);
+                                                        }
                                                     }
-                                                    self.current_edit = Option::None;
-                                                    let rename_file_path = get_renames_file();
-                                                    let contents = serde_json::to_string(&self.rename_map);
-                                                    if let Ok(contents) = contents{
-                                                        let res = std::fs::write(&rename_file_path, contents);
-                                                        println!("Write rename file at {:?} with result: {:?}",rename_file_path, res);
+                                                    let templates = &mut self.settings.steam.controller_templates;
+                                                    let mut template = templates.game_template(shortcut.app_id).copied();
+                                                    if render_controller_template_combo(ui, shortcut.app_id, &mut template, "Platform controller layout"){
+                                                        templates.set_game_template(shortcut.app_id, template);
                                                     }
                                                 }
-                                            }
-                                        }  else {
-                                            let name = self.rename_map.get(&shortcut.app_id).unwrap_or(&shortcut.app_name);
-                                            let checkbox = egui::Checkbox::new(&mut import_game,name);
-                                            let response = ui.add(checkbox);
-                                            if response.double_clicked(){
-                                                self.rename_map.entry(shortcut.app_id).or_insert_with(|| shortcut.app_name.to_owned());
-                                                self.current_edit = Option::Some(shortcut.app_id);
-                                            }
-                                            if response.clicked(){
-                                                if !self.settings.blacklisted_games.contains(&shortcut.app_id){
-                                                    self.settings.blacklisted_games.push(shortcut.app_id);
-                                                } else {
-                                                    self.settings.blacklisted_games.retain(|id| *id != shortcut.app_id);
+                                                if ui.small_button("Details").on_hover_text("Shows the shortcut that is imported, and settings for only this game").clicked(){
+                                                    open_details = Some((platform.clone(), shortcut.clone()));
                                                 }
                                             }
-                                        }
-                                    });
-                                }
-                            },
-                            Err(err) => {
-                                ui.label("Failed finding games").on_hover_text(format!("Error message: {err}"));
-                            },
-                        };
-                    },
-                }
+                                        });
+                                    }
+                                },
+                                Err(err) => {
+                                    ui.label("Failed finding games").on_hover_text(format!("Error message: {err}"));
+                                },
+                            };
+                        },
+                    }
+                });
 
             };
             ui.add_space(SECTION_SPACING);
 
             ui.label("Check the settings if BoilR didn't find the game you where looking for");
         });
+        if let Some((platform, shortcut)) = open_details {
+            self.open_game_details(platform, &shortcut);
+        }
+    }
+
+    /// Steam locks its collections while it runs, so they can only be written if it is stopped first
+    pub(crate) fn collections_need_steam_stopped(&self) -> bool {
+        let steam = &self.settings.steam;
+        let wants_collections = steam.create_collections || !self.settings.hidden_games.is_empty();
+        wants_collections && !steam.stop_steam && is_collections_db_locked(steam)
+    }
+
+    pub(crate) fn render_steam_running_prompt(&mut self, ui: &mut egui::Ui) {
+        ui.label("Steam is running, collections and hidden games can only be written while it is closed");
+        ui.horizontal(|ui| {
+            if ui.button("Stop Steam and import").clicked() {
+                self.steam_running_prompt = false;
+                crate::steam::ensure_steam_stopped();
+                self.run_sync(false);
+            }
+            if ui.button("Import without collections").clicked() {
+                self.steam_running_prompt = false;
+                self.run_sync(false);
+            }
+            if ui.button("Cancel").clicked() {
+                self.steam_running_prompt = false;
+            }
+        });
     }
 
     pub fn run_sync(&mut self, wait: bool) {
@@ -131,6 +219,8 @@ impl MyEguiApp {
         }
 
         self.status_reciever = reciever;
+        let cancel = SyncCancel::default();
+        self.sync_cancel = cancel.clone();
         let renames = self.rename_map.clone();
         let all_ready = all_ready(&self.games_to_sync);
         let _ = sender.send(SyncProgress::Starting);
@@ -138,26 +228,49 @@ impl MyEguiApp {
             let shortcuts_to_import = get_all_games(&self.games_to_sync);
             let handle = self.rt.spawn_blocking(move || {
                 #[cfg(target_family = "unix")]
-                setup_proton(shortcuts_to_import.iter());
+                setup_proton(shortcuts_to_import.iter(), &settings, &renames);
 
+                sync::save_shortcut_external_ids(&shortcuts_to_import, &renames);
                 let import_games = to_shortcut_owned(shortcuts_to_import);
 
                 let mut some_sender = Some(sender);
                 backup_shortcuts(&settings.steam);
-                let usersinfo =
-                    sync::sync_shortcuts(&settings, &import_games, &mut some_sender, &renames)
-                        .unwrap();
-                let task = download_images(&settings, &usersinfo, &mut some_sender);
-                block_on(task);
-                //Run a second time to fix up shortcuts after images are downloaded
-                if let Err(e) = sync::fix_all_shortcut_icons(&settings){
-                    eprintln!("Could not fix shortcuts with error {e}");
+                let usersinfo = match sync::sync_shortcuts(
+                    &settings,
+                    &import_games,
+                    &mut some_sender,
+                    &renames,
+                    &cancel,
+                ) {
+                    Ok(usersinfo) => usersinfo,
+                    Err(e) => {
+                        eprintln!("Could not import games: {e}");
+                        if let Some(sender) = some_sender {
+                            let _ = sender.send(SyncProgress::Failed(e));
+                        }
+                        return;
+                    }
+                };
+                if !cancel.is_cancelled() {
+                    let task = download_images(&settings, &usersinfo, &mut some_sender, &cancel);
+                    block_on(task);
+                }
+                // A cancelled import does not touch the shortcuts or Steam again
+                if !cancel.is_cancelled() {
+                    //Run a second time to fix up shortcuts after images are downloaded
+                    if let Err(e) = sync::fix_all_shortcut_icons(&settings) {
+                        eprintln!("Could not fix shortcuts with error {e}");
+                    }
                 }
 
                 if let Some(sender) = some_sender {
-                    let _ = sender.send(SyncProgress::Done);
+                    if cancel.is_cancelled() {
+                        let _ = sender.send(SyncProgress::Cancelled);
+                    } else {
+                        let _ = sender.send(SyncProgress::Done);
+                    }
                 }
-                if settings.steam.start_steam {
+                if settings.steam.start_steam && !cancel.is_cancelled() {
                     crate::steam::ensure_steam_started(&settings.steam);
                 }
             });
@@ -168,6 +281,18 @@ impl MyEguiApp {
     }
 }
 
+pub(super) fn save_rename_map(rename_map: &HashMap<u32, String>) {
+    let rename_file_path = get_renames_file();
+    let contents = serde_json::to_string(rename_map);
+    if let Ok(contents) = contents {
+        let res = std::fs::write(&rename_file_path, contents);
+        println!(
+            "Write rename file at {:?} with result: {:?}",
+            rename_file_path, res
+        );
+    }
+}
+
 fn to_shortcut_owned(
     shortcuts_to_import: Vec<(String, Vec<ShortcutToImport>)>,
 ) -> Vec<(String, Vec<ShortcutOwned>)> {
@@ -183,25 +308,41 @@ fn to_shortcut_owned(
 }
 
 #[cfg(target_family = "unix")]
-fn setup_proton<'a, I>(shortcut_infos: I)
+fn setup_proton<'a, I>(shortcut_infos: I, settings: &Settings, renames: &HashMap<u32, String>)
 where
     I: IntoIterator<Item = &'a (String, Vec<ShortcutToImport>)>,
 {
     let mut shortcuts_to_proton = vec![];
+    let mut compat_tools = vec![];
+    let mut native_games = vec![];
 
     for (name, shortcuts) in shortcut_infos {
         for shortcut_info in shortcuts {
             if shortcut_info.needs_proton {
                 crate::sync::symlinks::ensure_links_folder_created(name);
             }
-            if shortcut_info.needs_proton {
-                shortcuts_to_proton.push(format!("{}", shortcut_info.shortcut.app_id));
+            // Steam maps the tools by the app id the shortcut has after it is renamed
+            let shortcut = &shortcut_info.shortcut;
+            let app_id = sync::generated_shortcut(settings, name, shortcut, renames)
+                .app_id
+                .to_string();
+            match settings.steam.compat_tool(shortcut.app_id) {
+                Some(NATIVE_COMPAT_TOOL) => native_games.push(app_id),
+                Some(tool) => compat_tools.push((app_id, tool)),
+                None if shortcut_info.needs_proton => shortcuts_to_proton.push(app_id),
+                None => {}
             }
 
             if shortcut_info.needs_symlinks {
-                crate::sync::symlinks::create_sym_links(&shortcut_info.shortcut);
+                crate::sync::symlinks::create_sym_links(shortcut);
             }
         }
-        setup_proton_games(&shortcuts_to_proton);
     }
+    if !native_games.is_empty() {
+        remove_proton_games(&settings.steam, &native_games);
+    }
+    if !compat_tools.is_empty() {
+        set_compat_tools(&settings.steam, &compat_tools);
+    }
+    setup_proton_games(&settings.steam, &shortcuts_to_proton);
 }
@@ -0,0 +1,295 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    platforms::ShortcutToImport,
+    settings::Settings,
+    steam::{get_shortcuts_for_user, get_shortcuts_paths, SteamSettings},
+    sync::{generated_shortcut, IsBoilRShortcut},
+};
+
+use super::{FetcStatus, MyEguiApp};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFilter {
+    All,
+    Blacklisted,
+    Renamed,
+    NeedsProton,
+    NewSinceLastSync,
+}
+
+impl ImportFilter {
+    const ALL: [ImportFilter; 5] = [
+        ImportFilter::All,
+        ImportFilter::Blacklisted,
+        ImportFilter::Renamed,
+        ImportFilter::NeedsProton,
+        ImportFilter::NewSinceLastSync,
+    ];
+
+    fn name(&self) -> &str {
+        match self {
+            ImportFilter::All => "All games",
+            ImportFilter::Blacklisted => "Not imported",
+            ImportFilter::Renamed => "Renamed",
+            ImportFilter::NeedsProton => "Needs proton",
+            ImportFilter::NewSinceLastSync => "New since last import",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSort {
+    Found,
+    NameAscending,
+    NameDescending,
+    ImportedFirst,
+}
+
+impl ImportSort {
+    const ALL: [ImportSort; 4] = [
+        ImportSort::Found,
+        ImportSort::NameAscending,
+        ImportSort::NameDescending,
+        ImportSort::ImportedFirst,
+    ];
+
+    fn name(&self) -> &str {
+        match self {
+            ImportSort::Found => "As found",
+            ImportSort::NameAscending => "Name A-Z",
+            ImportSort::NameDescending => "Name Z-A",
+            ImportSort::ImportedFirst => "Imported first",
+        }
+    }
+}
+
+/// The search, filters and sorting of the import list
+pub struct ImportListState {
+    pub search: String,
+    /// Only this platform is shown when it is set
+    pub platform: Option<String>,
+    pub filter: ImportFilter,
+    pub sort: ImportSort,
+    /// The app ids of the shortcuts BoilR imported before, read again after every import
+    imported_app_ids: Option<HashSet<u32>>,
+    was_syncing: bool,
+}
+
+impl Default for ImportListState {
+    fn default() -> Self {
+        Self {
+            search: String::new(),
+            platform: None,
+            filter: ImportFilter::All,
+            sort: ImportSort::Found,
+            imported_app_ids: None,
+            was_syncing: false,
+        }
+    }
+}
+
+impl ImportListState {
+    pub fn shows_platform(&self, platform: &str) -> bool {
+        self.platform
+            .as_ref()
+            .map(|shown| shown == platform)
+            .unwrap_or(true)
+    }
+}
+
+impl MyEguiApp {
+    /// Shows the search, filters and sorting of the import list, and a count of what will be imported
+    pub(super) fn render_import_list_controls(&mut self, ui: &mut egui::Ui) {
+        let syncing = self.status_reciever.borrow().is_running();
+        let state = &mut self.import_list;
+        if state.was_syncing && !syncing {
+            state.imported_app_ids = None;
+        }
+        state.was_syncing = syncing;
+        if state.filter == ImportFilter::NewSinceLastSync && state.imported_app_ids.is_none() {
+            state.imported_app_ids = Some(load_imported_app_ids(&self.settings.steam));
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut state.search)
+                    .hint_text("Search")
+                    .desired_width(200.),
+            );
+            egui::ComboBox::from_id_source("import_platform")
+                .selected_text(state.platform.as_deref().unwrap_or("All platforms"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut state.platform, None, "All platforms");
+                    for (name, _) in &self.games_to_sync {
+                        ui.selectable_value(&mut state.platform, Some(name.clone()), name);
+                    }
+                });
+            egui::ComboBox::from_id_source("import_filter")
+                .selected_text(state.filter.name())
+                .show_ui(ui, |ui| {
+                    for filter in ImportFilter::ALL {
+                        ui.selectable_value(&mut state.filter, filter, filter.name());
+                    }
+                });
+            egui::ComboBox::from_id_source("import_sort")
+                .selected_text(state.sort.name())
+                .show_ui(ui, |ui| {
+                    for sort in ImportSort::ALL {
+                        ui.selectable_value(&mut state.sort, sort, sort.name());
+                    }
+                });
+        });
+
+        let (mut total, mut imported, mut hidden, mut renamed) = (0, 0, 0, 0);
+        for (_, status) in &self.games_to_sync {
+            if let FetcStatus::Fetched(Ok(shortcuts)) = &*status.borrow() {
+                for info in shortcuts {
+                    let app_id = info.shortcut.app_id;
+                    total += 1;
+                    if self.settings.blacklisted_games.contains(&app_id) {
+                        continue;
+                    }
+                    imported += 1;
+                    if self.settings.hidden_games.contains(&app_id) {
+                        hidden += 1;
+                    }
+                    if is_renamed(info, &self.rename_map) {
+                        renamed += 1;
+                    }
+                }
+            }
+        }
+        ui.label(format!(
+            "{imported} of {total} games will be imported, {hidden} of them hidden and {renamed} renamed"
+        ));
+    }
+}
+
+/// The games of a platform that match the search and filters, in the chosen order
+pub(super) fn visible_games<'a>(
+    state: &ImportListState,
+    settings: &Settings,
+    renames: &HashMap<u32, String>,
+    platform: &str,
+    shortcuts: &'a [ShortcutToImport],
+) -> Vec<&'a ShortcutToImport> {
+    let search = state.search.trim().to_lowercase();
+    let display_name = |info: &ShortcutToImport| {
+        renames
+            .get(&info.shortcut.app_id)
+            .unwrap_or(&info.shortcut.app_name)
+            .to_lowercase()
+    };
+    let is_blacklisted =
+        |info: &ShortcutToImport| settings.blacklisted_games.contains(&info.shortcut.app_id);
+    let mut games: Vec<&ShortcutToImport> = shortcuts
+        .iter()
+        .filter(|info| {
+            search.is_empty()
+                || display_name(*info).contains(&search)
+                || info.shortcut.app_name.to_lowercase().contains(&search)
+        })
+        .filter(|info| match state.filter {
+            ImportFilter::All => true,
+            ImportFilter::Blacklisted => is_blacklisted(*info),
+            ImportFilter::Renamed => is_renamed(info, renames),
+            ImportFilter::NeedsProton => info.needs_proton,
+            ImportFilter::NewSinceLastSync => {
+                let app_id = generated_shortcut(settings, platform, &info.shortcut, renames).app_id;
+                !state
+                    .imported_app_ids
+                    .as_ref()
+                    .map(|ids| ids.contains(&app_id))
+                    .unwrap_or(false)
+            }
+        })
+        .collect();
+    match state.sort {
+        ImportSort::Found => {}
+        ImportSort::NameAscending => games.sort_by_key(|info| display_name(*info)),
+        ImportSort::NameDescending => {
+            games.sort_by_key(|info| std::cmp::Reverse(display_name(*info)))
+        }
+        ImportSort::ImportedFirst => games.sort_by_key(|info| is_blacklisted(*info)),
+    }
+    games
+}
+
+fn is_renamed(info: &ShortcutToImport, renames: &HashMap<u32, String>) -> bool {
+    renames
+        .get(&info.shortcut.app_id)
+        .map(|name| name != &info.shortcut.app_name)
+        .unwrap_or(false)
+}
+
+fn load_imported_app_ids(settings: &SteamSettings) -> HashSet<u32> {
+    match get_shortcuts_paths(settings) {
+        Ok(users) => users
+            .iter()
+            .flat_map(|user| get_shortcuts_for_user(user).shortcuts)
+            .filter(|shortcut| shortcut.is_boilr_shortcut())
+            .map(|shortcut| shortcut.app_id)
+            .collect(),
+        Err(e) => {
+            eprintln!("Could not read the imported shortcuts: {}", e);
+            HashSet::new()
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod tests {
+    use super::*;
+    use crate::sync::fake_steam::FakeSteam;
+    use steam_shortcuts_util::Shortcut;
+
+    fn game(name: &str, needs_proton: bool) -> ShortcutToImport {
+        ShortcutToImport {
+            shortcut: Shortcut::new("0", name, name, "", "", "", "").to_owned(),
+            needs_proton,
+            needs_symlinks: false,
+            external_id: None,
+        }
+    }
+
+    fn names(games: &[&ShortcutToImport]) -> Vec<String> {
+        games.iter().map(|g| g.shortcut.app_name.clone()).collect()
+    }
+
+    #[test]
+    fn searches_filters_and_sorts_games() {
+        let steam = FakeSteam::new("import_list");
+        let games = vec![
+            game("Celeste", false),
+            game("Doom", true),
+            game("Age of Empires", true),
+        ];
+        let mut settings = steam.settings();
+        settings.blacklisted_games = vec![games[1].shortcut.app_id];
+        let mut renames = HashMap::new();
+        renames.insert(games[0].shortcut.app_id, "Zelda".to_string());
+        let mut state = ImportListState {
+            sort: ImportSort::NameAscending,
+            ..Default::default()
+        };
+        let visible = |state: &ImportListState| {
+            names(&visible_games(state, &settings, &renames, "Lutris", &games))
+        };
+
+        assert_eq!(visible(&state), vec!["Age of Empires", "Doom", "Celeste"]);
+        state.sort = ImportSort::ImportedFirst;
+        assert_eq!(visible(&state), vec!["Celeste", "Age of Empires", "Doom"]);
+        state.search = "zel".to_string();
+        assert_eq!(visible(&state), vec!["Celeste"]);
+        state.search.clear();
+        state.filter = ImportFilter::NeedsProton;
+        state.sort = ImportSort::NameDescending;
+        assert_eq!(visible(&state), vec!["Doom", "Age of Empires"]);
+        state.filter = ImportFilter::Blacklisted;
+        assert_eq!(visible(&state), vec!["Doom"]);
+        state.filter = ImportFilter::Renamed;
+        assert_eq!(visible(&state), vec!["Celeste"]);
+    }
+}
@@ -2,6 +2,15 @@ use copypasta::ClipboardProvider;
 use eframe::egui;
 use egui::ScrollArea;
 
+use crate::steam::{
+    get_shortcuts_paths, read_collections, template_from_text, Collection, ControllerTemplate,
+    SteamSettings,
+};
+use crate::steamgriddb::{
+    available_dimensions, available_mime_types, available_styles, ContentFilter, ImagePreferences,
+    ImageType,
+};
+
 use super::{
     ui_colors::{BACKGROUND_COLOR, EXTRA_BACKGROUND_COLOR},
     MyEguiApp,
@@ -28,6 +37,8 @@ impl MyEguiApp {
 
                 self.render_steamgriddb_settings(ui);
 
+                self.render_local_artwork_settings(ui);
+
                 self.render_steam_settings(ui);
 
                 for platform in &mut self.platforms {
@@ -62,6 +73,38 @@ impl MyEguiApp {
             "Create collections",
         )
         .on_hover_text("Tries to create a games collection for each platform");
+        if self.settings.steam.create_collections {
+            ui.horizontal(|ui| {
+                ui.label("Collection name: ");
+                ui.text_edit_singleline(&mut self.settings.steam.collection_rules.name_template)
+                    .on_hover_text("{platform} is replaced with the name of the platform, collections by tag and by game can be set up in the config file");
+            });
+        }
+        if ui
+            .button("Show collections in Steam")
+            .on_hover_text("Reads a copy of the collections, so this works while Steam is running")
+            .clicked()
+        {
+            self.steam_collections = Some(load_steam_collections(&self.settings.steam));
+        }
+        match &self.steam_collections {
+            Some(Ok(users)) => {
+                for (user_id, collections) in users {
+                    ui.label(format!("Steam user {}", user_id));
+                    for collection in collections {
+                        ui.label(format!(
+                            "    {} ({} games)",
+                            collection.name,
+                            collection.game_ids.len()
+                        ));
+                    }
+                }
+            }
+            Some(Err(e)) => {
+                ui.label(format!("Could not read the collections: {}", e));
+            }
+            None => {}
+        }
         ui.checkbox(&mut self.settings.steam.optimize_for_big_picture, "Optimize for big picture").on_hover_text("Set icons to be larger horizontal images, this looks nice in steam big picture mode, but a bit off in desktop mode");
         ui.checkbox(
             &mut self.settings.steam.stop_steam,
@@ -73,6 +116,74 @@ impl MyEguiApp {
             "Start Steam after import",
         )
         .on_hover_text("Starts Steam is it is not running after the import");
+        self.render_controller_template_settings(ui);
+        self.render_launch_option_settings(ui);
+        ui.add_space(SECTION_SPACING);
+    }
+
+    fn render_launch_option_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label("Launch options").on_hover_text(
+            "Environment variables, wrappers and arguments for imported games, like DXVK_ASYNC=1 gamemoderun %command% -fullscreen",
+        );
+        let templates = &mut self.settings.steam.launch_options;
+        for platform in self.platforms.iter().filter(|p| p.enabled()) {
+            let name = platform.name();
+            let mut template = templates
+                .platform_template(name)
+                .cloned()
+                .unwrap_or_default();
+            ui.horizontal(|ui| {
+                ui.label(format!("{}: ", name));
+                if ui.text_edit_singleline(&mut template).changed() {
+                    templates.set_platform_template(name, template_from_text(&template));
+                }
+            });
+        }
+    }
+
+    fn render_controller_template_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label("Controller layouts")
+            .on_hover_text("The Steam Input layout imported games get, it can also be picked for a single game on the import page");
+        let templates = &mut self.settings.steam.controller_templates;
+        for platform in self.platforms.iter().filter(|p| p.enabled()) {
+            let name = platform.name();
+            let mut template = templates.platform_template(name).copied();
+            ui.horizontal(|ui| {
+                ui.label(format!("{}: ", name));
+                if render_controller_template_combo(ui, name, &mut template, "Steam default") {
+                    templates.set_platform_template(name, template);
+                }
+            });
+        }
+    }
+
+    fn render_local_artwork_settings(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Local artwork");
+        let settings = &mut self.settings.local_artwork;
+        ui.checkbox(&mut settings.enabled, "Use images from a folder")
+            .on_hover_text(
+                "Images found here are used before anything is downloaded from SteamGridDB",
+            );
+        if settings.enabled {
+            ui.horizontal(|ui| {
+                let mut folder = settings.folder.clone().unwrap_or_default();
+                ui.label("Artwork folder: ");
+                if ui.text_edit_singleline(&mut folder).changed() {
+                    settings.folder = if folder.trim().is_empty() {
+                        None
+                    } else {
+                        Some(folder)
+                    };
+                }
+            });
+            ui.label("Put the images in a folder per game, named after the game or its platform id like gog-1207658924");
+            ui.label("Name the images grid, hero, logo, icon, widegrid or bigpicture, as png, jpg, webp or ico");
+            #[cfg(target_family = "unix")]
+            ui.checkbox(
+                &mut settings.link_images,
+                "Link to the images instead of copying them",
+            );
+        }
         ui.add_space(SECTION_SPACING);
     }
 
@@ -117,8 +228,187 @@ impl MyEguiApp {
                 &mut self.settings.steamgrid_db.only_download_boilr_images,
                 "Only download images for BoilR shortcuts",
             );
-            ui.checkbox(&mut self.settings.steamgrid_db.allow_nsfw, "Allow NSFW images");
+            ui.checkbox(
+                &mut self.settings.steamgrid_db.allow_nsfw,
+                "Allow NSFW images",
+            );
+            ui.checkbox(
+                &mut self.settings.steamgrid_db.apply_to_all_users,
+                "Use the same images for all Steam users",
+            )
+            .on_hover_text(
+                "Images are downloaded once and shared with every Steam user on this computer that has the game",
+            );
+            ui.checkbox(
+                &mut self.settings.steamgrid_db.transcode_webp,
+                "Convert webp images to png",
+            )
+            .on_hover_text(
+                "Some Steam clients can not show webp images, animated images are kept as they are",
+            );
+            ui.checkbox(
+                &mut self.settings.steamgrid_db.resize_large_images,
+                "Shrink large images",
+            )
+            .on_hover_text(
+                "Scales images down to the largest size Steam shows them at, this saves disk space",
+            );
+            ui.horizontal(|ui| {
+                ui.label("Concurrent downloads: ");
+                ui.add(egui::DragValue::new(
+                    &mut self.settings.steamgrid_db.concurrent_downloads,
+                ).clamp_range(1..=50));
+            })
+            .response
+            .on_hover_text("How many images are downloaded at the same time, lower this if SteamGridDB is rate limiting you");
+            ui.horizontal(|ui| {
+                ui.label("Download retries: ");
+                ui.add(
+                    egui::DragValue::new(&mut self.settings.steamgrid_db.download_retries)
+                        .clamp_range(0..=10),
+                );
+            })
+            .response
+            .on_hover_text(
+                "How many times a failed image download is retried before it is given up on",
+            );
+            ui.horizontal(|ui| {
+                ui.label("Search games again after (days): ");
+                ui.add(egui::DragValue::new(
+                    &mut self.settings.steamgrid_db.cache_max_age_days,
+                ));
+            })
+            .response
+            .on_hover_text(
+                "Games are matched again after this many days, 0 keeps matches forever. Matches you picked yourself are always kept",
+            );
+            ui.collapsing("Image preferences", |ui| {
+                ui.label("Nothing selected means any style, size or file type is fine");
+                for image_type in ImageType::all() {
+                    ui.collapsing(image_type.name(), |ui| {
+                        let preferences = self
+                            .settings
+                            .steamgrid_db
+                            .image_preferences
+                            .get_mut(image_type);
+                        render_image_preferences(ui, image_type, preferences);
+                    });
+                }
+            });
         }
         ui.add_space(SECTION_SPACING);
     }
 }
+
+fn render_image_preferences(
+    ui: &mut egui::Ui,
+    image_type: &ImageType,
+    preferences: &mut ImagePreferences,
+) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Styles: ");
+        for style in available_styles(image_type) {
+            toggle_value(ui, &mut preferences.styles, *style, style.name());
+        }
+    });
+    let dimensions = available_dimensions(image_type);
+    if !dimensions.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Dimensions: ");
+            for dimension in dimensions {
+                toggle_value(
+                    ui,
+                    &mut preferences.dimensions,
+                    dimension.to_string(),
+                    dimension,
+                );
+            }
+        });
+    }
+    ui.horizontal_wrapped(|ui| {
+        ui.label("File types: ");
+        for mime in available_mime_types(image_type) {
+            toggle_value(ui, &mut preferences.mime_types, *mime, mime.name());
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Humor: ");
+        content_filter_combo(ui, image_type, "humor", &mut preferences.humor);
+        ui.label("Epilepsy warning: ");
+        content_filter_combo(ui, image_type, "epilepsy", &mut preferences.epilepsy);
+    });
+    ui.horizontal(|ui| {
+        let mut use_min_score = preferences.min_score.is_some();
+        if ui.checkbox(&mut use_min_score, "Minimum score").changed() {
+            preferences.min_score = use_min_score.then_some(0);
+        }
+        if let Some(min_score) = preferences.min_score.as_mut() {
+            ui.add(egui::DragValue::new(min_score));
+        }
+    })
+    .response
+    .on_hover_text("Skip images with fewer votes on SteamGridDB than this");
+}
+
+fn toggle_value<T: PartialEq>(ui: &mut egui::Ui, values: &mut Vec<T>, value: T, label: &str) {
+    let mut selected = values.contains(&value);
+    if ui.checkbox(&mut selected, label).changed() {
+        if selected {
+            values.push(value);
+        } else {
+            values.retain(|v| *v != value);
+        }
+    }
+}
+
+fn content_filter_combo(
+    ui: &mut egui::Ui,
+    image_type: &ImageType,
+    name: &str,
+    filter: &mut ContentFilter,
+) {
+    egui::ComboBox::from_id_source(format!("{}-{}", image_type.name(), name))
+        .selected_text(filter.name())
+        .show_ui(ui, |ui| {
+            for option in ContentFilter::ALL {
+                ui.selectable_value(filter, option, option.name());
+            }
+        });
+}
+
+/// Lets the user pick a controller layout, or none with the given text. Returns true if it changed.
+pub(super) fn render_controller_template_combo(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    template: &mut Option<ControllerTemplate>,
+    none_text: &str,
+) -> bool {
+    let before = *template;
+    egui::ComboBox::from_id_source(("controller_template", id))
+        .selected_text(
+            template
+                .map(|t| t.name().to_string())
+                .unwrap_or_else(|| none_text.to_string()),
+        )
+        .show_ui(ui, |ui| {
+            ui.selectable_value(template, None, none_text);
+            for option in ControllerTemplate::ALL {
+                ui.selectable_value(template, Some(option), option.name());
+            }
+        });
+    before != *template
+}
+
+fn load_steam_collections(
+    settings: &SteamSettings,
+) -> Result<Vec<(String, Vec<Collection>)>, String> {
+    let users = get_shortcuts_paths(settings).map_err(|e| e.to_string())?;
+    users
+        .into_iter()
+        .map(|user| {
+            let collections =
+                read_collections(settings, &user.user_id).map_err(|e| e.to_string())?;
+            Ok((user.user_id, collections))
+        })
+        .collect()
+}
@@ -11,7 +11,9 @@ use crate::{
     config::get_renames_file,
     platforms::{get_platforms, GamesPlatform, Platforms, ShortcutToImport},
     settings::{save_settings, Settings},
-    sync::{self, SyncProgress},
+    steam::Collection,
+    steamgriddb::{MockData, MockServer},
+    sync::{self, SyncCancel, SyncProgress},
 };
 
 use super::{
@@ -21,7 +23,7 @@ use super::{
     },
     ui_images::{get_import_image, get_logo, get_logo_icon, get_save_image},
     ui_import_games::FetcStatus,
-    BackupState, DiconnectState, ImageSelectState,
+    BackupState, CacheState, DiconnectState, GameDetailsState, ImageSelectState, ImportListState,
 };
 
 const SECTION_SPACING: f32 = 25.0;
@@ -61,12 +63,19 @@ pub struct MyEguiApp {
     ui_images: UiImages,
     pub(crate) games_to_sync: GamesToSync,
     pub(crate) status_reciever: Receiver<SyncProgress>,
+    pub(crate) sync_cancel: SyncCancel,
     pub(crate) image_selected_state: ImageSelectState,
     pub(crate) backup_state: BackupState,
     pub(crate) disconect_state: DiconnectState,
+    pub(crate) cache_state: CacheState,
     pub(crate) rename_map: HashMap<u32, String>,
     pub(crate) current_edit: Option<u32>,
+    pub(crate) game_details: GameDetailsState,
+    pub(crate) import_list: ImportListState,
     pub(crate) platforms: Platforms,
+    /// Asks to stop Steam before importing, because it locks the collections
+    pub(crate) steam_running_prompt: bool,
+    pub(crate) steam_collections: Option<Result<Vec<(String, Vec<Collection>)>, String>>,
 }
 
 impl MyEguiApp {
@@ -82,12 +91,18 @@ impl MyEguiApp {
             games_to_sync,
             ui_images: UiImages::default(),
             status_reciever: watch::channel(SyncProgress::NotStarted).1,
+            sync_cancel: SyncCancel::default(),
             image_selected_state: ImageSelectState::default(),
             backup_state: BackupState::default(),
             disconect_state: DiconnectState::default(),
+            cache_state: CacheState::default(),
             rename_map: get_rename_map(),
             current_edit: Option::None,
+            game_details: GameDetailsState::default(),
+            import_list: ImportListState::default(),
             platforms,
+            steam_running_prompt: false,
+            steam_collections: None,
         }
     }
 }
@@ -108,6 +123,7 @@ enum Menues {
     Import,
     Settings,
     Images,
+    Cache,
     Backup,
     Disconnect,
 }
@@ -159,6 +175,10 @@ impl App for MyEguiApp {
                         || ui
                             .selectable_value(&mut self.selected_menu, Menues::Images, "Images")
                             .changed();
+                    changed = changed
+                        || ui
+                            .selectable_value(&mut self.selected_menu, Menues::Cache, "Cache")
+                            .changed();
                 }
                 changed = changed
                     || ui
@@ -177,6 +197,7 @@ impl App for MyEguiApp {
 
                 if changed {
                     self.backup_state.available_backups = None;
+                    self.cache_state.entries = None;
                 }
                 if changed
                     && menu_before == Menues::Settings
@@ -204,17 +225,9 @@ impl App for MyEguiApp {
             egui::TopBottomPanel::new(egui::panel::TopBottomSide::Bottom, "Bottom Panel")
                 .frame(frame)
                 .show(ctx, |ui| {
-                    let (status_string, syncing) = match &*self.status_reciever.borrow() {
-                        SyncProgress::NotStarted => ("".to_string(), false),
-                        SyncProgress::Starting => ("Starting Import".to_string(), true),
-                        SyncProgress::FoundGames { games_found } => {
-                            (format!("Found {} games to  import", games_found), true)
-                        }
-                        SyncProgress::FindingImages => ("Searching for images".to_string(), true),
-                        SyncProgress::DownloadingImages { to_download } => {
-                            (format!("Downloading {} images ", to_download), true)
-                        }
-                        SyncProgress::Done => ("Done importing games".to_string(), false),
+                    let (status_string, syncing) = {
+                        let progress = self.status_reciever.borrow();
+                        (progress.description(), progress.is_running())
                     };
                     if syncing {
                         ui.ctx().request_repaint();
@@ -224,11 +237,26 @@ impl App for MyEguiApp {
                             ui.horizontal(|c| {
                                 c.spinner();
                                 c.label(&status_string);
+                                let cancelling = self.sync_cancel.is_cancelled();
+                                if !cancelling
+                                    && c.button("Cancel")
+                                        .on_hover_text("Stop the import after the current step")
+                                        .clicked()
+                                {
+                                    self.sync_cancel.cancel();
+                                }
+                                if cancelling {
+                                    c.label("Cancelling...");
+                                }
                             });
                         } else {
                             ui.label(&status_string);
                         }
                     }
+                    if self.steam_running_prompt {
+                        self.render_steam_running_prompt(ui);
+                        return;
+                    }
                     let all_ready = all_ready(&self.games_to_sync);
 
                     let texture = self.get_import_image(ui);
@@ -242,7 +270,11 @@ impl App for MyEguiApp {
                         && !syncing
                     {
                         save_settings(&self.settings, &self.platforms);
-                        self.run_sync(false);
+                        if self.collections_need_steam_stopped() {
+                            self.steam_running_prompt = true;
+                        } else {
+                            self.run_sync(false);
+                        }
                     }
                 });
         }
@@ -258,6 +290,9 @@ impl App for MyEguiApp {
                 Menues::Images => {
                     self.render_ui_images(ui);
                 }
+                Menues::Cache => {
+                    self.render_cache(ui);
+                }
                 Menues::Backup => {
                     self.render_backup(ui);
                 }
@@ -337,8 +372,33 @@ fn setup(ctx: &egui::Context) {
     create_style(&mut style);
     ctx.set_style(style);
 }
-pub fn run_sync() {
+/// Points SteamGridDB and Steam CDN requests at another server,
+/// `--sgdb-base-url mock` starts the bundled mock server and uses that
+fn apply_sgdb_base_url(app: &mut MyEguiApp, args: &[String]) {
+    let base_url = args
+        .iter()
+        .position(|a| a == "--sgdb-base-url")
+        .and_then(|index| args.get(index + 1));
+    if let Some(base_url) = base_url {
+        let base_url = if base_url == "mock" {
+            match app.rt.block_on(MockServer::start(MockData::default())) {
+                Ok(server) => server.base_url,
+                Err(e) => {
+                    eprintln!("Could not start mock SteamGridDB server: {e}");
+                    return;
+                }
+            }
+        } else {
+            base_url.to_string()
+        };
+        println!("Using {} for SteamGridDB and Steam images", base_url);
+        app.settings.steamgrid_db.url_override = Some(base_url);
+    }
+}
+
+pub fn run_sync(args: Vec<String>) {
     let mut app = MyEguiApp::new();
+    apply_sgdb_base_url(&mut app, &args);
     while !all_ready(&app.games_to_sync) {
         println!("Finding games, trying again in 500ms");
         std::thread::sleep(Duration::from_secs_f32(0.5));
@@ -347,7 +407,8 @@ pub fn run_sync() {
 }
 
 pub fn run_ui(args: Vec<String>) {
-    let app = MyEguiApp::new();
+    let mut app = MyEguiApp::new();
+    apply_sgdb_base_url(&mut app, &args);
     let no_v_sync = args.contains(&"--no-vsync".to_string());
     let native_options = eframe::NativeOptions {
         initial_window_size: Some(egui::Vec2 { x: 1280., y: 800. }),